@@ -11,10 +11,13 @@ use std::cmp::Ordering;
 use std::marker::PhantomData;
 use std::rc::Rc;
 
-use opendp::core::{Domain, Function, Measure, Measurement, Metric, PrivacyRelation, StabilityRelation, Transformation};
+use num::Float;
+
+use opendp::core::{BasicCompositionMeasure, Domain, Function, Measure, Measurement, Metric, PrivacyRelation, StabilityRelation, Transformation};
+use opendp::dist::MaxDivergence;
 use opendp::err;
 use opendp::error::*;
-use opendp::traits::{FallibleSub, MeasureDistance, MetricDistance};
+use opendp::traits::{Bisect, FallibleSub, MeasureDistance, MetricDistance};
 
 use crate::glue::Glue;
 use crate::util::Type;
@@ -311,6 +314,28 @@ impl Measure for AnyMeasure {
     type Distance = AnyMeasureDistance;
 }
 
+// only scalar (epsilon) budgets are currently split over FFI
+impl BasicCompositionMeasure for AnyMeasure {
+    fn split(
+        &self, d_out: &Self::Distance,
+        predicate0: &dyn Fn(&Self::Distance) -> Fallible<bool>,
+        predicate1: &dyn Fn(&Self::Distance) -> Fallible<bool>,
+    ) -> Fallible<Option<(Self::Distance, Self::Distance)>> {
+        fn monomorphize<Q: 'static + Float + Bisect + MeasureDistance>(
+            d_out: &AnyMeasureDistance,
+            predicate0: &dyn Fn(&AnyMeasureDistance) -> Fallible<bool>,
+            predicate1: &dyn Fn(&AnyMeasureDistance) -> Fallible<bool>,
+        ) -> Fallible<Option<(AnyMeasureDistance, AnyMeasureDistance)>> {
+            let split = MaxDivergence::<Q>::default().split(
+                d_out.downcast_ref::<Q>()?,
+                &|d_out0: &Q| predicate0(&AnyMeasureDistance::new(*d_out0)),
+                &|d_out1: &Q| predicate1(&AnyMeasureDistance::new(*d_out1)))?;
+            Ok(split.map(|(d_out0, d_out1)| (AnyMeasureDistance::new(d_out0), AnyMeasureDistance::new(d_out1))))
+        }
+        dispatch!(monomorphize, [(self.distance_type, [f32, f64])], (d_out, predicate0, predicate1))
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct AnyMetric {
     pub metric: AnyBoxClonePartialEq,
@@ -454,14 +479,17 @@ impl<DI: 'static + Domain, DO: 'static + Domain, MI: 'static + Metric, MO: 'stat
           MI::Distance: 'static + Clone + PartialOrd,
           MO::Distance: 'static + Clone + PartialOrd {
     fn into_any(self) -> AnyMeasurement {
-        AnyMeasurement::new(
-            AnyDomain::new(self.input_domain),
-            AnyDomain::new(self.output_domain),
-            self.function.into_any(),
-            AnyMetric::new(self.input_metric),
-            AnyMeasure::new(self.output_measure),
-            self.privacy_relation.into_any(),
-        )
+        AnyMeasurement {
+            accuracy: self.accuracy,
+            ..AnyMeasurement::new(
+                AnyDomain::new(self.input_domain),
+                AnyDomain::new(self.output_domain),
+                self.function.into_any(),
+                AnyMetric::new(self.input_metric),
+                AnyMeasure::new(self.output_measure),
+                self.privacy_relation.into_any(),
+            )
+        }
     }
 }
 
@@ -475,14 +503,17 @@ impl<DO: 'static + Domain, MO: 'static + Measure> IntoAnyMeasurementOutExt for M
     where DO::Carrier: 'static,
           MO::Distance: 'static + Clone + PartialOrd {
     fn into_any_out(self) -> AnyMeasurement {
-        AnyMeasurement::new(
-            AnyDomain::new(self.input_domain),
-            AnyDomain::new(self.output_domain),
-            self.function.into_any_out(),
-            AnyMetric::new(self.input_metric),
-            AnyMeasure::new(self.output_measure),
-            self.privacy_relation.into_any(),
-        )
+        AnyMeasurement {
+            accuracy: self.accuracy,
+            ..AnyMeasurement::new(
+                AnyDomain::new(self.input_domain),
+                AnyDomain::new(self.output_domain),
+                self.function.into_any_out(),
+                AnyMetric::new(self.input_metric),
+                AnyMeasure::new(self.output_measure),
+                self.privacy_relation.into_any(),
+            )
+        }
     }
 }
 
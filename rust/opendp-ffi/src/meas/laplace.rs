@@ -43,21 +43,22 @@ mod tests {
 
     #[test]
     fn test_make_base_laplace() -> Fallible<()> {
-        let measurement = Result::from(opendp_meas__make_base_laplace(util::into_raw(0.0) as *const c_void, "AllDomain<f64>".to_char_p()))?;
+        let measurement = Result::from(opendp_meas__make_base_laplace(util::into_raw(1e-10) as *const c_void, "AllDomain<f64>".to_char_p()))?;
         let arg = AnyObject::new_raw(1.0);
         let res = core::opendp_core__measurement_invoke(&measurement, arg);
         let res: f64 = Fallible::from(res)?.downcast()?;
-        assert_eq!(res, 1.0);
+        assert!((res - 1.0).abs() < 1e-6);
         Ok(())
     }
 
     #[test]
     fn test_make_base_laplace_vec() -> Fallible<()> {
-        let measurement = Result::from(opendp_meas__make_base_laplace(util::into_raw(0.0) as *const c_void, "VectorDomain<AllDomain<f64>>".to_char_p()))?;
+        let measurement = Result::from(opendp_meas__make_base_laplace(util::into_raw(1e-10) as *const c_void, "VectorDomain<AllDomain<f64>>".to_char_p()))?;
         let arg = AnyObject::new_raw(vec![1.0, 2.0, 3.0]);
         let res = core::opendp_core__measurement_invoke(&measurement, arg);
         let res: Vec<f64> = Fallible::from(res)?.downcast()?;
-        assert_eq!(res, vec![1.0, 2.0, 3.0]);
+        assert_eq!(res.len(), 3);
+        assert!(res.iter().zip([1.0, 2.0, 3.0]).all(|(r, v)| (r - v).abs() < 1e-6));
         Ok(())
     }
 }
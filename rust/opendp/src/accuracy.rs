@@ -0,0 +1,79 @@
+//! Confidence intervals for releases of additive noise mechanisms.
+//!
+//! These are attached to the measurements constructed by [`make_base_laplace`](crate::meas::make_base_laplace)
+//! and [`make_base_gaussian`](crate::meas::make_base_gaussian), with the scale the measurement was constructed with,
+//! and are evaluated through [`Measurement::confidence_interval`](crate::core::Measurement::confidence_interval).
+
+use statrs::function::erf;
+
+use crate::error::*;
+
+fn check_parameters(scale: f64, alpha: f64) -> Fallible<()> {
+    if scale.is_nan() || scale.is_sign_negative() {
+        return fallible!(FailedFunction, "scale must be non-negative")
+    }
+    if !(alpha > 0. && alpha < 1.) {
+        return fallible!(FailedFunction, "alpha must be within (0, 1)")
+    }
+    Ok(())
+}
+
+/// The symmetric `(1 - alpha)`-confidence interval around a value `released` with Laplace noise of `scale`.
+///
+/// Laplace noise `X` satisfies `P(|X| > t) = exp(-t / scale)`, so the half-width is `scale * ln(1 / alpha)`.
+pub(crate) fn laplace_confidence_interval(scale: f64, released: f64, alpha: f64) -> Fallible<(f64, f64)> {
+    check_parameters(scale, alpha)?;
+    let half_width = scale * alpha.recip().ln();
+    Ok((released - half_width, released + half_width))
+}
+
+/// The symmetric `(1 - alpha)`-confidence interval around a value `released` with Gaussian noise of `scale`.
+///
+/// Gaussian noise `X` satisfies `P(|X| > t) = erfc(t / (scale * sqrt(2)))`, so the half-width is `scale * sqrt(2) * erfc_inv(alpha)`.
+pub(crate) fn gaussian_confidence_interval(scale: f64, released: f64, alpha: f64) -> Fallible<(f64, f64)> {
+    check_parameters(scale, alpha)?;
+    let half_width = scale * std::f64::consts::SQRT_2 * erf::erfc_inv(alpha);
+    Ok((released - half_width, released + half_width))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::chain::make_chain_mt;
+    use crate::dom::AllDomain;
+    use crate::meas::{make_base_gaussian, make_base_geometric, make_base_laplace};
+    use crate::trans::make_bounded_sum;
+
+    use super::*;
+
+    #[test]
+    fn test_laplace_confidence_interval() -> Fallible<()> {
+        let measurement = make_base_laplace::<AllDomain<f64>>(2.)?;
+        let (lower, upper) = measurement.confidence_interval(10., 0.05)?;
+        // the 0.975 quantile of Laplace(0, 2) is 2 * ln(20)
+        assert!((upper - 10. - 2. * 20_f64.ln()).abs() < 1e-12);
+        assert!((upper - lower - 4. * 20_f64.ln()).abs() < 1e-12);
+        assert!(measurement.confidence_interval(10., 1.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gaussian_confidence_interval() -> Fallible<()> {
+        let (lower, upper) = make_base_gaussian::<AllDomain<f64>>(1.)?.confidence_interval(0., 0.05)?;
+        // the 0.975 quantile of the standard normal
+        assert!((upper - 1.959964).abs() < 1e-6);
+        assert!((lower + 1.959964).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chained_confidence_interval() -> Fallible<()> {
+        let measurement = make_chain_mt(&make_base_laplace(2.)?, &make_bounded_sum(0., 10.)?, None)?;
+        assert_eq!(measurement.confidence_interval(10., 0.05)?, laplace_confidence_interval(2., 10., 0.05)?);
+
+        // the noise distribution of other measurements is unknown
+        let measurement = make_base_geometric::<AllDomain<i32>, f64>(1., None)?;
+        assert!(measurement.confidence_interval(1., 0.05).is_err());
+        Ok(())
+    }
+}
@@ -1,8 +1,14 @@
 use std::ops::Shr;
+use std::rc::Rc;
 
-use crate::core::{Domain, Function, HintMt, HintTt, Measure, Measurement, Metric, PrivacyRelation, StabilityRelation, Transformation};
-use crate::dom::PairDomain;
-use crate::error::Fallible;
+use num::Float;
+
+use crate::core::{BasicCompositionMeasure, Domain, Function, HintMt, HintTt, Measure, Measurement, Metric, PrivacyRelation, StabilityRelation, Transformation};
+use crate::dist::{HammingDistance, MaxDivergence, SmoothedMaxDivergence, SymmetricDistance};
+use crate::dom::{Lazy, LazyDomain, PairDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::shuffle;
+use crate::trans::{make_sample_bernoulli, make_sample_without_replacement};
 
 pub fn make_chain_mt<DI, DX, DO, MI, MX, MO>(
     measurement1: &Measurement<DX, DO, MX, MO>,
@@ -21,14 +27,18 @@ pub fn make_chain_mt<DI, DX, DO, MI, MX, MO>(
         return fallible!(MetricMismatch, "Intermediate metric mismatch");
     }
 
-    Ok(Measurement::new(
-        transformation0.input_domain.clone(),
-        measurement1.output_domain.clone(),
-        Function::make_chain(&measurement1.function, &transformation0.function),
-        transformation0.input_metric.clone(),
-        measurement1.output_measure.clone(),
-        PrivacyRelation::make_chain(&measurement1.privacy_relation,&transformation0.stability_relation, hint)
-    ))
+    // the noise is added by the measurement, so the chain has the same accuracy
+    Ok(Measurement {
+        accuracy: measurement1.accuracy.clone(),
+        ..Measurement::new(
+            transformation0.input_domain.clone(),
+            measurement1.output_domain.clone(),
+            Function::make_chain(&measurement1.function, &transformation0.function),
+            transformation0.input_metric.clone(),
+            measurement1.output_measure.clone(),
+            PrivacyRelation::make_chain(&measurement1.privacy_relation,&transformation0.stability_relation, hint)?
+        )
+    })
 }
 
 pub fn make_chain_tt<DI, DX, DO, MI, MX, MO>(
@@ -54,7 +64,7 @@ pub fn make_chain_tt<DI, DX, DO, MI, MX, MO>(
         Function::make_chain(&transformation1.function, &transformation0.function),
         transformation0.input_metric.clone(),
         transformation1.output_metric.clone(),
-        StabilityRelation::make_chain(&transformation1.stability_relation,&transformation0.stability_relation, hint)
+        StabilityRelation::make_chain(&transformation1.stability_relation,&transformation0.stability_relation, hint)?
     ))
 }
 
@@ -63,7 +73,7 @@ pub fn make_basic_composition<DI, DO0, DO1, MI, MO>(measurement0: &Measurement<D
           DO0: 'static + Domain,
           DO1: 'static + Domain,
           MI: 'static + Metric,
-          MO: 'static + Measure {
+          MO: 'static + BasicCompositionMeasure {
     if measurement0.input_domain != measurement1.input_domain {
         return fallible!(DomainMismatch, "Input domain mismatch");
     } else if measurement0.input_metric != measurement1.input_metric {
@@ -72,25 +82,304 @@ pub fn make_basic_composition<DI, DO0, DO1, MI, MO>(measurement0: &Measurement<D
         return fallible!(MeasureMismatch, "Output measure mismatch");
     }
 
+    let output_measure = measurement0.output_measure.clone();
+    let relation0 = measurement0.privacy_relation.relation.clone();
+    let relation1 = measurement1.privacy_relation.relation.clone();
+
     Ok(Measurement::new(
         measurement0.input_domain.clone(),
         PairDomain::new(measurement0.output_domain.clone(), measurement1.output_domain.clone()),
         Function::make_basic_composition(&measurement0.function, &measurement1.function),
         measurement0.input_metric.clone(),
-        measurement0.output_measure.clone(),
-        // TODO: PrivacyRelation for make_composition
-        PrivacyRelation::new(|_i, _o| false),
+        output_measure.clone(),
+        PrivacyRelation::new_fallible(move |d_in: &MI::Distance, d_out: &MO::Distance| {
+            let split = output_measure.split(
+                d_out,
+                &|d_out0| relation0(d_in, d_out0),
+                &|d_out1| relation1(d_in, d_out1))?;
+            Ok(split.is_some())
+        }),
     ))
 }
 
+/// Split a budget `d_out` into shares proportional to `weights`.
+pub fn proportional_split<Q: Float>(weights: &[Q], d_out: Q) -> Fallible<Vec<Q>> {
+    if weights.iter().any(|w| !w.is_finite() || w.is_sign_negative()) {
+        return fallible!(InvalidDistance, "weights must be non-negative and finite")
+    }
+    let total = weights.iter().fold(Q::zero(), |acc, w| acc + *w);
+    if total.is_zero() { return fallible!(InvalidDistance, "weights must not sum to zero") }
+    Ok(weights.iter().map(|w| *w / total * d_out).collect())
+}
+
+/// Compose a vector of measurements, where each measurement is allotted a share of the budget proportional to its weight.
+/// The composition is `d_out`-private if every measurement is private at its share of `d_out`.
+pub fn make_proportional_composition<DI, DO, MI, Q>(
+    measurements: Vec<(Measurement<DI, DO, MI, MaxDivergence<Q>>, Q)>
+) -> Fallible<Measurement<DI, VectorDomain<DO>, MI, MaxDivergence<Q>>>
+    where DI: 'static + Domain,
+          DO: 'static + Domain,
+          MI: 'static + Metric,
+          Q: 'static + Float {
+    let (input_domain, output_domain, input_metric) = match measurements.first() {
+        Some((measurement, _)) => (
+            measurement.input_domain.clone(),
+            measurement.output_domain.clone(),
+            measurement.input_metric.clone()),
+        None => return fallible!(MakeMeasurement, "must compose at least one measurement")
+    };
+    if measurements.iter().any(|(m, _)| m.input_domain != input_domain) {
+        return fallible!(DomainMismatch, "Input domain mismatch");
+    } else if measurements.iter().any(|(m, _)| m.output_domain != output_domain) {
+        return fallible!(DomainMismatch, "Output domain mismatch");
+    } else if measurements.iter().any(|(m, _)| m.input_metric != input_metric) {
+        return fallible!(MetricMismatch, "Input metric mismatch");
+    }
+
+    let weights = measurements.iter().map(|(_, w)| *w).collect::<Vec<Q>>();
+    // fail at construction rather than when the relation is evaluated
+    proportional_split(&weights, Q::one())?;
+
+    let (functions, relations): (Vec<_>, Vec<_>) = measurements.into_iter()
+        .map(|(m, _)| (m.function, m.privacy_relation)).unzip();
+
+    Ok(Measurement::new(
+        input_domain,
+        VectorDomain::new(output_domain),
+        Function::new_fallible(move |arg: &DI::Carrier|
+            functions.iter().map(|f| f.eval(arg)).collect()),
+        input_metric,
+        MaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &MI::Distance, d_out: &Q| {
+            for (relation, share) in relations.iter().zip(proportional_split(&weights, *d_out)?) {
+                if !relation.eval(d_in, &share)? { return Ok(false) }
+            }
+            Ok(true)
+        })))
+}
+
+/// Compose a vector of measurements, where each measurement is only evaluated when its output is accessed.
+/// Outputs are memoized, so accessing an output repeatedly does not release fresh noise.
+///
+/// The budget is split evenly among all measurements, and the full budget is spent regardless of how many outputs are read:
+/// the caller may inspect any output, so the privacy analysis cannot depend on which outputs are inspected.
+pub fn make_lazy_composition<DI, DO, MI, Q>(
+    measurements: Vec<Measurement<DI, DO, MI, MaxDivergence<Q>>>
+) -> Fallible<Measurement<DI, VectorDomain<LazyDomain<DO>>, MI, MaxDivergence<Q>>>
+    where DI: 'static + Domain,
+          DI::Carrier: Clone,
+          DO: 'static + Domain,
+          DO::Carrier: Clone,
+          MI: 'static + Metric,
+          Q: 'static + Float {
+    let (input_domain, output_domain, input_metric) = match measurements.first() {
+        Some(measurement) => (
+            measurement.input_domain.clone(),
+            measurement.output_domain.clone(),
+            measurement.input_metric.clone()),
+        None => return fallible!(MakeMeasurement, "must compose at least one measurement")
+    };
+    if measurements.iter().any(|m| m.input_domain != input_domain) {
+        return fallible!(DomainMismatch, "Input domain mismatch");
+    } else if measurements.iter().any(|m| m.output_domain != output_domain) {
+        return fallible!(DomainMismatch, "Output domain mismatch");
+    } else if measurements.iter().any(|m| m.input_metric != input_metric) {
+        return fallible!(MetricMismatch, "Input metric mismatch");
+    }
+
+    let weights = vec![Q::one(); measurements.len()];
+    let (functions, relations): (Vec<_>, Vec<_>) = measurements.into_iter()
+        .map(|m| (m.function, m.privacy_relation)).unzip();
+
+    Ok(Measurement::new(
+        input_domain,
+        VectorDomain::new(LazyDomain::new(output_domain)),
+        Function::new(move |arg: &DI::Carrier| {
+            let arg = Rc::new(arg.clone());
+            functions.iter().map(|function| {
+                let (function, arg) = (function.clone(), arg.clone());
+                Lazy::new(move || function.eval(&arg))
+            }).collect()
+        }),
+        input_metric,
+        MaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &MI::Distance, d_out: &Q| {
+            for (relation, share) in relations.iter().zip(proportional_split(&weights, *d_out)?) {
+                if !relation.eval(d_in, &share)? { return Ok(false) }
+            }
+            Ok(true)
+        })))
+}
+
+/// Compose two measurements additively, where the budget claimed for `measurement0` may not exceed the budget claimed for `measurement1`.
+/// The composition is `d_out`-private if both measurements are private at their claimed budgets, and `d_out` covers the sum of the claims.
+pub fn make_ordered_composition<DI, DO0, DO1, MI, Q>(
+    measurement0: &Measurement<DI, DO0, MI, MaxDivergence<Q>>, d_out0: Q,
+    measurement1: &Measurement<DI, DO1, MI, MaxDivergence<Q>>, d_out1: Q,
+) -> Fallible<Measurement<DI, PairDomain<DO0, DO1>, MI, MaxDivergence<Q>>>
+    where DI: 'static + Domain,
+          DO0: 'static + Domain,
+          DO1: 'static + Domain,
+          MI: 'static + Metric,
+          Q: 'static + Float {
+    if measurement0.input_domain != measurement1.input_domain {
+        return fallible!(DomainMismatch, "Input domain mismatch");
+    } else if measurement0.input_metric != measurement1.input_metric {
+        return fallible!(MetricMismatch, "Input metric mismatch");
+    }
+    if [d_out0, d_out1].iter().any(|d_out| d_out.is_nan() || d_out.is_sign_negative()) {
+        return fallible!(MakeMeasurement, "budgets must be non-negative")
+    }
+    if d_out0 > d_out1 {
+        return fallible!(MakeMeasurement, "the budget of the first measurement may not exceed the budget of the second")
+    }
+
+    let relation0 = measurement0.privacy_relation.relation.clone();
+    let relation1 = measurement1.privacy_relation.relation.clone();
+    Ok(Measurement::new(
+        measurement0.input_domain.clone(),
+        PairDomain::new(measurement0.output_domain.clone(), measurement1.output_domain.clone()),
+        Function::make_basic_composition(&measurement0.function, &measurement1.function),
+        measurement0.input_metric.clone(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &MI::Distance, d_out: &Q|
+            Ok(*d_out >= d_out0 + d_out1 && relation0(d_in, &d_out0)? && relation1(d_in, &d_out1)?))))
+}
+
+/// The epsilon of the shuffled reports of `n` users of a `local_epsilon`-DP local randomizer, at `delta`,
+/// from Feldman, McMillan and Talwar (2021), Theorem 3.1.
+/// Returns None when the bound does not apply, which is when `local_epsilon > ln(n / (16 ln(2 / delta)))`.
+pub fn shuffle_amplification_epsilon(local_epsilon: f64, n: usize, delta: f64) -> Option<f64> {
+    if !(delta > 0. && delta < 1.) || local_epsilon.is_nan() { return None }
+    let n = n as f64;
+    if local_epsilon > (n / (16. * (2. / delta).ln())).ln() { return None }
+    let e0 = local_epsilon.exp();
+    Some((1. + (e0 - 1.) / (e0 + 1.) * (8. * (e0 * (4. / delta).ln()).sqrt() / n.sqrt() + 8. * e0 / n)).ln())
+}
+
+/// Apply a `local_epsilon`-DP local randomizer to each of `n` records, and release the reports in a uniformly random order.
+///
+/// When `trusted_shuffler` is true, the reports are assumed to reach the analyst only through a trusted shuffler,
+/// and the central guarantee includes amplification by shuffling (see [`shuffle_amplification_epsilon`]).
+/// Changing `d_in > 1` records is handled by group privacy, with the budget split evenly over the changed records.
+/// Otherwise, or when the amplification bound does not apply, the guarantee is the local one: `(d_in * local_epsilon, 0)`.
+/// `local_epsilon` must satisfy the privacy relation of `local` at a distance of 1.
+pub fn make_shuffle<DI, DO, MI>(
+    local: &Measurement<DI, DO, MI, MaxDivergence<f64>>, local_epsilon: f64, n: usize, trusted_shuffler: bool
+) -> Fallible<Measurement<SizedDomain<VectorDomain<DI>>, VectorDomain<DO>, HammingDistance, SmoothedMaxDivergence<f64>>>
+    where DI: 'static + Domain,
+          DO: 'static + Domain,
+          MI: 'static + Metric<Distance=u32> {
+    if n == 0 { return fallible!(MakeMeasurement, "n must be positive") }
+    if local_epsilon.is_nan() || local_epsilon.is_sign_negative() {
+        return fallible!(MakeMeasurement, "local_epsilon must be non-negative")
+    }
+    if !local.privacy_relation.eval(&1, &local_epsilon)? {
+        return fallible!(MakeMeasurement, "the local randomizer does not satisfy local_epsilon-DP")
+    }
+    let function = local.function.function.clone();
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new(local.input_domain.clone()), n),
+        VectorDomain::new(local.output_domain.clone()),
+        Function::new_fallible(move |arg: &Vec<DI::Carrier>| {
+            if arg.len() != n { return fallible!(FailedFunction, "expected {} records", n) }
+            let mut reports = arg.iter().map(|v| function(v)).collect::<Fallible<Vec<DO::Carrier>>>()?;
+            shuffle(&mut reports)?;
+            Ok(reports)
+        }),
+        HammingDistance,
+        SmoothedMaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &u32, &(eps, del): &(f64, f64)| {
+            if eps.is_nan() || eps.is_sign_negative() { return fallible!(InvalidDistance, "epsilon must be non-negative") }
+            if del.is_nan() || del.is_sign_negative() { return fallible!(InvalidDistance, "delta must be non-negative") }
+            if *d_in == 0 { return Ok(true) }
+            let k = *d_in as f64;
+            if eps >= k * local_epsilon { return Ok(true) }
+            if !trusted_shuffler { return Ok(false) }
+            // group privacy: (eps_1, del_1) for one record gives (k eps_1, k exp((k - 1) eps_1) del_1) for k records
+            let del_1 = del / (k * ((k - 1.) * eps / k).exp());
+            Ok(shuffle_amplification_epsilon(local_epsilon, n, del_1)
+                .map(|eps_1| k * eps_1 <= eps)
+                .unwrap_or(false))
+        })))
+}
+
+/// Keep each record independently with probability `prob`, and then apply `measurement` to the sample.
+///
+/// If `measurement` is `eps`-DP when one record is added or removed, then the composition is
+/// `ln(1 + prob * (exp(eps) - 1))`-DP, which is privacy amplification by Poisson subsampling.
+/// Changing `d_in > 1` records is handled by group privacy, with the budget split evenly over the changed records.
+pub fn make_bernoulli_amplification<DA, DO>(
+    measurement: &Measurement<VectorDomain<DA>, DO, SymmetricDistance, MaxDivergence<f64>>, prob: f64
+) -> Fallible<Measurement<VectorDomain<DA>, DO, SymmetricDistance, MaxDivergence<f64>>>
+    where DA: 'static + Domain,
+          DA::Carrier: 'static + Clone,
+          DO: 'static + Domain {
+    let sample = make_sample_bernoulli(measurement.input_domain.element_domain.clone(), prob)?.function.function;
+    let function = measurement.function.function.clone();
+    let relation = measurement.privacy_relation.relation.clone();
+
+    Ok(Measurement::new(
+        measurement.input_domain.clone(),
+        measurement.output_domain.clone(),
+        Function::new_fallible(move |arg: &Vec<DA::Carrier>| function(&sample(arg)?)),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &u32, d_out: &f64| {
+            if d_out.is_nan() || d_out.is_sign_negative() { return fallible!(InvalidDistance, "epsilon must be non-negative") }
+            if *d_in == 0 { return Ok(true) }
+            // the largest epsilon of the measurement that amplifies to d_out / d_in
+            let epsilon = ((d_out / *d_in as f64).exp_m1() / prob).ln_1p();
+            relation(&1, &epsilon)
+        })))
+}
+
+/// Draw a uniformly random subset of size `m` from a dataset of `n` records, and then apply `measurement` to the subset,
+/// where `m` is the length of the input domain of `measurement`.
+///
+/// Neighboring sized datasets differ by replacing records. If `measurement` is `eps`-DP when one record is replaced
+/// (a symmetric distance of 2), then the composition is `ln(1 + m / n * (exp(eps) - 1))`-DP per replaced record,
+/// which is privacy amplification by sampling without replacement (Balle, Barthe and Gaboardi, 2018).
+/// Replacing more than one record is handled by group privacy.
+pub fn make_without_replacement_amplification<DA, DO>(
+    measurement: &Measurement<SizedDomain<VectorDomain<DA>>, DO, SymmetricDistance, MaxDivergence<f64>>, n: usize
+) -> Fallible<Measurement<SizedDomain<VectorDomain<DA>>, DO, SymmetricDistance, MaxDivergence<f64>>>
+    where DA: 'static + Domain,
+          DA::Carrier: 'static + Clone,
+          DO: 'static + Domain {
+    let m = measurement.input_domain.length;
+    let element_domain = measurement.input_domain.element_domain.element_domain.clone();
+    let sample = make_sample_without_replacement(element_domain.clone(), n, m)?.function.function;
+    let function = measurement.function.function.clone();
+    let relation = measurement.privacy_relation.relation.clone();
+    let ratio = m as f64 / n as f64;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new(element_domain), n),
+        measurement.output_domain.clone(),
+        Function::new_fallible(move |arg: &Vec<DA::Carrier>| function(&sample(arg)?)),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &u32, d_out: &f64| {
+            if d_out.is_nan() || d_out.is_sign_negative() { return fallible!(InvalidDistance, "epsilon must be non-negative") }
+            if *d_in == 0 { return Ok(true) }
+            let replacements = d_in.div_ceil(2) as f64;
+            // the largest epsilon of the measurement that amplifies to d_out / replacements
+            let epsilon = ((d_out / replacements).exp_m1() / ratio).ln_1p();
+            relation(&2, &epsilon)
+        })))
+}
+
 
 // UNIT TESTS
 #[cfg(test)]
 mod tests {
     use crate::core::*;
-    use crate::dist::{L1Distance, MaxDivergence};
+    use crate::dist::{L1Distance, MaxDivergence, SmoothedMaxDivergence};
     use crate::dom::AllDomain;
     use crate::error::ExplainUnwrap;
+    use crate::meas::{make_base_laplace, make_randomized_response_bool};
 
     use super::*;
 
@@ -116,6 +405,49 @@ mod tests {
         assert_eq!(ret, 101.0);
     }
 
+    #[test]
+    fn test_make_chain_mt_relation() -> Fallible<()> {
+        let transformation0 = Transformation::new(
+            AllDomain::<i32>::new(), AllDomain::<i32>::new(),
+            Function::new(|a: &i32| a * 2),
+            L1Distance::<i32>::default(), L1Distance::<i32>::default(),
+            StabilityRelation::new_from_constant(2));
+        let measurement1 = Measurement::new(
+            AllDomain::<i32>::new(), AllDomain::<f64>::new(),
+            Function::new(|a: &i32| *a as f64),
+            L1Distance::<i32>::default(), MaxDivergence::default(),
+            PrivacyRelation::new_from_constant(0.5));
+        let chain = make_chain_mt(&measurement1, &transformation0, None)?;
+        // d_in of 1 maps to a d_mid of 2, which costs an epsilon of 1
+        assert!(chain.privacy_relation.eval(&1, &1.)?);
+        assert!(!chain.privacy_relation.eval(&1, &0.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_chain_mt_no_maps() -> Fallible<()> {
+        let make_transformation = || Transformation::new(
+            AllDomain::<i32>::new(), AllDomain::<i32>::new(),
+            Function::new(|a: &i32| *a),
+            L1Distance::<i32>::default(), L1Distance::<i32>::default(),
+            StabilityRelation::new(|d_in: &i32, d_out: &i32| d_out >= d_in));
+        let measurement1 = Measurement::new(
+            AllDomain::<i32>::new(), AllDomain::<f64>::new(),
+            Function::new(|a: &i32| *a as f64),
+            L1Distance::<i32>::default(), MaxDivergence::default(),
+            PrivacyRelation::new(|d_in: &i32, d_out: &f64| *d_out >= *d_in as f64));
+        // the intermediate distance is found by bisection
+        let chain = make_chain_mt(&measurement1, &make_transformation(), None)?;
+        assert!(chain.privacy_relation.eval(&1, &1.)?);
+        assert!(!chain.privacy_relation.eval(&2, &1.)?);
+
+        let hint = HintMt::new(|d_in: &i32, _d_out: &f64| Ok(Box::new(*d_in)));
+        let chain = make_chain_mt(&measurement1, &make_transformation(), Some(&hint))?;
+        assert!(chain.privacy_relation.eval(&1, &1.)?);
+        assert!(!chain.privacy_relation.eval(&2, &1.)?);
+        Ok(())
+    }
+
     #[test]
     fn test_make_chain_tt() {
         let input_domain0 = AllDomain::<u8>::new();
@@ -138,6 +470,44 @@ mod tests {
         assert_eq!(ret, 101.0);
     }
 
+    #[test]
+    fn test_make_chain_tt_relation() -> Fallible<()> {
+        let make_scale = |c: i32| Transformation::new(
+            AllDomain::<i32>::new(), AllDomain::<i32>::new(),
+            Function::new(move |a: &i32| a * c),
+            L1Distance::<i32>::default(), L1Distance::<i32>::default(),
+            StabilityRelation::new_from_constant(c));
+        let chain = make_chain_tt(&make_scale(3), &make_scale(2), None)?;
+        // constants multiply: d_out must be at least 2 * 3 * d_in
+        assert!(chain.stability_relation.eval(&2, &12)?);
+        assert!(!chain.stability_relation.eval(&2, &11)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_chain_tt_bisect() -> Fallible<()> {
+        let make_relation_only = |c: f64| Transformation::new(
+            AllDomain::<f64>::new(), AllDomain::<f64>::new(),
+            Function::new(move |a: &f64| a * c),
+            L1Distance::<f64>::default(), L1Distance::<f64>::default(),
+            StabilityRelation::new(move |d_in: &f64, d_out: &f64| *d_out >= *d_in * c));
+        let transformation0 = make_relation_only(2.);
+        let transformation1 = make_relation_only(3.);
+        // without a hint, the same search is the fallback
+        let chain = make_chain_tt(&transformation1, &transformation0, None)?;
+        assert!(chain.stability_relation.eval(&1., &6.)?);
+        assert!(!chain.stability_relation.eval(&1., &5.99)?);
+
+        let hint = HintTt::new_bisect(&transformation0.stability_relation);
+        let chain = make_chain_tt(&transformation1, &transformation0, Some(&hint))?;
+        // a d_mid exists exactly when d_out >= 6 * d_in
+        assert!(chain.stability_relation.eval(&1., &6.)?);
+        assert!(!chain.stability_relation.eval(&1., &5.99)?);
+        assert!(chain.stability_relation.eval(&0.5, &3.)?);
+        assert!(chain.stability_relation.eval(&0., &0.)?);
+        Ok(())
+    }
+
     #[test]
     fn test_make_basic_composition() {
         let input_domain0 = AllDomain::<i32>::new();
@@ -159,6 +529,188 @@ mod tests {
         let ret = composition.function.eval(&arg).unwrap_test();
         assert_eq!(ret, (100_f32, 98_f64));
     }
+
+
+    #[test]
+    fn test_make_basic_composition_relation() -> Fallible<()> {
+        // each measurement is (eps / 2)-private, with eps = 1
+        let measurement0 = make_base_laplace::<AllDomain<f64>>(2.)?;
+        let measurement1 = make_base_laplace::<AllDomain<f64>>(2.)?;
+        assert!(measurement0.privacy_relation.eval(&1., &0.5)?);
+        let composition = make_basic_composition(&measurement0, &measurement1)?;
+        assert!(composition.privacy_relation.eval(&1., &1.)?);
+        assert!(!composition.privacy_relation.eval(&1., &0.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_basic_composition_asymmetric() -> Fallible<()> {
+        // the budget is split unevenly, as (0.2, 0.8)
+        let measurement0 = make_base_laplace::<AllDomain<f64>>(5.)?;
+        let measurement1 = make_base_laplace::<AllDomain<f64>>(1.25)?;
+        let composition = make_basic_composition(&measurement0, &measurement1)?;
+        assert!(composition.privacy_relation.eval(&1., &1.)?);
+        assert!(!composition.privacy_relation.eval(&1., &0.99)?);
+
+        // a pure measurement leaves all of delta to an approximate one
+        let make_measurement = |min_eps: f64, min_del: f64| Measurement::new(
+            AllDomain::<i32>::new(), AllDomain::<i32>::new(), Function::new(|arg: &i32| *arg),
+            L1Distance::<i32>::default(), SmoothedMaxDivergence::default(),
+            PrivacyRelation::new(move |_d_in: &i32, &(eps, del): &(f64, f64)| eps >= min_eps && del >= min_del));
+        let composition = make_basic_composition(&make_measurement(0.2, 0.), &make_measurement(0.8, 1e-6))?;
+        assert!(composition.privacy_relation.eval(&1, &(1., 1e-6))?);
+        assert!(!composition.privacy_relation.eval(&1, &(1., 1e-7))?);
+        assert!(!composition.privacy_relation.eval(&1, &(0.99, 1e-6))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_proportional_composition() -> Fallible<()> {
+        assert_eq!(proportional_split(&[1., 1., 2.], 4.)?, vec![1., 1., 2.]);
+
+        let measurements = vec![
+            (make_base_laplace::<AllDomain<f64>>(1.)?, 1.),
+            (make_base_laplace::<AllDomain<f64>>(1.)?, 1.),
+            (make_base_laplace::<AllDomain<f64>>(0.5)?, 2.),
+        ];
+        let composition = make_proportional_composition(measurements)?;
+        assert_eq!(composition.function.eval(&0.)?.len(), 3);
+        assert!(composition.privacy_relation.eval(&1., &4.)?);
+        assert!(!composition.privacy_relation.eval(&1., &3.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_lazy_composition() -> Fallible<()> {
+        use std::cell::Cell;
+        let counters = (0..5).map(|_| Rc::new(Cell::new(0))).collect::<Vec<_>>();
+        let measurements = counters.iter().map(|counter| {
+            let counter = counter.clone();
+            Measurement::new(
+                AllDomain::<i32>::new(),
+                AllDomain::<i32>::new(),
+                Function::new(move |arg: &i32| { counter.set(counter.get() + 1); *arg }),
+                L1Distance::<i32>::default(),
+                MaxDivergence::default(),
+                PrivacyRelation::new_from_constant(1.))
+        }).collect();
+        let composition = make_lazy_composition(measurements)?;
+
+        let outputs = composition.function.eval(&7)?;
+        assert!(counters.iter().all(|c| c.get() == 0));
+        assert_eq!(outputs[2].eval()?, 7);
+        assert_eq!(outputs[2].eval()?, 7);
+        assert_eq!(counters.iter().map(|c| c.get()).collect::<Vec<_>>(), vec![0, 0, 1, 0, 0]);
+
+        // the budget covers all five measurements, even though only one was evaluated
+        assert!(composition.privacy_relation.eval(&1, &5.)?);
+        assert!(!composition.privacy_relation.eval(&1, &4.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_lazy_composition_failure() -> Fallible<()> {
+        use std::cell::Cell;
+        let counter = Rc::new(Cell::new(0));
+        let measurement = Measurement::new(
+            AllDomain::<i32>::new(),
+            AllDomain::<i32>::new(),
+            Function::new_fallible(enclose!(counter, move |_arg: &i32| {
+                counter.set(counter.get() + 1);
+                fallible!(FailedFunction, "noise failed")
+            })),
+            L1Distance::<i32>::default(),
+            MaxDivergence::default(),
+            PrivacyRelation::new_from_constant(1.));
+        let outputs = make_lazy_composition(vec![measurement])?.function.eval(&7)?;
+
+        // a retry returns the same error, and does not invoke the mechanism again
+        let error = outputs[0].eval().unwrap_err();
+        assert_eq!(error.message, Some("noise failed".to_string()));
+        assert_eq!(outputs[0].eval().unwrap_err(), error);
+        assert_eq!(counter.get(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_ordered_composition() -> Fallible<()> {
+        let measurement0 = make_base_laplace::<AllDomain<f64>>(0.5)?;
+        let measurement1 = make_base_laplace::<AllDomain<f64>>(1.)?;
+        assert!(make_ordered_composition(&measurement0, 2., &measurement1, 1.).is_err());
+
+        let measurement0 = make_base_laplace::<AllDomain<f64>>(2.)?;
+        let composition = make_ordered_composition(&measurement0, 0.5, &measurement1, 1.)?;
+        composition.function.eval(&0.)?;
+        assert!(composition.privacy_relation.eval(&1., &1.5)?);
+        assert!(!composition.privacy_relation.eval(&1., &1.4)?);
+        assert!(!composition.privacy_relation.eval(&2., &1.5)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_shuffle() -> Fallible<()> {
+        let local_epsilon = (0.75f64 / 0.25).ln();
+        let local = make_randomized_response_bool::<f64>(0.75, false)?;
+        let n = 100_000;
+        let shuffled = make_shuffle(&local, local_epsilon, n, true)?;
+        let reports = shuffled.function.eval(&vec![true; n])?;
+        assert_eq!(reports.len(), n);
+        assert!(shuffled.function.eval(&vec![true]).is_err());
+
+        // amplification gives a much smaller central epsilon than the local guarantee
+        let amplified = shuffle_amplification_epsilon(local_epsilon, n, 1e-6).unwrap();
+        assert!(amplified < local_epsilon / 10.);
+        assert!(shuffled.privacy_relation.eval(&1, &(amplified, 1e-6))?);
+        assert!(!shuffled.privacy_relation.eval(&1, &(amplified * 0.99, 1e-6))?);
+        // group privacy also tightens delta, so two records cost slightly more than twice as much
+        assert!(shuffled.privacy_relation.eval(&2, &(2. * amplified * 1.1, 1e-6))?);
+
+        // without a trusted shuffler, only the local guarantee holds
+        let untrusted = make_shuffle(&local, local_epsilon, n, false)?;
+        assert!(!untrusted.privacy_relation.eval(&1, &(amplified, 1e-6))?);
+        assert!(untrusted.privacy_relation.eval(&1, &(local_epsilon, 0.))?);
+        assert!(make_shuffle(&local, local_epsilon / 2., n, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_bernoulli_amplification() -> Fallible<()> {
+        use crate::trans::make_bounded_sum;
+        let sum = make_chain_mt(&make_base_laplace(1.)?, &make_bounded_sum(0., 1.)?, None)?;
+        let amplified = make_bernoulli_amplification(&sum, 0.1)?;
+        assert!(amplified.function.eval(&vec![1.; 100])?.is_finite());
+
+        // ln(1 + 0.1 * (e - 1)) ~= 0.1586
+        assert!(amplified.privacy_relation.eval(&1, &0.16)?);
+        assert!(!amplified.privacy_relation.eval(&1, &0.15)?);
+        assert!(amplified.privacy_relation.eval(&2, &0.32)?);
+        assert!(!amplified.privacy_relation.eval(&2, &0.31)?);
+        // without subsampling, the guarantee is unchanged
+        let unamplified = make_bernoulli_amplification(&sum, 1.)?;
+        assert!(unamplified.privacy_relation.eval(&1, &1.)?);
+        assert!(!unamplified.privacy_relation.eval(&1, &0.99)?);
+        assert!(make_bernoulli_amplification(&sum, 0.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_without_replacement_amplification() -> Fallible<()> {
+        use crate::trans::make_bounded_mean;
+        // replacing one record moves the mean of 10 records in [0, 1] by 0.1
+        let mean = make_chain_mt(&make_base_laplace(0.1)?, &make_bounded_mean(0., 1., 10)?, None)?;
+        let amplified = make_without_replacement_amplification(&mean, 100)?;
+        assert_eq!(amplified.input_domain.length, 100);
+        assert!(amplified.invoke(&vec![0.5; 100])?.is_finite());
+        assert!(amplified.invoke(&vec![0.5; 10]).is_err());
+
+        // ln(1 + 0.1 * (e - 1)) ~= 0.1586 per replaced record
+        assert!(amplified.privacy_relation.eval(&2, &0.16)?);
+        assert!(!amplified.privacy_relation.eval(&2, &0.15)?);
+        assert!(amplified.privacy_relation.eval(&4, &0.32)?);
+        assert!(!amplified.privacy_relation.eval(&4, &0.31)?);
+        assert!(make_without_replacement_amplification(&mean, 5).is_err());
+        Ok(())
+    }
 }
 
 
@@ -20,11 +20,16 @@
 // Ordering of generic arguments
 // DI, DO, MI, MO, TI, TO, QI, QO
 
+use std::any::{Any, TypeId};
+use std::cmp::Ordering;
+use std::fmt::Debug;
 use std::rc::Rc;
 
+use num::Zero;
+
 use crate::dom::PairDomain;
 use crate::error::*;
-use crate::traits::{DistanceCast, DistanceConstant};
+use crate::traits::{Bisect, DistanceCast, DistanceConstant};
 
 /// A set which constrains the input or output of a [`Function`].
 ///
@@ -82,6 +87,18 @@ pub trait Measure: Default + Clone + PartialEq {
     type Distance;
 }
 
+/// A [`Measure`] under which the privacy losses of two measurements compose.
+pub trait BasicCompositionMeasure: Measure {
+    /// Search for two budgets whose composition does not exceed `d_out`, such that the first satisfies `predicate0`
+    /// and the second satisfies `predicate1`. Both predicates must be monotone in the budget.
+    /// Returns `None` if no such split is found.
+    fn split(
+        &self, d_out: &Self::Distance,
+        predicate0: &dyn Fn(&Self::Distance) -> Fallible<bool>,
+        predicate1: &dyn Fn(&Self::Distance) -> Fallible<bool>,
+    ) -> Fallible<Option<(Self::Distance, Self::Distance)>>;
+}
+
 /// An indicator trait that is only implemented for dataset distances.
 pub trait DatasetMetric: Metric<Distance=u32> {}
 
@@ -89,6 +106,45 @@ pub trait DatasetMetric: Metric<Distance=u32> {}
 pub trait SensitivityMetric: Metric {}
 
 
+/// Search for the smallest distance that satisfies a monotone `predicate`, by bisection.
+/// If no such distance is found, the largest distance tried is returned.
+fn bisect_smallest<Q: Bisect>(predicate: &dyn Fn(&Q) -> Fallible<bool>) -> Fallible<Q> {
+    let mut lower = Q::zero();
+    if predicate(&lower)? { return Ok(lower) }
+
+    // grow the upper bound until the predicate holds
+    let mut upper = Q::one();
+    while !predicate(&upper)? {
+        match upper.double() {
+            Some(doubled) => { lower = upper; upper = doubled }
+            None => return Ok(upper)
+        }
+    }
+    // the predicate holds at upper and fails at lower
+    while let Some(mid) = Q::midpoint(&lower, &upper) {
+        if predicate(&mid)? { upper = mid } else { lower = mid }
+    }
+    Ok(upper)
+}
+
+/// [`bisect_smallest`] for a distance type `Q` that may implement [`Bisect`].
+/// Chaining is generic over distances that can't be searched, so the search is selected at runtime,
+/// and `None` is returned if `Q` is not one of the types that implement [`Bisect`].
+fn bisect_dyn<Q: 'static>() -> Option<fn(&dyn Fn(&Q) -> Fallible<bool>) -> Fallible<Q>> {
+    macro_rules! dispatch_bisect {
+        ($($ty:ty),+) => ($(if TypeId::of::<Q>() == TypeId::of::<$ty>() {
+            return Some(|predicate: &dyn Fn(&Q) -> Fallible<bool>| {
+                let found = bisect_smallest(&|d: &$ty| predicate((d as &dyn Any).downcast_ref::<Q>()
+                    .unwrap_assert("Q is the same type as the searched type")))?;
+                Ok(*(Box::new(found) as Box<dyn Any>).downcast::<Q>()
+                    .unwrap_assert("Q is the same type as the searched type"))
+            })
+        })+)
+    }
+    dispatch_bisect!(f32, f64, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+    None
+}
+
 // HINTS
 #[derive(Clone)]
 pub struct HintMt<MI: Metric, MO: Measure, MX: Metric> {
@@ -113,6 +169,15 @@ impl<MI: Metric, MO: Metric, MX: Metric> HintTt<MI, MO, MX> {
     pub fn new_fallible(hint: impl Fn(&MI::Distance, &MO::Distance) -> Fallible<Box<MX::Distance>> + 'static) -> Self {
         HintTt { hint: Rc::new(hint) }
     }
+    /// Search for the smallest intermediate distance that satisfies `relation0`, by bisection.
+    /// Useful when `relation0` has no forward map and the following relation has no backward map.
+    /// If no such distance is found, the largest distance tried is returned, and the chained relation will not hold.
+    pub fn new_bisect(relation0: &StabilityRelation<MI, MX>) -> Self
+        where MI: 'static, MO: 'static, MX: 'static, MX::Distance: Bisect {
+        let relation0 = relation0.relation.clone();
+        HintTt::new_fallible(move |d_in: &MI::Distance, _d_out: &MO::Distance|
+            bisect_smallest(&|d_mid| relation0(d_in, d_mid)).map(Box::new))
+    }
     pub fn eval(&self, input_distance: &MI::Distance, output_distance: &MO::Distance) -> Fallible<MX::Distance> {
         (self.hint)(input_distance, output_distance).map(|v| *v)
     }
@@ -163,6 +228,12 @@ impl<MI: Metric, MO: Measure> PrivacyRelation<MI, MO> {
     pub fn eval(&self, input_distance: &MI::Distance, output_distance: &MO::Distance) -> Fallible<bool> {
         (self.relation)(input_distance, output_distance)
     }
+    /// Evaluate the relation on a grid of distances. Rows correspond to `d_ins`, and columns to `d_outs`.
+    pub fn tabulate(&self, d_ins: &[MI::Distance], d_outs: &[MO::Distance]) -> Fallible<Vec<Vec<bool>>> {
+        d_ins.iter()
+            .map(|d_in| d_outs.iter().map(|d_out| self.eval(d_in, d_out)).collect())
+            .collect()
+    }
 }
 
 fn chain_option_maps<QI, QX, QO>(
@@ -181,30 +252,35 @@ impl<MI: 'static + Metric, MO: 'static + Measure> PrivacyRelation<MI, MO> {
         relation1: &PrivacyRelation<MX, MO>,
         relation0: &StabilityRelation<MI, MX>,
         hint: Option<&HintMt<MI, MO, MX>>,
-    ) -> Self {
+    ) -> Fallible<Self> {
         if let Some(hint) = hint {
-            Self::make_chain_hint(relation1, relation0, hint)
+            Ok(Self::make_chain_hint(relation1, relation0, hint))
         } else {
             Self::make_chain_no_hint(relation1, relation0)
         }
     }
 
+    /// Derive the intermediate distance from the forward map of `relation0`,
+    /// or else from the backward map of `relation1`,
+    /// or else by searching for the smallest distance that satisfies `relation0`.
     fn make_chain_no_hint<MX: 'static + Metric>(
         relation1: &PrivacyRelation<MX, MO>,
         relation0: &StabilityRelation<MI, MX>,
-    ) -> Self {
+    ) -> Fallible<Self> {
         let hint = if let Some(forward_map) = &relation0.forward_map {
             Some(HintMt::new(enclose!(forward_map, move |d_in, _d_out| forward_map(d_in))))
         } else if let Some(backward_map) = &relation1.backward_map {
             Some(HintMt::new(enclose!(backward_map, move |_d_in, d_out| backward_map(d_out))))
         } else {
-            None
+            bisect_dyn::<MX::Distance>().map(|bisect| {
+                let relation0 = relation0.relation.clone();
+                HintMt::new(move |d_in, _d_out| bisect(&|d_mid| relation0(d_in, d_mid)).map(Box::new))
+            })
         };
         if let Some(hint) = hint {
-            Self::make_chain_hint(relation1, relation0, &hint)
+            Ok(Self::make_chain_hint(relation1, relation0, &hint))
         } else {
-            // TODO: Implement binary search for hints.
-            unimplemented!("Binary search for hints not implemented, must have maps or supply explicit hint.")
+            fallible!(FailedRelation, "cannot derive the intermediate distance: the transformation has no forward map, the measurement has no backward map, the intermediate distance cannot be searched, and no hint was supplied")
         }
     }
 
@@ -284,15 +360,34 @@ impl<MI: Metric, MO: Metric> StabilityRelation<MI, MO> {
 }
 
 impl<MI: 'static + Metric, MO: 'static + Metric> StabilityRelation<MI, MO> {
-    pub fn make_chain<MX: 'static + Metric>(relation1: &StabilityRelation<MX, MO>, relation0: &StabilityRelation<MI, MX>, hint: Option<&HintTt<MI, MO, MX>>) -> Self {
+    /// Scale the output distance of a relation by a positive `factor`.
+    /// If (d_in, d_out) satisfies `self`, then (d_in, d_out * factor) satisfies the scaled relation.
+    pub fn scale(self, factor: MO::Distance) -> Fallible<Self> where MO::Distance: DistanceConstant + Zero {
+        if factor.partial_cmp(&MO::Distance::zero()) != Some(Ordering::Greater) {
+            return fallible!(FailedFunction, "scale factor must be positive")
+        }
+        let StabilityRelation { relation, forward_map, backward_map } = self;
+        Ok(StabilityRelation::new_all(
+            enclose!(factor, move |d_in: &MI::Distance, d_out: &MO::Distance|
+                relation(d_in, &(d_out.clone() / factor.clone()))),
+            forward_map.map(|forward_map| enclose!(factor, move |d_in: &MI::Distance|
+                Ok(Box::new(*forward_map(d_in)? * factor.clone())))),
+            backward_map.map(|backward_map| move |d_out: &MO::Distance|
+                backward_map(&(d_out.clone() / factor.clone())))))
+    }
+
+    pub fn make_chain<MX: 'static + Metric>(relation1: &StabilityRelation<MX, MO>, relation0: &StabilityRelation<MI, MX>, hint: Option<&HintTt<MI, MO, MX>>) -> Fallible<Self> {
         if let Some(hint) = hint {
-            Self::make_chain_hint(relation1, relation0, hint)
+            Ok(Self::make_chain_hint(relation1, relation0, hint))
         } else {
             Self::make_chain_no_hint(relation1, relation0)
         }
     }
 
-    fn make_chain_no_hint<MX: 'static + Metric>(relation1: &StabilityRelation<MX, MO>, relation0: &StabilityRelation<MI, MX>) -> Self {
+    /// Derive the intermediate distance from the forward map of `relation0`,
+    /// or else from the backward map of `relation1`,
+    /// or else by searching for the smallest distance that satisfies `relation0`, as in [`HintTt::new_bisect`].
+    fn make_chain_no_hint<MX: 'static + Metric>(relation1: &StabilityRelation<MX, MO>, relation0: &StabilityRelation<MI, MX>) -> Fallible<Self> {
         let hint = if let Some(forward_map) = &relation0.forward_map {
             let forward_map = forward_map.clone();
             Some(HintTt::new_fallible(move |d_in, _d_out| forward_map(d_in)))
@@ -300,13 +395,15 @@ impl<MI: 'static + Metric, MO: 'static + Metric> StabilityRelation<MI, MO> {
             let backward_map = backward_map.clone();
             Some(HintTt::new_fallible(move |_d_in, d_out| backward_map(d_out)))
         } else {
-            None
+            bisect_dyn::<MX::Distance>().map(|bisect| {
+                let relation0 = relation0.relation.clone();
+                HintTt::new_fallible(move |d_in, _d_out| bisect(&|d_mid| relation0(d_in, d_mid)).map(Box::new))
+            })
         };
         if let Some(hint) = hint {
-            Self::make_chain_hint(relation1, relation0, &hint)
+            Ok(Self::make_chain_hint(relation1, relation0, &hint))
         } else {
-            // TODO: Implement binary search for hints.
-            unimplemented!("Binary search for hints not implemented, must have maps or supply explicit hint.")
+            fallible!(FailedRelation, "cannot derive the intermediate distance: neither transformation has a suitable map, the intermediate distance cannot be searched, and no hint was supplied")
         }
     }
 
@@ -343,6 +440,8 @@ pub struct Measurement<DI: Domain, DO: Domain, MI: Metric, MO: Measure> {
     pub input_metric: MI,
     pub output_measure: MO,
     pub privacy_relation: PrivacyRelation<MI, MO>,
+    /// The symmetric `(1 - alpha)`-confidence interval around a `released` scalar, if the noise distribution is known.
+    pub accuracy: Option<Rc<dyn Fn(f64, f64) -> Fallible<(f64, f64)>>>,
 }
 
 impl<DI: Domain, DO: Domain, MI: Metric, MO: Measure> Measurement<DI, DO, MI, MO> {
@@ -361,6 +460,44 @@ impl<DI: Domain, DO: Domain, MI: Metric, MO: Measure> Measurement<DI, DO, MI, MO
             input_metric,
             output_measure,
             privacy_relation,
+            accuracy: None,
+        }
+    }
+
+    /// Attach a confidence interval, as a function of the `released` value and `alpha`.
+    pub fn with_confidence_interval(mut self, accuracy: impl Fn(f64, f64) -> Fallible<(f64, f64)> + 'static) -> Self {
+        self.accuracy = Some(Rc::new(accuracy));
+        self
+    }
+
+    /// The symmetric `(1 - alpha)`-confidence interval around a scalar `released` by this measurement.
+    /// For vector-valued measurements, the interval holds for each coordinate.
+    pub fn confidence_interval(&self, released: f64, alpha: f64) -> Fallible<(f64, f64)> {
+        let accuracy = self.accuracy.as_ref()
+            .ok_or_else(|| err!(FailedFunction, "the noise distribution of the measurement is unknown"))?;
+        accuracy(released, alpha)
+    }
+
+    /// Invoke the measurement on `arg`, after checking that `arg` is a member of the input domain.
+    pub fn invoke(&self, arg: &DI::Carrier) -> Fallible<DO::Carrier> {
+        if !self.input_domain.member(arg) {
+            return fallible!(DomainMismatch, "argument is not a member of the input domain")
+        }
+        self.function.eval(arg)
+    }
+}
+
+impl<DI: Domain, DO: Domain, MI: Metric, MO: Measure> Measurement<DI, DO, MI, MO>
+    where MI::Distance: Debug, MO::Distance: Debug {
+    /// Check that the measurement is `d_out`-private for inputs that are `d_in`-close.
+    /// If not, the error describes the largest `d_in` that `d_out` permits, when the relation has a backward map.
+    pub fn satisfies(&self, d_in: &MI::Distance, d_out: &MO::Distance) -> Fallible<()> {
+        if self.privacy_relation.eval(d_in, d_out)? { return Ok(()) }
+        match &self.privacy_relation.backward_map {
+            Some(backward_map) => fallible!(FailedRelation,
+                "budget of {:?} permits a d_in of at most {:?}, but d_in is {:?}", d_out, backward_map(d_out)?, d_in),
+            None => fallible!(FailedRelation,
+                "budget of {:?} is insufficient for a d_in of {:?}", d_out, d_in)
         }
     }
 }
@@ -393,6 +530,44 @@ impl<DI: Domain, DO: Domain, MI: Metric, MO: Metric> Transformation<DI, DO, MI,
             stability_relation,
         }
     }
+
+    /// Invoke the transformation on `arg`, after checking that `arg` is a member of the input domain.
+    pub fn invoke(&self, arg: &DI::Carrier) -> Fallible<DO::Carrier> {
+        if !self.input_domain.member(arg) {
+            return fallible!(DomainMismatch, "argument is not a member of the input domain")
+        }
+        self.function.eval(arg)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn invoke_serialized<DI: Domain, DO: Domain>(function: &Function<DI, DO>, arg_bytes: &[u8]) -> Fallible<Vec<u8>>
+    where DI::Carrier: serde::de::DeserializeOwned,
+          DO::Carrier: serde::Serialize {
+    let arg = serde_json::from_slice(arg_bytes)
+        .map_err(|e| err!(FFI, "failed to deserialize argument: {}", e))?;
+    serde_json::to_vec(&function.eval(&arg)?)
+        .map_err(|e| err!(FFI, "failed to serialize result: {}", e))
+}
+
+#[cfg(feature = "serde")]
+impl<DI: Domain, DO: Domain, MI: Metric, MO: Measure> Measurement<DI, DO, MI, MO>
+    where DI::Carrier: serde::de::DeserializeOwned,
+          DO::Carrier: serde::Serialize {
+    /// Invoke the measurement on a JSON-serialized argument, returning the JSON-serialized release.
+    pub fn invoke_serialized(&self, arg_bytes: &[u8]) -> Fallible<Vec<u8>> {
+        invoke_serialized(&self.function, arg_bytes)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<DI: Domain, DO: Domain, MI: Metric, MO: Metric> Transformation<DI, DO, MI, MO>
+    where DI::Carrier: serde::de::DeserializeOwned,
+          DO::Carrier: serde::Serialize {
+    /// Invoke the transformation on a JSON-serialized argument, returning the JSON-serialized result.
+    pub fn invoke_serialized(&self, arg_bytes: &[u8]) -> Fallible<Vec<u8>> {
+        invoke_serialized(&self.function, arg_bytes)
+    }
 }
 
 
@@ -417,4 +592,101 @@ mod tests {
         let ret = identity.function.eval(&arg).unwrap_test();
         assert_eq!(ret, 99);
     }
+
+    #[test]
+    fn test_measurement_satisfies() -> Fallible<()> {
+        use crate::dist::SymmetricDistance;
+        use crate::dom::VectorDomain;
+        use crate::meas::make_base_laplace;
+        use crate::trans::{make_bounded_sum, make_clamp};
+
+        let measurement = (
+            make_clamp::<VectorDomain<AllDomain<f64>>, SymmetricDistance>(0., 10.)? >>
+            make_bounded_sum(0., 10.)? >>
+            make_base_laplace(10.)?
+        )?;
+        measurement.satisfies(&1, &1.)?;
+        let error = measurement.satisfies(&2, &1.).unwrap_err();
+        assert_eq!(error.variant, ErrorVariant::FailedRelation);
+        assert_eq!(error.message, Some("budget of 1.0 permits a d_in of at most 1, but d_in is 2".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invoke_checks_domain() -> Fallible<()> {
+        use crate::dist::SymmetricDistance;
+        use crate::dom::{IntervalDomain, VectorDomain};
+        use crate::meas::make_base_laplace;
+        use crate::trans::{make_bounded_sum, make_identity};
+        use std::ops::Bound;
+
+        let domain = VectorDomain::new(IntervalDomain::new(Bound::Included(0.), Bound::Included(10.))?);
+        let identity = make_identity(domain, SymmetricDistance)?;
+        assert_eq!(identity.invoke(&vec![1., 2.])?, vec![1., 2.]);
+        let error = identity.invoke(&vec![1., 20.]).unwrap_err();
+        assert_eq!(error.variant, ErrorVariant::DomainMismatch);
+        // the raw function does not check the domain
+        assert_eq!(identity.function.eval(&vec![1., 20.])?, vec![1., 20.]);
+
+        let measurement = (make_bounded_sum(0., 10.)? >> make_base_laplace(1.)?)?;
+        measurement.invoke(&vec![1., 2.])?;
+        assert!(measurement.invoke(&vec![1., 20.]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_from_constant_cast_overflow() -> Fallible<()> {
+        use crate::dist::SymmetricDistance;
+        let relation = StabilityRelation::<SymmetricDistance, L1Distance<i8>>::new_from_constant(1);
+        assert!(relation.eval(&100, &100)?);
+        // 200 does not fit in an i8, so the relation errors instead of panicking
+        assert_eq!(relation.eval(&200, &100).unwrap_err().variant, ErrorVariant::FailedCast);
+        assert!(relation.forward_map.as_ref().unwrap_test()(&200).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_privacy_relation_tabulate() -> Fallible<()> {
+        use crate::dist::MaxDivergence;
+        // laplace relation with scale 2: d_out >= d_in / 2
+        let relation = PrivacyRelation::<L1Distance<f64>, MaxDivergence<f64>>::new_from_constant(0.5);
+        let table = relation.tabulate(&[1., 2., 3.], &[0.5, 1., 1.5])?;
+        assert_eq!(table, vec![
+            vec![true, true, true],
+            vec![false, true, true],
+            vec![false, false, true]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stability_relation_scale() -> Fallible<()> {
+        let scaled = StabilityRelation::<L1Distance<i32>, L1Distance<i32>>::new_from_constant(1).scale(3)?;
+        let constant = StabilityRelation::<L1Distance<i32>, L1Distance<i32>>::new_from_constant(3);
+        for (d_in, d_out) in [(1, 2), (1, 3), (2, 5), (2, 6), (2, 7)] {
+            assert_eq!(scaled.eval(&d_in, &d_out)?, constant.eval(&d_in, &d_out)?);
+        }
+        assert_eq!(scaled.forward_map.as_ref().unwrap_test()(&2)?, Box::new(6));
+        assert_eq!(scaled.backward_map.as_ref().unwrap_test()(&6)?, Box::new(2));
+
+        let relation = StabilityRelation::<L1Distance<i32>, L1Distance<i32>>::new_from_constant(1);
+        assert!(relation.clone().scale(0).is_err());
+        assert!(relation.scale(-1).is_err());
+        assert!(StabilityRelation::<L1Distance<f64>, L1Distance<f64>>::new_from_constant(1.).scale(f64::NAN).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_invoke_serialized() -> Fallible<()> {
+        use crate::dist::SymmetricDistance;
+        use crate::dom::VectorDomain;
+        use crate::trans::make_clamp;
+
+        let clamp = make_clamp::<VectorDomain<AllDomain<f64>>, SymmetricDistance>(0., 10.)?;
+        let arg = serde_json::to_vec(&vec![-1., 5., 11.]).unwrap_test();
+        let ret: Vec<f64> = serde_json::from_slice(&clamp.invoke_serialized(&arg)?).unwrap_test();
+        assert_eq!(ret, vec![0., 5., 10.]);
+        assert!(clamp.invoke_serialized(b"not json").is_err());
+        Ok(())
+    }
 }
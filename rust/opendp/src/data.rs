@@ -10,6 +10,8 @@ pub trait IsVec: Debug {
     fn as_any(&self) -> &dyn Any;
     fn box_clone(&self) -> Box<dyn IsVec>;
     fn eq(&self, other: &dyn Any) -> bool;
+    /// The elements at the positions where `mask` is true.
+    fn subset(&self, mask: &[bool]) -> Box<dyn IsVec>;
 }
 
 impl<T> IsVec for Vec<T> where
@@ -18,6 +20,12 @@ impl<T> IsVec for Vec<T> where
     fn as_any(&self) -> &dyn Any { self }
     fn box_clone(&self) -> Box<dyn IsVec> { Box::new(self.clone()) }
     fn eq(&self, other: &dyn Any) -> bool { other.downcast_ref::<Self>().map_or(false, |o| o == self) }
+    fn subset(&self, mask: &[bool]) -> Box<dyn IsVec> {
+        Box::new(self.iter().zip(mask.iter())
+            .filter(|(_, keep)| **keep)
+            .map(|(v, _)| v.clone())
+            .collect::<Vec<T>>())
+    }
 }
 
 impl<T> From<Vec<T>> for Column
@@ -39,6 +47,9 @@ impl Column {
         self.0.as_any().downcast_ref::<T>()
             .ok_or_else(|| err!(FailedCast))
     }
+    pub fn subset(&self, mask: &[bool]) -> Self {
+        Column(self.0.subset(mask))
+    }
     pub fn into_form<T: 'static + IsVec>(self) -> Fallible<T> {
         self.0.into_any().downcast::<T>()
             .map_err(|_e| err!(FailedCast))
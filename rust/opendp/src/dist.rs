@@ -2,7 +2,11 @@
 
 use std::marker::PhantomData;
 
-use crate::core::{DatasetMetric, Measure, Metric, SensitivityMetric};
+use num::Float;
+
+use crate::core::{BasicCompositionMeasure, DatasetMetric, Measure, Metric, SensitivityMetric};
+use crate::error::Fallible;
+use crate::traits::Bisect;
 
 /// Measures
 #[derive(Clone)]
@@ -19,6 +23,45 @@ impl<Q: Clone> Measure for MaxDivergence<Q> {
     type Distance = Q;
 }
 
+/// Find the smallest budget in `[0, upper]` that satisfies a monotone `predicate`, by bisection.
+fn bisect_budget<Q: Float + Bisect>(upper: Q, predicate: &dyn Fn(&Q) -> Fallible<bool>) -> Fallible<Option<Q>> {
+    if !predicate(&upper)? { return Ok(None) }
+    let mut lower = Q::zero();
+    let mut upper = upper;
+    if predicate(&lower)? { return Ok(Some(lower)) }
+    // the predicate holds at upper and fails at lower
+    while let Some(mid) = Q::midpoint(&lower, &upper) {
+        if predicate(&mid)? { upper = mid } else { lower = mid }
+    }
+    Ok(Some(upper))
+}
+
+// a scalar budget adds under composition.
+//     The smallest budget that satisfies the first predicate leaves the most for the second
+fn split_scalar<Q: Float + Bisect>(
+    d_out: &Q,
+    predicate0: &dyn Fn(&Q) -> Fallible<bool>,
+    predicate1: &dyn Fn(&Q) -> Fallible<bool>,
+) -> Fallible<Option<(Q, Q)>> {
+    let d_out0 = match bisect_budget(*d_out, predicate0)? {
+        Some(d_out0) => d_out0,
+        None => return Ok(None)
+    };
+    let d_out1 = *d_out - d_out0;
+    Ok(if predicate1(&d_out1)? { Some((d_out0, d_out1)) } else { None })
+}
+
+// epsilons add
+impl<Q: Float + Bisect> BasicCompositionMeasure for MaxDivergence<Q> {
+    fn split(
+        &self, d_out: &Q,
+        predicate0: &dyn Fn(&Q) -> Fallible<bool>,
+        predicate1: &dyn Fn(&Q) -> Fallible<bool>,
+    ) -> Fallible<Option<(Q, Q)>> {
+        split_scalar(d_out, predicate0, predicate1)
+    }
+}
+
 #[derive(Clone)]
 pub struct SmoothedMaxDivergence<Q>(PhantomData<Q>);
 
@@ -34,6 +77,68 @@ impl<Q: Clone> Measure for SmoothedMaxDivergence<Q> {
     type Distance = (Q, Q);
 }
 
+// both epsilon and delta add.
+//     The first budget is found by minimizing one of epsilon or delta while holding the other at its full value,
+//     and then minimizing the other. Both orders are tried
+impl<Q: Float + Bisect> BasicCompositionMeasure for SmoothedMaxDivergence<Q> {
+    fn split(
+        &self, d_out: &(Q, Q),
+        predicate0: &dyn Fn(&(Q, Q)) -> Fallible<bool>,
+        predicate1: &dyn Fn(&(Q, Q)) -> Fallible<bool>,
+    ) -> Fallible<Option<((Q, Q), (Q, Q))>> {
+        let (eps, del) = *d_out;
+        let check = |d_out0: Option<(Q, Q)>| -> Fallible<Option<((Q, Q), (Q, Q))>> {
+            Ok(match d_out0 {
+                Some((eps0, del0)) => {
+                    let d_out1 = (eps - eps0, del - del0);
+                    if predicate1(&d_out1)? { Some(((eps0, del0), d_out1)) } else { None }
+                }
+                None => None
+            })
+        };
+
+        // minimize delta first
+        let d_out0 = match bisect_budget(del, &|del0| predicate0(&(eps, *del0)))? {
+            Some(del0) => bisect_budget(eps, &|eps0| predicate0(&(*eps0, del0)))?.map(|eps0| (eps0, del0)),
+            None => return Ok(None)
+        };
+        if let Some(split) = check(d_out0)? { return Ok(Some(split)) }
+
+        // minimize epsilon first
+        let d_out0 = match bisect_budget(eps, &|eps0| predicate0(&(*eps0, del)))? {
+            Some(eps0) => bisect_budget(del, &|del0| predicate0(&(eps0, *del0)))?.map(|del0| (eps0, del0)),
+            None => return Ok(None)
+        };
+        check(d_out0)
+    }
+}
+
+#[derive(Clone)]
+pub struct ZeroConcentratedDivergence<Q>(PhantomData<Q>);
+
+impl<Q> Default for ZeroConcentratedDivergence<Q> {
+    fn default() -> Self { ZeroConcentratedDivergence(PhantomData) }
+}
+
+impl<Q> PartialEq for ZeroConcentratedDivergence<Q> {
+    fn eq(&self, _other: &Self) -> bool { true }
+}
+
+impl<Q: Clone> Measure for ZeroConcentratedDivergence<Q> {
+    type Distance = Q;
+}
+
+// rho adds under composition
+impl<Q: Float + Bisect> BasicCompositionMeasure for ZeroConcentratedDivergence<Q> {
+    fn split(
+        &self, d_out: &Q,
+        predicate0: &dyn Fn(&Q) -> Fallible<bool>,
+        predicate1: &dyn Fn(&Q) -> Fallible<bool>,
+    ) -> Fallible<Option<(Q, Q)>> {
+        split_scalar(d_out, predicate0, predicate1)
+    }
+}
+
 /// Metrics
 #[derive(Clone)]
 pub struct SymmetricDistance;
@@ -69,6 +174,22 @@ impl Metric for HammingDistance {
 
 impl DatasetMetric for HammingDistance {}
 
+/// The discrete metric: the distance between two values is 0 if they are equal, and 1 otherwise.
+#[derive(Clone)]
+pub struct DiscreteDistance;
+
+impl Default for DiscreteDistance {
+    fn default() -> Self { DiscreteDistance }
+}
+
+impl PartialEq for DiscreteDistance {
+    fn eq(&self, _other: &Self) -> bool { true }
+}
+
+impl Metric for DiscreteDistance {
+    type Distance = u32;
+}
+
 // Sensitivity in P-space
 pub struct LpDistance<Q, const P: usize>(PhantomData<Q>);
 impl<Q, const P: usize> Default for LpDistance<Q, P> {
@@ -89,6 +210,29 @@ impl<Q, const P: usize> SensitivityMetric for LpDistance<Q, P> {}
 pub type L1Distance<Q> = LpDistance<Q, 1>;
 pub type L2Distance<Q> = LpDistance<Q, 2>;
 
+/// The largest absolute difference between any two coordinates.
+pub struct LInfDistance<Q>(PhantomData<Q>);
+impl<Q> Default for LInfDistance<Q> {
+    fn default() -> Self { LInfDistance(PhantomData) }
+}
+
+impl<Q> Clone for LInfDistance<Q> {
+    fn clone(&self) -> Self { Self::default() }
+}
+impl<Q> PartialEq for LInfDistance<Q> {
+    fn eq(&self, _other: &Self) -> bool { true }
+}
+impl<Q> Metric for LInfDistance<Q> {
+    type Distance = Q;
+}
+impl<Q> SensitivityMetric for LInfDistance<Q> {}
+
+/// A metric on vectors of scores that bounds the change in every score.
+/// Any Lp distance is at least the L-infinity distance, so all of them qualify.
+pub trait ScoreMetric: SensitivityMetric {}
+impl<Q, const P: usize> ScoreMetric for LpDistance<Q, P> {}
+impl<Q> ScoreMetric for LInfDistance<Q> {}
+
 
 pub struct AbsoluteDistance<Q>(PhantomData<Q>);
 impl<Q> Default for AbsoluteDistance<Q> {
@@ -105,3 +249,21 @@ impl<Q> Metric for AbsoluteDistance<Q> {
     type Distance = Q;
 }
 impl<Q> SensitivityMetric for AbsoluteDistance<Q> {}
+
+/// A separate absolute distance for each coordinate of a vector. Distances are vectors,
+/// where the `i`th element bounds the change in the `i`th coordinate.
+pub struct CoordinateDistance<Q>(PhantomData<Q>);
+impl<Q> Default for CoordinateDistance<Q> {
+    fn default() -> Self { CoordinateDistance(PhantomData) }
+}
+
+impl<Q> Clone for CoordinateDistance<Q> {
+    fn clone(&self) -> Self { Self::default() }
+}
+impl<Q> PartialEq for CoordinateDistance<Q> {
+    fn eq(&self, _other: &Self) -> bool { true }
+}
+impl<Q> Metric for CoordinateDistance<Q> {
+    type Distance = Vec<Q>;
+}
+impl<Q> SensitivityMetric for CoordinateDistance<Q> {}
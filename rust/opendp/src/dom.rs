@@ -5,10 +5,12 @@
 //! type.
 
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::marker::PhantomData;
 use std::ops::Bound;
+use std::rc::Rc;
 
 use crate::core::Domain;
 use crate::error::Fallible;
@@ -187,7 +189,19 @@ impl<D: Domain> Domain for VectorDomain<D> {
     }
 }
 
-/// A Domain that specifies the length of the enclosed domain
+/// A collection with a known number of elements.
+pub trait CollectionSize {
+    fn size(&self) -> usize;
+}
+impl<T> CollectionSize for Vec<T> {
+    fn size(&self) -> usize { self.len() }
+}
+impl<K, V> CollectionSize for HashMap<K, V> {
+    fn size(&self) -> usize { self.len() }
+}
+
+/// A Domain that specifies the length of the enclosed domain.
+/// Members must have exactly `length` elements, so that transformations may treat the length as public.
 #[derive(Clone, PartialEq)]
 pub struct SizedDomain<D: Domain> {
     pub element_domain: D,
@@ -198,13 +212,51 @@ impl<D: Domain> SizedDomain<D> {
         SizedDomain { element_domain: member_domain, length }
     }
 }
-impl<D: Domain> Domain for SizedDomain<D> {
+impl<D: Domain> Domain for SizedDomain<D> where D::Carrier: CollectionSize {
     type Carrier = D::Carrier;
     fn member(&self, val: &Self::Carrier) -> bool {
-        self.element_domain.member(val)
+        val.size() == self.length && self.element_domain.member(val)
+    }
+}
+
+/// A value that is computed on first access, and memoized thereafter.
+/// Failures are memoized too, so that a failed evaluation is never retried.
+#[derive(Clone)]
+pub struct Lazy<T> {
+    thunk: Rc<dyn Fn() -> Fallible<T>>,
+    value: Rc<RefCell<Option<Fallible<T>>>>,
+}
+impl<T: Clone> Lazy<T> {
+    pub fn new(thunk: impl Fn() -> Fallible<T> + 'static) -> Self {
+        Lazy { thunk: Rc::new(thunk), value: Rc::new(RefCell::new(None)) }
+    }
+    /// Compute the value, or return the memoized result if it has already been computed.
+    pub fn eval(&self) -> Fallible<T> {
+        if let Some(value) = self.value.borrow().as_ref() {
+            return value.clone()
+        }
+        let value = (self.thunk)();
+        *self.value.borrow_mut() = Some(value.clone());
+        value
     }
 }
 
+/// A Domain of values that are computed on demand.
+/// Membership is not checked, because checking would force evaluation.
+#[derive(Clone, PartialEq)]
+pub struct LazyDomain<D: Domain> {
+    pub element_domain: D,
+}
+impl<D: Domain> LazyDomain<D> {
+    pub fn new(element_domain: D) -> Self {
+        LazyDomain { element_domain }
+    }
+}
+impl<D: Domain> Domain for LazyDomain<D> {
+    type Carrier = Lazy<D::Carrier>;
+    fn member(&self, _val: &Self::Carrier) -> bool { true }
+}
+
 /// A domain with a built-in representation of nullity, that may take on null values at runtime
 #[derive(Clone, PartialEq)]
 pub struct InherentNullDomain<D: Domain>
@@ -30,7 +30,7 @@ macro_rules! err {
         (err!($variant, format!($template, $($args,)+)));
 }
 
-#[derive(thiserror::Error, Debug)]
+#[derive(thiserror::Error, Debug, Clone)]
 pub struct Error {
     pub variant: ErrorVariant,
     pub message: Option<String>,
@@ -43,7 +43,7 @@ impl PartialEq for Error {
     }
 }
 
-#[derive(PartialEq, thiserror::Error, Debug)]
+#[derive(PartialEq, thiserror::Error, Debug, Clone)]
 #[non_exhaustive]
 pub enum ErrorVariant {
     #[error("FFI")]
@@ -1,9 +1,13 @@
 use std::any::Any;
 use std::rc::Rc;
 
-use crate::core::{Domain, Function, Measure, Measurement, Metric, PrivacyRelation};
+use num::Float;
+
+use crate::core::{Domain, Function, Measure, Measurement, Metric, PrivacyRelation, Transformation};
+use crate::dist::{AbsoluteDistance, HammingDistance, MaxDivergence};
 use crate::dom::AllDomain;
 use crate::error::*;
+use crate::samplers::SampleLaplace;
 use crate::traits::{FallibleSub, MeasureDistance, MetricDistance};
 
 /// A structure tracking the state of an interactive measurement queryable.
@@ -48,6 +52,29 @@ impl<S, Q> Queryable<S, Q, Box<dyn Any>> {
 
 pub type InteractiveMeasurement<DI, DO, MI, MO, S, Q> = Measurement<DI, AllDomain<Queryable<S, Q, <DO as Domain>::Carrier>>, MI, MO>;
 
+/// A Domain of streams whose elements are members of `element_domain`.
+/// The stream is not known when a measurement over it is invoked, so the carrier is the placeholder `()`,
+/// and the elements arrive as queries to the Queryable.
+#[derive(Clone, PartialEq)]
+pub struct StreamDomain<D: Domain> {
+    pub element_domain: D,
+}
+impl<D: Domain> StreamDomain<D> {
+    pub fn new(element_domain: D) -> Self {
+        StreamDomain { element_domain }
+    }
+}
+impl<D: Domain> Domain for StreamDomain<D> {
+    type Carrier = ();
+    fn member(&self, _val: &Self::Carrier) -> bool { true }
+}
+
+/// An interactive measurement over a stream whose elements are in DI.
+/// The measurement is invoked on `()`, and each query to the Queryable is the next element of the stream.
+/// `d_in` is a distance between whole streams in MI, not between the placeholder inputs.
+/// No transformation outputs a [`StreamDomain`], so a streaming measurement cannot be chained behind one.
+pub type StreamingMeasurement<DI, DO, MI, MO, S> = Measurement<StreamDomain<DI>, AllDomain<Queryable<S, <DI as Domain>::Carrier, <DO as Domain>::Carrier>>, MI, MO>;
+
 /// The state of an adaptive composition Queryable.
 pub struct AcState<DI: Domain, DO: Domain, MI: Metric, MO: Measure> {
     input_domain: DI,
@@ -151,9 +178,165 @@ pub fn make_adaptive_composition<DI, DO, MI, MO>(
     )
 }
 
+/// The state of a sparse vector Queryable.
+pub struct SvtState<DI: Domain, MI: Metric, T> {
+    input_domain: DI,
+    input_metric: MI,
+    d_in_budget: MI::Distance,
+    sensitivity: T,
+    scale: T,
+    max_positives: usize,
+    noisy_threshold: T,
+    positives: usize,
+    data: DI::Carrier,
+}
+impl<DI: Domain, MI: Metric, T> SvtState<DI, MI, T>
+    where T: 'static + Float + SampleLaplace {
+    /// Answers whether the noisy query answer is above the noisy threshold.
+    fn transition(self, query: &SvtQuery<DI, MI, T>) -> Fallible<(Self, bool)> {
+        if self.positives == self.max_positives {
+            return fallible!(FailedRelation, "all {} positive answers have been released", self.max_positives)
+        }
+        if query.input_domain != self.input_domain {
+            return fallible!(DomainMismatch, "wrong query input domain")
+        } else if query.input_metric != self.input_metric {
+            return fallible!(MetricMismatch, "wrong query input metric")
+        }
+        if !query.stability_relation.eval(&self.d_in_budget, &self.sensitivity)? {
+            return fallible!(FailedRelation, "query sensitivity exceeds the sensitivity of the sparse vector")
+        }
+        let _2c = num_cast!(2 * self.max_positives; T)?;
+        let answer = query.function.eval(&self.data)?;
+        let above = T::sample_laplace(answer, _2c * self.scale, false)? >= self.noisy_threshold;
+        let positives = self.positives + above as usize;
+        Ok((Self { positives, ..self }, above))
+    }
+}
+type SvtQuery<DI, MI, T> = Transformation<DI, AllDomain<T>, MI, AbsoluteDistance<T>>;
+type SvtQueryable<DI, MI, T> = Queryable<SvtState<DI, MI, T>, SvtQuery<DI, MI, T>, bool>;
+type SvtMeasurement<DI, MI, T> = InteractiveMeasurement<DI, AllDomain<bool>, MI, MaxDivergence<T>, SvtState<DI, MI, T>, SvtQuery<DI, MI, T>>;
+
+/// An interactive [`Measurement`] implementing the sparse vector technique (AboveThreshold).
+///
+/// The Queryable answers a stream of queries, each `sensitivity`-sensitive at `d_in_budget`,
+/// with whether the query answer is above `threshold`, until `max_positives` positive answers have been released.
+/// The threshold is perturbed once with Laplace(`scale`) noise, and each query with Laplace(`2 * max_positives * scale`) noise,
+/// so the mechanism is `2 * sensitivity / scale`-DP.
+/// The whole dataset is known when the measurement is invoked, so `d_in` is the distance between neighboring
+/// datasets in `input_metric`, and may be at most `d_in_budget`.
+pub fn make_sparse_vector<DI, MI, T>(
+    input_domain: DI,
+    input_metric: MI,
+    d_in_budget: MI::Distance,
+    sensitivity: T,
+    threshold: T,
+    max_positives: usize,
+    scale: T,
+) -> Fallible<SvtMeasurement<DI, MI, T>>
+    where DI: 'static + Domain,
+          DI::Carrier: Clone,
+          MI: 'static + Metric,
+          MI::Distance: 'static + MetricDistance + Clone,
+          T: 'static + Float + SampleLaplace {
+    if max_positives == 0 { return fallible!(MakeMeasurement, "max_positives must be positive") }
+    if scale.is_nan() || scale <= T::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    if sensitivity.is_nan() || sensitivity.is_sign_negative() { return fallible!(MakeMeasurement, "sensitivity must be non-negative") }
+    if !threshold.is_finite() { return fallible!(MakeMeasurement, "threshold must be finite") }
+    let _2 = num_cast!(2; T)?;
+
+    Ok(SvtMeasurement::new(
+        input_domain.clone(),
+        AllDomain::new(),
+        Function::new_fallible(enclose!((input_domain, input_metric, d_in_budget), move |arg: &DI::Carrier| -> Fallible<SvtQueryable<DI, MI, T>> {
+            Ok(SvtQueryable::new(
+                SvtState {
+                    input_domain: input_domain.clone(),
+                    input_metric: input_metric.clone(),
+                    d_in_budget: d_in_budget.clone(),
+                    sensitivity,
+                    scale,
+                    max_positives,
+                    noisy_threshold: T::sample_laplace(threshold, scale, false)?,
+                    positives: 0,
+                    data: arg.clone(),
+                },
+                |s, q| s.transition(q)))
+        })),
+        input_metric,
+        MaxDivergence::default(),
+        PrivacyRelation::new(move |d_in, d_out| d_in <= &d_in_budget && *d_out >= _2 * sensitivity / scale),
+    ))
+}
+
+/// The state of a binary tree counter Queryable.
+pub struct BinaryTreeCounterState {
+    max_length: usize,
+    scale: f64,
+    time: usize,
+    // exact and noisy partial sums, one per level of the tree
+    partial_sums: Vec<f64>,
+    noisy_partial_sums: Vec<f64>,
+}
+impl BinaryTreeCounterState {
+    /// Consumes the next element of the stream, and releases the noisy running count.
+    fn transition(mut self, element: &bool) -> Fallible<(Self, f64)> {
+        if self.time == self.max_length {
+            return fallible!(FailedFunction, "the stream may have at most {} elements", self.max_length)
+        }
+        self.time += 1;
+        // the partial sum at the lowest set bit absorbs all partial sums below it
+        let level = self.time.trailing_zeros() as usize;
+        let below = self.partial_sums[..level].iter().sum::<f64>();
+        self.partial_sums[level] = below + *element as u8 as f64;
+        self.noisy_partial_sums[level] = f64::sample_laplace(self.partial_sums[level], self.scale, false)?;
+        for i in 0..level {
+            self.partial_sums[i] = 0.;
+            self.noisy_partial_sums[i] = 0.;
+        }
+        // the running count is the sum of the partial sums at the set bits of the time
+        let count = (0..self.partial_sums.len())
+            .filter(|i| self.time & (1 << i) != 0)
+            .map(|i| self.noisy_partial_sums[i])
+            .sum();
+        Ok((self, count))
+    }
+}
+type BinaryTreeCounterQueryable = Queryable<BinaryTreeCounterState, bool, f64>;
+type BinaryTreeCounterMeasurement = StreamingMeasurement<AllDomain<bool>, AllDomain<f64>, HammingDistance, MaxDivergence<f64>, BinaryTreeCounterState>;
+
+/// A streaming [`Measurement`] that releases running counts of a stream of bits, with the binary mechanism.
+///
+/// Each element of a stream of at most `max_length` elements contributes to one partial sum at each of the
+/// `L = floor(log2(max_length)) + 1` levels of a binary tree, and each partial sum is released with Laplace(`scale`) noise.
+/// Each running count is a sum of at most `L` noisy partial sums, so the error is polylogarithmic in the stream length.
+/// The mechanism is `d_in * L / scale`-DP, where `d_in` is the number of elements in which neighboring streams differ.
+pub fn make_binary_tree_counter(max_length: usize, scale: f64) -> Fallible<BinaryTreeCounterMeasurement> {
+    if max_length == 0 { return fallible!(MakeMeasurement, "max_length must be positive") }
+    if scale.is_nan() || scale <= 0. { return fallible!(MakeMeasurement, "scale must be positive") }
+    // the number of bits in max_length
+    let levels = (0..).find(|l| max_length >> l == 0).unwrap_assert("max_length has finitely many bits");
+
+    Ok(BinaryTreeCounterMeasurement::new(
+        StreamDomain::new(AllDomain::new()),
+        AllDomain::new(),
+        Function::new(move |_: &()| BinaryTreeCounterQueryable::new(
+            BinaryTreeCounterState {
+                max_length,
+                scale,
+                time: 0,
+                partial_sums: vec![0.; levels],
+                noisy_partial_sums: vec![0.; levels],
+            },
+            |s, q| s.transition(q))),
+        HammingDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(levels as f64 / scale)))
+}
+
 
 #[cfg(test)]
 mod tests {
+    use crate::chain::make_chain_tt;
     use crate::dist::{MaxDivergence, AbsoluteDistance, SymmetricDistance};
     use crate::dom::VectorDomain;
     use crate::error::*;
@@ -271,4 +454,43 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_sparse_vector() -> Fallible<()> {
+        let make_query = |upper: f64| make_chain_tt(&make_bounded_sum(0., upper)?, &make_clamp(0., upper)?, None);
+        let svt = make_sparse_vector(
+            VectorDomain::new_all(), SymmetricDistance, 1, 1., 5., 1, 0.01)?;
+        assert!(svt.privacy_relation.eval(&1, &200.)?);
+        assert!(!svt.privacy_relation.eval(&2, &200.)?);
+
+        let mut queryable = svt.function.eval(&vec![1.; 10])?;
+        // the sum of 2 is below the threshold, and the sum of 10 is above
+        assert!(!queryable.eval(&make_query(0.2)?)?);
+        assert!(queryable.eval(&make_query(1.)?)?);
+        // the one positive answer is spent
+        assert!(queryable.eval(&make_query(1.)?).is_err());
+
+        // queries may not exceed the sensitivity
+        let mut queryable = svt.function.eval(&vec![1.; 10])?;
+        assert!(queryable.eval(&make_query(2.)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_tree_counter() -> Fallible<()> {
+        let measurement = make_binary_tree_counter(8, 1e-6)?;
+        let mut queryable = measurement.function.eval(&())?;
+        let stream = [true, false, true, true, false, true, true, true];
+        let mut count = 0;
+        for element in stream.iter() {
+            count += *element as i32;
+            assert!((queryable.eval(element)? - count as f64).abs() < 1e-3);
+        }
+        assert!(queryable.eval(&true).is_err());
+
+        // 8 elements span 4 levels
+        assert!(measurement.privacy_relation.eval(&1, &4e6)?);
+        assert!(!measurement.privacy_relation.eval(&1, &3.9e6)?);
+        Ok(())
+    }
 }
@@ -145,6 +145,7 @@ macro_rules! num_cast {
 #[macro_use]
 pub mod error;
 
+pub mod accuracy;
 pub mod chain;
 pub mod core;
 pub mod data;
@@ -152,7 +153,10 @@ pub mod dist;
 pub mod dom;
 pub mod interactive;
 pub mod meas;
+pub mod pipeline;
 pub mod poly;
 pub mod samplers;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 pub mod traits;
 pub mod trans;
@@ -0,0 +1,56 @@
+use num::Float;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{AbsoluteDistance, MaxDivergence};
+use crate::dom::AllDomain;
+use crate::error::*;
+use crate::samplers::SampleCauchy;
+
+/// A [`Measurement`] that adds noise from the Cauchy(`scale`) distribution to the input.
+///
+/// Shifting the Cauchy density by `t = d_in / scale` changes it by a factor of at most `1 + t^2 / 2 + t sqrt(1 + t^2 / 4)`,
+/// so the mechanism satisfies `d_out`-DP when `d_out >= 2 asinh(d_in / (2 scale))`. This never exceeds the `d_in / scale`
+/// of the Laplace mechanism, and grows only logarithmically in `d_in`.
+/// The noise has no mean or variance: releases are heavy-tailed, and averaging repeated releases does not reduce the error.
+/// Cauchy noise is mainly of use with smooth sensitivity, see [`make_smooth_sensitivity_cauchy`](crate::meas::make_smooth_sensitivity_cauchy).
+pub fn make_base_cauchy<T>(scale: T) -> Fallible<Measurement<AllDomain<T>, AllDomain<T>, AbsoluteDistance<T>, MaxDivergence<T>>>
+    where T: 'static + Clone + Float + SampleCauchy {
+    if scale.is_nan() || scale <= T::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    let _2 = num_cast!(2; T)?;
+
+    Ok(Measurement::new(
+        AllDomain::new(),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &T| T::sample_cauchy(*arg, scale, false)),
+        AbsoluteDistance::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &T, d_out: &T| {
+            if d_in.is_nan() || d_in.is_sign_negative() {
+                return fallible!(InvalidDistance, "cauchy mechanism: input sensitivity must be non-negative")
+            }
+            Ok(*d_out >= _2 * (*d_in / (_2 * scale)).asinh())
+        })))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_base_cauchy() -> Fallible<()> {
+        let measurement = make_base_cauchy(1.)?;
+        let releases = (0..1001).map(|_| measurement.function.eval(&10.)).collect::<Fallible<Vec<f64>>>()?;
+        let mut sorted = releases.clone();
+        sorted.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        assert!((sorted[500] - 10.).abs() < 0.5, "median release was {}", sorted[500]);
+
+        // for small sensitivities, the cost approaches d_in / scale
+        let epsilon = 2. * (0.5f64).asinh();
+        assert!(measurement.privacy_relation.eval(&1., &epsilon)?);
+        assert!(!measurement.privacy_relation.eval(&1., &(epsilon * 0.99))?);
+        assert!(epsilon < 1.);
+        assert!(make_base_cauchy(0.).is_err());
+        Ok(())
+    }
+}
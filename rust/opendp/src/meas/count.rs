@@ -0,0 +1,135 @@
+use std::convert::TryFrom;
+use std::hash::Hash;
+use std::ops::AddAssign;
+
+use num::{Bounded, Float, Integer, One, Zero};
+
+use crate::chain::make_chain_mt;
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{L1Distance, MaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::meas::{make_base_geometric, make_base_laplace};
+use crate::samplers::{SampleLaplace, SampleTwoSidedGeometric};
+use crate::traits::{DistanceCast, DistanceConstant};
+use crate::trans::{make_count, make_count_by_categories};
+
+/// A [`Measurement`] that releases the length of a dataset with geometric noise, censored to `[0, n]`.
+/// Since the output is clamped to `[0, n]`, released counts are never negative.
+/// The privacy relation is that of [`make_base_geometric`].
+pub fn make_private_count<TIA, TO, QO>(
+    n: TO, scale: QO
+) -> Fallible<Measurement<VectorDomain<AllDomain<TIA>>, AllDomain<TO>, SymmetricDistance, MaxDivergence<QO>>>
+    where TIA: 'static,
+          TO: 'static + TryFrom<usize> + Bounded + Zero + One + DistanceConstant + SampleTwoSidedGeometric,
+          QO: 'static + Float + DistanceCast,
+          f64: From<QO> {
+    if n < TO::zero() { return fallible!(MakeMeasurement, "n must not be negative") }
+    make_chain_mt(
+        &make_base_geometric::<AllDomain<TO>, QO>(scale, Some((TO::zero(), n)))?,
+        &make_count()?,
+        None)
+}
+
+/// A [`Measurement`] that releases the counts of each of `categories` with Laplace(`scale`) noise.
+/// The last count is of records that are not in `categories`.
+/// Each record contributes to only one count, so the mechanism is `d_in / scale`-DP regardless of the number of categories.
+pub fn make_private_histogram_laplace<TI, T>(
+    categories: Vec<TI>, scale: T
+) -> Fallible<Measurement<VectorDomain<AllDomain<TI>>, VectorDomain<AllDomain<T>>, SymmetricDistance, MaxDivergence<T>>>
+    where TI: 'static + Eq + Hash,
+          T: 'static + Float + DistanceConstant + SampleLaplace + One {
+    let count = make_count_by_categories::<L1Distance<T>, TI, u64>(categories)?;
+    let noise = make_base_laplace::<VectorDomain<AllDomain<T>>>(scale)?;
+    let (count_function, noise_function) = (count.function.function.clone(), noise.function.function.clone());
+
+    Ok(Measurement::new(
+        count.input_domain.clone(),
+        noise.output_domain.clone(),
+        Function::new_fallible(move |arg: &Vec<TI>| {
+            let counts = count_function(arg)?.into_iter()
+                .map(|c| num_cast!(c; T))
+                .collect::<Fallible<Vec<T>>>()?;
+            noise_function(&counts)
+        }),
+        count.input_metric.clone(),
+        noise.output_measure.clone(),
+        PrivacyRelation::make_chain(&noise.privacy_relation, &count.stability_relation, None)?))
+}
+
+/// A [`Measurement`] that releases the counts of each of `categories` with geometric(`scale`) noise.
+/// The last count is of records that are not in `categories`.
+/// Each record contributes to only one count, so the privacy relation is that of [`make_base_geometric`] on a single count.
+pub fn make_private_histogram_geometric<TI, TO, QO>(
+    categories: Vec<TI>, scale: QO
+) -> Fallible<Measurement<VectorDomain<AllDomain<TI>>, VectorDomain<AllDomain<TO>>, SymmetricDistance, MaxDivergence<QO>>>
+    where TI: 'static + Eq + Hash,
+          TO: 'static + Integer + Zero + One + AddAssign + DistanceConstant + SampleTwoSidedGeometric,
+          QO: 'static + Float + DistanceCast,
+          f64: From<QO> {
+    let count = make_count_by_categories::<L1Distance<TO>, TI, TO>(categories)?;
+    let noise = make_base_geometric::<VectorDomain<AllDomain<TO>>, QO>(scale, None)?;
+    let (count_function, noise_function) = (count.function.function.clone(), noise.function.function.clone());
+
+    Ok(Measurement::new(
+        count.input_domain.clone(),
+        noise.output_domain.clone(),
+        Function::new_fallible(move |arg: &Vec<TI>|
+            noise_function(&count_function(arg)?)),
+        count.input_metric.clone(),
+        noise.output_measure.clone(),
+        PrivacyRelation::make_chain(&noise.privacy_relation, &count.stability_relation, None)?))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_private_count() -> Fallible<()> {
+        let measurement = make_private_count::<i32, i32, f64>(10, 1.0)?;
+        let arg = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        for _ in 0..100 {
+            let ret = measurement.function.eval(&arg)?;
+            assert!((0..=10).contains(&ret));
+        }
+        assert!(measurement.function.eval(&vec![])? >= 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_private_count_relation() -> Fallible<()> {
+        let measurement = make_private_count::<i32, i32, f64>(10, 2.0)?;
+        let geometric = make_base_geometric::<AllDomain<i32>, f64>(2.0, Some((0, 10)))?;
+        for (d_in, d_out) in [(1, 0.5), (1, 0.4), (2, 1.0), (2, 0.9)] {
+            assert_eq!(
+                measurement.privacy_relation.eval(&d_in, &d_out)?,
+                geometric.privacy_relation.eval(&(d_in as i32), &d_out)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_private_histogram_laplace() -> Fallible<()> {
+        let measurement = make_private_histogram_laplace(vec!["a", "b"], 1e-3)?;
+        let ret = measurement.function.eval(&vec!["a", "b", "b", "c"])?;
+        assert_eq!(ret.len(), 3);
+        assert!((ret[1] - 2.).abs() < 0.1);
+
+        assert!(measurement.privacy_relation.eval(&1, &1000.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &999.)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_private_histogram_geometric() -> Fallible<()> {
+        let measurement = make_private_histogram_geometric::<_, i64, f64>(vec![1, 2, 3], 2.)?;
+        let ret = measurement.function.eval(&vec![1, 1, 3, 4])?;
+        assert_eq!(ret.len(), 4);
+
+        assert!(measurement.privacy_relation.eval(&1, &0.5)?);
+        assert!(!measurement.privacy_relation.eval(&1, &0.4)?);
+        Ok(())
+    }
+}
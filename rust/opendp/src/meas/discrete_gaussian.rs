@@ -0,0 +1,102 @@
+use crate::core::{Domain, Function, Measurement, PrivacyRelation, SensitivityMetric};
+use crate::dist::{AbsoluteDistance, L2Distance, ZeroConcentratedDivergence};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::SampleDiscreteGaussian;
+
+// the variance is represented as a rational with this denominator
+const VARIANCE_DENOM: u64 = 1 << 16;
+// bounds the intermediate integers in the sampler
+const MAX_SCALE: f64 = (1 << 20) as f64;
+
+pub trait DiscreteGaussianDomain: Domain {
+    type Metric: SensitivityMetric<Distance=i64> + Default;
+    fn new() -> Self;
+    fn noise_function(var_numer: u64, var_denom: u64) -> Function<Self, Self>;
+}
+
+impl DiscreteGaussianDomain for AllDomain<i64> {
+    type Metric = AbsoluteDistance<i64>;
+
+    fn new() -> Self { AllDomain::new() }
+    fn noise_function(var_numer: u64, var_denom: u64) -> Function<Self, Self> {
+        Function::new_fallible(move |arg: &i64| i64::sample_discrete_gaussian(*arg, var_numer, var_denom))
+    }
+}
+
+impl DiscreteGaussianDomain for VectorDomain<AllDomain<i64>> {
+    type Metric = L2Distance<i64>;
+
+    fn new() -> Self { VectorDomain::new_all() }
+    fn noise_function(var_numer: u64, var_denom: u64) -> Function<Self, Self> {
+        Function::new_fallible(move |arg: &Vec<i64>| arg.iter()
+            .map(|v| i64::sample_discrete_gaussian(*v, var_numer, var_denom))
+            .collect())
+    }
+}
+
+/// A [`Measurement`] that adds noise from the discrete Gaussian distribution with standard deviation `scale`.
+/// The sampler is exact, and uses only integer arithmetic.
+/// Satisfies `d_out`-zCDP for `d_in`-sensitive inputs when `d_out >= d_in^2 / (2 scale^2)`.
+pub fn make_base_discrete_gaussian<D>(
+    scale: f64
+) -> Fallible<Measurement<D, D, D::Metric, ZeroConcentratedDivergence<f64>>>
+    where D: 'static + DiscreteGaussianDomain {
+    if scale.is_nan() || scale <= 0. || scale > MAX_SCALE {
+        return fallible!(MakeMeasurement, "scale must be positive and at most {}", MAX_SCALE)
+    }
+    // round the variance up, so that the noise is at least as large as requested.
+    //     The product is computed in floating point and may already be rounded down below the exact value,
+    //     in which case ceil alone would land on the integer below it, so add one more
+    let var_numer = (scale * scale * VARIANCE_DENOM as f64).ceil() as u64 + 1;
+
+    Ok(Measurement::new(
+        D::new(),
+        D::new(),
+        D::noise_function(var_numer, VARIANCE_DENOM),
+        D::Metric::default(),
+        ZeroConcentratedDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &i64, d_out: &f64| {
+            if d_in.is_negative() {
+                return fallible!(InvalidDistance, "discrete gaussian mechanism: input sensitivity must be non-negative")
+            }
+            Ok(*d_out >= (*d_in as f64).powi(2) / (2. * scale * scale))
+        })))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_base_discrete_gaussian() -> Fallible<()> {
+        let measurement = make_base_discrete_gaussian::<AllDomain<i64>>(2.)?;
+        let samples = (0..1000).map(|_| measurement.function.eval(&10)).collect::<Fallible<Vec<i64>>>()?;
+        let mean = samples.iter().sum::<i64>() as f64 / 1000.;
+        let variance = samples.iter().map(|v| (*v as f64 - mean).powi(2)).sum::<f64>() / 1000.;
+        assert!((mean - 10.).abs() < 0.5, "mean was {}", mean);
+        assert!((variance - 4.).abs() < 1.5, "variance was {}", variance);
+
+        // rho = d_in^2 / (2 scale^2)
+        assert!(measurement.privacy_relation.eval(&2, &0.5)?);
+        assert!(!measurement.privacy_relation.eval(&2, &0.49)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_base_discrete_gaussian_vec() -> Fallible<()> {
+        let measurement = make_base_discrete_gaussian::<VectorDomain<_>>(0.5)?;
+        let ret = measurement.function.eval(&vec![1, 2, 3])?;
+        assert_eq!(ret.len(), 3);
+        assert!(measurement.privacy_relation.eval(&1, &2.)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_base_discrete_gaussian_invalid_scale() {
+        assert!(make_base_discrete_gaussian::<AllDomain<i64>>(0.).is_err());
+        assert!(make_base_discrete_gaussian::<AllDomain<i64>>(f64::NAN).is_err());
+        assert!(make_base_discrete_gaussian::<AllDomain<i64>>(1e10).is_err());
+    }
+}
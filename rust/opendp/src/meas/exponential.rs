@@ -0,0 +1,203 @@
+use num::{Float, ToPrimitive};
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{MaxDivergence, ScoreMetric};
+use crate::dom::{AllDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::{SampleBernoulli, SampleUniform, shuffle};
+use crate::traits::DistanceConstant;
+
+/// Sample an index with probability proportional to `exp(log_weights[i])`, via the inverse cdf.
+pub(crate) fn sample_log_weighted_index<Q: Float + SampleUniform>(log_weights: &[Q]) -> Fallible<usize> {
+    if log_weights.iter().any(|w| w.is_nan()) { return fallible!(FailedFunction, "log-weights may not be nan") }
+    let max_log_weight = log_weights.iter().cloned().fold(Q::neg_infinity(), Q::max);
+    if max_log_weight == Q::neg_infinity() { return fallible!(FailedFunction, "at least one weight must be positive") }
+
+    // shift the log-weights for numerical stability
+    let weights = log_weights.iter().map(|w| (*w - max_log_weight).exp()).collect::<Vec<Q>>();
+    let total = weights.iter().fold(Q::zero(), |sum, w| sum + *w);
+    let mut remaining = Q::sample_standard_uniform(false)? * total;
+    Ok(weights.iter().position(|w| {
+        remaining = remaining - *w;
+        remaining < Q::zero()
+    }).unwrap_or_else(|| weights.iter().rposition(|w| *w > Q::zero()).unwrap_or(0)))
+}
+
+/// A [`Measurement`] that selects one of `candidates` with the exponential mechanism.
+///
+/// The input is a vector of scores, one per candidate, as produced by a scoring transformation.
+/// Candidate `i` is selected with probability proportional to `exp(score_i / scale)`.
+/// Since the input distance bounds the change in every score, the mechanism is `2 * d_in / scale`-DP.
+pub fn make_base_exponential_candidates<TC, TS, Q, MI>(
+    candidates: Vec<TC>, scale: Q
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<TC>, MI, MaxDivergence<Q>>>
+    where TC: 'static + Clone,
+          TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float + SampleUniform,
+          MI: ScoreMetric<Distance=Q> {
+    if candidates.is_empty() { return fallible!(MakeMeasurement, "there must be at least one candidate") }
+    if scale.is_nan() || scale <= Q::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    let _2 = num_cast!(2; Q)?;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), candidates.len()),
+        AllDomain::new(),
+        Function::new_fallible(move |scores: &Vec<TS>| {
+            if scores.len() != candidates.len() {
+                return fallible!(FailedFunction, "expected one score per candidate")
+            }
+            let log_weights = scores.iter()
+                .map(|score| Ok(num_cast!(score.clone(); Q)? / scale))
+                .collect::<Fallible<Vec<Q>>>()?;
+            Ok(candidates[sample_log_weighted_index(&log_weights)?].clone())
+        }),
+        MI::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(_2 / scale)))
+}
+
+/// A [`Measurement`] that selects the `k` candidates with the highest scores, by peeling.
+///
+/// The exponential mechanism is applied `k` times at the same `scale`, and each selected candidate is
+/// removed before the next selection. Selections are released in the order they were made.
+/// The budget is the sum over all `k` selections, so the mechanism is `2 * k * d_in / scale`-DP.
+pub fn make_base_exponential_top_k<TC, TS, Q, MI>(
+    candidates: Vec<TC>, k: usize, scale: Q
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, VectorDomain<AllDomain<TC>>, MI, MaxDivergence<Q>>>
+    where TC: 'static + Clone,
+          TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float + SampleUniform,
+          MI: ScoreMetric<Distance=Q> {
+    if k == 0 || k > candidates.len() { return fallible!(MakeMeasurement, "k must be within [1, number of candidates]") }
+    if scale.is_nan() || scale <= Q::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    let _2k = num_cast!(2 * k; Q)?;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), candidates.len()),
+        VectorDomain::new_all(),
+        Function::new_fallible(move |scores: &Vec<TS>| {
+            if scores.len() != candidates.len() {
+                return fallible!(FailedFunction, "expected one score per candidate")
+            }
+            let mut log_weights = scores.iter()
+                .map(|score| Ok(num_cast!(score.clone(); Q)? / scale))
+                .collect::<Fallible<Vec<Q>>>()?;
+            (0..k).map(|_| {
+                let index = sample_log_weighted_index(&log_weights)?;
+                // a weight of zero excludes the candidate from later selections
+                log_weights[index] = Q::neg_infinity();
+                Ok(candidates[index].clone())
+            }).collect()
+        }),
+        MI::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(_2k / scale)))
+}
+
+/// A [`Measurement`] that selects one of `candidates` with the permute-and-flip mechanism.
+///
+/// Candidates are visited in a random order, and candidate `i` is accepted with probability
+/// `exp((score_i - max_score) / scale)`. The first accepted candidate is released.
+/// Permute-and-flip is never worse than the exponential mechanism in expected score,
+/// and has the same privacy relation: it is `2 * d_in / scale`-DP.
+pub fn make_base_permute_and_flip_candidates<TC, TS, Q, MI>(
+    candidates: Vec<TC>, scale: Q
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<TC>, MI, MaxDivergence<Q>>>
+    where TC: 'static + Clone,
+          TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float,
+          MI: ScoreMetric<Distance=Q> {
+    if candidates.is_empty() { return fallible!(MakeMeasurement, "there must be at least one candidate") }
+    if scale.is_nan() || scale <= Q::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    let _2 = num_cast!(2; Q)?;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), candidates.len()),
+        AllDomain::new(),
+        Function::new_fallible(move |scores: &Vec<TS>| {
+            if scores.len() != candidates.len() {
+                return fallible!(FailedFunction, "expected one score per candidate")
+            }
+            let scores = scores.iter()
+                .map(|score| num_cast!(score.clone(); Q))
+                .collect::<Fallible<Vec<Q>>>()?;
+            if scores.iter().any(|s| s.is_nan()) { return fallible!(FailedFunction, "scores may not be nan") }
+            let max_score = scores.iter().cloned().fold(Q::neg_infinity(), Q::max);
+
+            let mut order = (0..candidates.len()).collect::<Vec<usize>>();
+            shuffle(&mut order)?;
+            for i in order {
+                let prob = num_cast!(((scores[i] - max_score) / scale).exp(); f64)?;
+                if bool::sample_bernoulli(prob, false)? { return Ok(candidates[i].clone()) }
+            }
+            // the candidate with the max score is always accepted
+            fallible!(FailedFunction, "no candidate was accepted")
+        }),
+        MI::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(_2 / scale)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::make_chain_mt;
+    use crate::dist::L1Distance;
+    use crate::trans::make_count_by_categories;
+
+    #[test]
+    fn test_make_base_exponential_candidates() -> Fallible<()> {
+        let measurement = make_base_exponential_candidates::<_, f64, f64, L1Distance<f64>>(vec!["a", "b", "c"], 1.)?;
+        assert_eq!(measurement.function.eval(&vec![0., 0., 1000.])?, "c");
+        assert!(measurement.function.eval(&vec![0., 1.]).is_err());
+        assert!(measurement.privacy_relation.eval(&1., &2.)?);
+        assert!(!measurement.privacy_relation.eval(&1., &1.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain_count_exponential() -> Fallible<()> {
+        let count = make_count_by_categories::<L1Distance<f64>, _, i32>(vec!["a", "b"])?;
+        let measurement = make_base_exponential_candidates(vec!["a", "b", "other"], 0.01)?;
+        let chain = make_chain_mt(&measurement, &count, None)?;
+        assert_eq!(chain.function.eval(&vec!["b", "b", "a", "b"])?, "b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_base_exponential_top_k() -> Fallible<()> {
+        let measurement = make_base_exponential_top_k::<_, i32, f64, L1Distance<f64>>(vec!["a", "b", "c", "d"], 2, 0.01)?;
+        assert_eq!(measurement.function.eval(&vec![10, 1000, 0, 500])?, vec!["b", "d"]);
+        // ties are broken at random, but candidates are never selected twice
+        let ret = make_base_exponential_top_k::<_, i32, f64, L1Distance<f64>>(vec![1, 2, 3], 3, 1.)?
+            .function.eval(&vec![0, 0, 0])?;
+        assert!(ret.contains(&1) && ret.contains(&2) && ret.contains(&3));
+
+        // the budget is the sum over both selections
+        assert!(measurement.privacy_relation.eval(&1., &400.)?);
+        assert!(!measurement.privacy_relation.eval(&1., &399.)?);
+        assert!(make_base_exponential_top_k::<_, i32, f64, L1Distance<f64>>(vec![1, 2], 3, 1.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_base_permute_and_flip_candidates() -> Fallible<()> {
+        let measurement = make_base_permute_and_flip_candidates::<_, i32, f64, L1Distance<f64>>(vec!["a", "b", "c"], 1.)?;
+        assert_eq!(measurement.function.eval(&vec![0, 0, 1000])?, "c");
+        assert!(measurement.function.eval(&vec![0, 1]).is_err());
+        // ties at the max are selected uniformly
+        let releases = (0..100).map(|_| measurement.function.eval(&vec![5, 5, -1000]))
+            .collect::<Fallible<Vec<_>>>()?;
+        assert!(releases.contains(&"a") && releases.contains(&"b") && !releases.contains(&"c"));
+        assert!(measurement.privacy_relation.eval(&1., &2.)?);
+        assert!(!measurement.privacy_relation.eval(&1., &1.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_base_exponential_candidates_invalid() {
+        assert!(make_base_exponential_candidates::<i32, f64, f64, L1Distance<f64>>(vec![], 1.).is_err());
+        assert!(make_base_exponential_candidates::<_, f64, f64, L1Distance<f64>>(vec![1], 0.).is_err());
+    }
+}
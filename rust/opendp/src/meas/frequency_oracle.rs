@@ -0,0 +1,162 @@
+use std::hash::Hash;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{DiscreteDistance, MaxDivergence};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::{SampleBernoulli, fill_bytes, sample_uniform_u128_below};
+use crate::traits::stable_hash;
+
+fn check_epsilon(epsilon: f64) -> Fallible<()> {
+    if !(epsilon.is_finite() && epsilon > 0.) { return fallible!(MakeMeasurement, "epsilon must be positive and finite") }
+    Ok(())
+}
+
+// the number of hash buckets that minimizes the variance of the OLH estimator
+fn olh_num_buckets(epsilon: f64) -> Fallible<u64> {
+    num_cast!(epsilon.exp().round(); u64).ok().and_then(|g| g.checked_add(1))
+        .ok_or_else(|| err!(MakeMeasurement, "epsilon is too large for the number of buckets to be represented"))
+}
+
+fn olh_hash<T: Hash>(value: &T, seed: u64, num_buckets: u64) -> u64 {
+    stable_hash(seed, value) % num_buckets
+}
+
+// unbiased count, from the number of reports that support a value,
+// when the true value is supported with probability p, and any other value with probability q
+fn debias_count(support: usize, num_reports: usize, p: f64, q: f64) -> f64 {
+    (support as f64 - num_reports as f64 * q) / (p - q)
+}
+
+/// A [`Measurement`] that randomizes a value with optimized local hashing (OLH).
+///
+/// The value is hashed into `g = round(e^epsilon) + 1` buckets with a freshly sampled seed,
+/// and the bucket is perturbed with `g`-ary randomized response. The release is a `(seed, bucket)` pair,
+/// which can be aggregated with [`estimate_olh_frequencies`]. The mechanism is `d_in * epsilon`-DP.
+pub fn make_olh<T>(
+    epsilon: f64
+) -> Fallible<Measurement<AllDomain<T>, AllDomain<(u64, u64)>, DiscreteDistance, MaxDivergence<f64>>>
+    where T: 'static + Hash {
+    check_epsilon(epsilon)?;
+    let num_buckets = olh_num_buckets(epsilon)?;
+    let prob = epsilon.exp() / (epsilon.exp() + num_buckets as f64 - 1.);
+
+    Ok(Measurement::new(
+        AllDomain::new(),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &T| {
+            let mut buffer = [0u8; 8];
+            fill_bytes(&mut buffer)?;
+            let seed = u64::from_ne_bytes(buffer);
+            let bucket = olh_hash(arg, seed, num_buckets);
+            if bool::sample_bernoulli(prob, false)? { return Ok((seed, bucket)) }
+            // sample among the other g - 1 buckets, skipping over the true bucket
+            let mut other = sample_uniform_u128_below(num_buckets as u128 - 1)? as u64;
+            if other >= bucket { other += 1 }
+            Ok((seed, other))
+        }),
+        DiscreteDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+/// Estimate the number of reports from [`make_olh`] that were made for each of the `candidates`.
+/// The estimates are unbiased, so they may be negative.
+pub fn estimate_olh_frequencies<T: Hash>(
+    reports: &[(u64, u64)], candidates: &[T], epsilon: f64
+) -> Fallible<Vec<f64>> {
+    check_epsilon(epsilon)?;
+    let num_buckets = olh_num_buckets(epsilon)?;
+    let p = epsilon.exp() / (epsilon.exp() + num_buckets as f64 - 1.);
+    let q = 1. / num_buckets as f64;
+    Ok(candidates.iter().map(|candidate| {
+        let support = reports.iter()
+            .filter(|(seed, bucket)| olh_hash(candidate, *seed, num_buckets) == *bucket)
+            .count();
+        debias_count(support, reports.len(), p, q)
+    }).collect())
+}
+
+/// A [`Measurement`] that randomizes a member of `categories` with optimized unary encoding (OUE).
+///
+/// The value is one-hot encoded over `categories`. The set bit is kept with probability 1/2,
+/// and each unset bit is flipped with probability `1 / (e^epsilon + 1)`.
+/// Reports can be aggregated with [`estimate_oue_frequencies`]. The mechanism is `d_in * epsilon`-DP.
+/// Inputs outside of `categories` are rejected when the measurement is invoked.
+pub fn make_oue<T>(
+    categories: Vec<T>, epsilon: f64
+) -> Fallible<Measurement<AllDomain<T>, VectorDomain<AllDomain<bool>>, DiscreteDistance, MaxDivergence<f64>>>
+    where T: 'static + PartialEq {
+    check_epsilon(epsilon)?;
+    if categories.is_empty() { return fallible!(MakeMeasurement, "there must be at least one category") }
+    let flip_prob = 1. / (epsilon.exp() + 1.);
+
+    Ok(Measurement::new(
+        AllDomain::new(),
+        VectorDomain::new_all(),
+        Function::new_fallible(move |arg: &T| {
+            let index = categories.iter().position(|cat| cat == arg)
+                .ok_or_else(|| err!(FailedFunction, "input is not one of the categories"))?;
+            (0..categories.len())
+                .map(|i| bool::sample_bernoulli(if i == index { 0.5 } else { flip_prob }, false))
+                .collect()
+        }),
+        DiscreteDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+/// Estimate the number of reports from [`make_oue`] that were made for each category.
+/// The estimates are unbiased, so they may be negative.
+pub fn estimate_oue_frequencies(reports: &[Vec<bool>], epsilon: f64) -> Fallible<Vec<f64>> {
+    check_epsilon(epsilon)?;
+    let num_categories = reports.first().map(|r| r.len()).unwrap_or(0);
+    if reports.iter().any(|r| r.len() != num_categories) {
+        return fallible!(FailedFunction, "all reports must have the same length")
+    }
+    let q = 1. / (epsilon.exp() + 1.);
+    Ok((0..num_categories)
+        .map(|i| debias_count(reports.iter().filter(|r| r[i]).count(), reports.len(), 0.5, q))
+        .collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_olh() -> Fallible<()> {
+        let measurement = make_olh::<&str>(2.)?;
+        let data = [vec!["a"; 600], vec!["b"; 400]].concat();
+        let reports = data.iter().map(|v| measurement.function.eval(v)).collect::<Fallible<Vec<_>>>()?;
+        let estimates = estimate_olh_frequencies(&reports, &["a", "b", "c"], 2.)?;
+        assert!((estimates[0] - 600.).abs() < 150., "estimates were {:?}", estimates);
+        assert!((estimates[1] - 400.).abs() < 150., "estimates were {:?}", estimates);
+        assert!(estimates[2].abs() < 150., "estimates were {:?}", estimates);
+
+        assert!(measurement.privacy_relation.eval(&1, &2.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &1.9)?);
+
+        // e^50 buckets cannot be counted in a u64
+        assert!(make_olh::<&str>(40.)?.function.eval(&"a").is_ok());
+        assert!(make_olh::<&str>(50.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_oue() -> Fallible<()> {
+        let measurement = make_oue(vec!["a", "b", "c"], 2.)?;
+        assert!(measurement.function.eval(&"d").is_err());
+        let data = [vec!["a"; 600], vec!["b"; 400]].concat();
+        let reports = data.iter().map(|v| measurement.function.eval(v)).collect::<Fallible<Vec<_>>>()?;
+        let estimates = estimate_oue_frequencies(&reports, 2.)?;
+        assert!((estimates[0] - 600.).abs() < 150., "estimates were {:?}", estimates);
+        assert!((estimates[1] - 400.).abs() < 150., "estimates were {:?}", estimates);
+        assert!(estimates[2].abs() < 150., "estimates were {:?}", estimates);
+
+        assert!(measurement.privacy_relation.eval(&1, &2.)?);
+        assert!(make_oue(vec!["a"], f64::INFINITY).is_err());
+        Ok(())
+    }
+}
@@ -1,19 +1,52 @@
 use num::Float;
+use statrs::function::erf;
 
+use crate::accuracy::gaussian_confidence_interval;
 use crate::core::{Function, Measurement, PrivacyRelation, Domain, SensitivityMetric};
-use crate::dist::{L2Distance, SmoothedMaxDivergence, AbsoluteDistance};
+use crate::dist::{L2Distance, SmoothedMaxDivergence, AbsoluteDistance, ZeroConcentratedDivergence};
 use crate::dom::{AllDomain, VectorDomain};
 use crate::error::*;
 use crate::samplers::SampleGaussian;
 
-// const ADDITIVE_GAUSS_CONST: f64 = 8. / 9. + (2. / PI).ln();
-const ADDITIVE_GAUSS_CONST: f64 = 0.4373061836;
+/// Standard normal CDF.
+fn phi(x: f64) -> f64 {
+    0.5 * erf::erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// The smallest delta for which Gaussian noise with `scale` is (`eps`, delta)-DP on `sensitivity`-close inputs.
+/// This is the exact characterization of the analytic Gaussian mechanism (Balle & Wang, 2018, Theorem 8).
+pub(crate) fn analytic_gaussian_delta(sensitivity: f64, scale: f64, eps: f64) -> f64 {
+    let a = sensitivity / (2. * scale);
+    let b = eps * scale / sensitivity;
+    phi(a - b) - eps.exp() * phi(-a - b)
+}
+
+/// The smallest Gaussian scale that satisfies (`eps`, `del`)-DP on `sensitivity`-close inputs, under the analytic Gaussian mechanism.
+/// The scale is found by bisection, and is rounded up so that the returned scale always satisfies the relation.
+pub fn analytic_gaussian_scale(sensitivity: f64, eps: f64, del: f64) -> Fallible<f64> {
+    if !sensitivity.is_finite() || sensitivity.is_sign_negative() {
+        return fallible!(InvalidDistance, "sensitivity must be non-negative and finite")
+    }
+    if eps.is_nan() || eps <= 0. { return fallible!(InvalidDistance, "epsilon must be positive") }
+    if del.is_nan() || del <= 0. { return fallible!(InvalidDistance, "delta must be positive") }
+    if sensitivity == 0. { return Ok(0.) }
+
+    let satisfies = |scale: f64| analytic_gaussian_delta(sensitivity, scale, eps) <= del;
+    let mut upper = sensitivity;
+    while !satisfies(upper) {
+        upper *= 2.;
+        if !upper.is_finite() { return fallible!(FailedRelation, "no finite scale satisfies the budget") }
+    }
+    let mut lower = 0.;
+    for _ in 0..100 {
+        let mid = lower + (upper - lower) / 2.;
+        if satisfies(mid) { upper = mid } else { lower = mid }
+    }
+    Ok(upper)
+}
 
 fn make_gaussian_privacy_relation<T: 'static + Clone + SampleGaussian + Float, MI: SensitivityMetric<Distance=T>>(scale: T) -> PrivacyRelation<MI, SmoothedMaxDivergence<T>> {
     PrivacyRelation::new_fallible(move |&d_in: &T, &(eps, del): &(T, T)| {
-        let _2 = num_cast!(2.; T)?;
-        let additive_gauss_const = num_cast!(ADDITIVE_GAUSS_CONST; T)?;
-
         if d_in.is_sign_negative() {
             return fallible!(InvalidDistance, "gaussian mechanism: input sensitivity must be non-negative")
         }
@@ -23,9 +56,13 @@ fn make_gaussian_privacy_relation<T: 'static + Clone + SampleGaussian + Float, M
         if del.is_sign_negative() || del.is_zero() {
             return fallible!(InvalidDistance, "gaussian mechanism: delta must be positive")
         }
+        if d_in.is_zero() { return Ok(true) }
+        if scale.is_zero() { return Ok(false) }
 
-        // TODO: should we error if epsilon > 1., or just waste the budget?
-        Ok(eps.min(T::one()) >= (d_in / scale) * (additive_gauss_const + _2 * del.recip().ln()).sqrt())
+        let delta = analytic_gaussian_delta(
+            num_cast!(d_in; f64)?, num_cast!(scale; f64)?, num_cast!(eps; f64)?);
+        // a nan delta fails the relation
+        Ok(delta <= num_cast!(del; f64)?)
     })
 }
 
@@ -63,6 +100,12 @@ impl<T> GaussianDomain for VectorDomain<AllDomain<T>>
 }
 
 
+/// A [`Measurement`] that adds noise from the Gaussian(`scale`) distribution to the input.
+/// The privacy relation is the exact analytic Gaussian characterization, which is tighter than the classical
+/// `scale >= d_in * sqrt(2 ln(1.25 / delta)) / epsilon` bound and holds for any epsilon.
+/// See [`analytic_gaussian_scale`] to calibrate the scale to a budget.
+/// Use `VectorDomain<AllDomain<T>>` to add independent noise to each element,
+/// where `d_in` is the L2 norm of the coordinate-wise differences between neighboring vectors.
 pub fn make_base_gaussian<D>(scale: D::Atom) -> Fallible<Measurement<D, D, D::Metric, SmoothedMaxDivergence<D::Atom>>>
     where D: GaussianDomain,
           D::Atom: 'static + Clone + SampleGaussian + Float {
@@ -76,7 +119,34 @@ pub fn make_base_gaussian<D>(scale: D::Atom) -> Fallible<Measurement<D, D, D::Me
         D::Metric::default(),
         SmoothedMaxDivergence::default(),
         make_gaussian_privacy_relation(scale),
-    ))
+    ).with_confidence_interval(move |released, alpha|
+        gaussian_confidence_interval(num_cast!(scale; f64)?, released, alpha)))
+}
+
+/// A [`Measurement`] that adds noise from the Gaussian(`scale`) distribution to the input, with privacy measured in zCDP.
+/// Satisfies `d_out`-zCDP for `d_in`-sensitive inputs when `d_out >= d_in^2 / (2 scale^2)`.
+/// Unlike (epsilon, delta), rho adds exactly under composition.
+pub fn make_base_gaussian_zcdp<D>(scale: D::Atom) -> Fallible<Measurement<D, D, D::Metric, ZeroConcentratedDivergence<D::Atom>>>
+    where D: GaussianDomain,
+          D::Atom: 'static + Clone + SampleGaussian + Float {
+    if scale.is_nan() || scale.is_sign_negative() {
+        return fallible!(MakeMeasurement, "scale must not be negative")
+    }
+    let _2 = num_cast!(2; D::Atom)?;
+    Ok(Measurement::new(
+        D::new(),
+        D::new(),
+        D::noise_function(scale),
+        D::Metric::default(),
+        ZeroConcentratedDivergence::default(),
+        PrivacyRelation::new_fallible(move |&d_in: &D::Atom, &d_out: &D::Atom| {
+            if d_in.is_sign_negative() {
+                return fallible!(InvalidDistance, "gaussian mechanism: input sensitivity must be non-negative")
+            }
+            Ok(d_out >= d_in.powi(2) / (_2 * scale.powi(2)))
+        })
+    ).with_confidence_interval(move |released, alpha|
+        gaussian_confidence_interval(num_cast!(scale; f64)?, released, alpha)))
 }
 
 #[cfg(test)]
@@ -93,6 +163,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_make_gaussian_relation() -> Fallible<()> {
+        let (scale, d_in, del): (f64, f64, f64) = (1.0, 0.1, 1e-5);
+        let measurement = make_base_gaussian::<AllDomain<_>>(scale)?;
+        // epsilon from the classical bound is sufficient
+        let eps = d_in * (2. * (1.25 / del).ln()).sqrt() / scale;
+        assert!(measurement.privacy_relation.eval(&d_in, &(eps, del))?);
+        assert!(!measurement.privacy_relation.eval(&d_in, &(eps / 2., del))?);
+        assert!(measurement.privacy_relation.eval(&d_in, &(eps, 0.)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_analytic_gaussian_scale() -> Fallible<()> {
+        let (d_in, eps, del) = (1., 0.1, 1e-5);
+        let scale = analytic_gaussian_scale(d_in, eps, del)?;
+        // the analytic scale is smaller than the classical scale at small epsilon
+        let classical_scale = d_in * (2. * (1.25 / del).ln()).sqrt() / eps;
+        assert!(scale < classical_scale);
+
+        let measurement = make_base_gaussian::<AllDomain<_>>(scale)?;
+        assert!(measurement.privacy_relation.eval(&d_in, &(eps, del))?);
+        let measurement = make_base_gaussian::<AllDomain<_>>(scale * 0.99)?;
+        assert!(!measurement.privacy_relation.eval(&d_in, &(eps, del))?);
+        Ok(())
+    }
+
     #[test]
     fn test_make_gaussian_vec_mechanism() -> Fallible<()> {
         let measurement = make_base_gaussian::<VectorDomain<_>>(1.0)?;
@@ -102,4 +199,33 @@ mod tests {
         assert!(measurement.privacy_relation.eval(&0.1, &(0.5, 0.00001))?);
         Ok(())
     }
+
+    #[test]
+    fn test_make_gaussian_vec_relation() -> Fallible<()> {
+        let (d_in, eps, del) = (5., 1., 1e-6);
+        let scale = analytic_gaussian_scale(d_in, eps, del)?;
+        let measurement = make_base_gaussian::<VectorDomain<_>>(scale)?;
+        let noisy = measurement.function.eval(&vec![0.0; 100])?;
+        assert!(noisy.iter().any(|v| *v != noisy[0]));
+
+        // the budget depends on the L2 norm, which is 5 for a change of (3, 4) in two coordinates
+        assert!(measurement.privacy_relation.eval(&d_in, &(eps, del))?);
+        assert!(!measurement.privacy_relation.eval(&(3. + 4.), &(eps, del))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_gaussian_zcdp() -> Fallible<()> {
+        let measurement = make_base_gaussian_zcdp::<AllDomain<_>>(2.)?;
+        let _ret = measurement.function.eval(&0.)?;
+        // rho = d_in^2 / (2 scale^2)
+        assert!(measurement.privacy_relation.eval(&2., &0.5)?);
+        assert!(!measurement.privacy_relation.eval(&2., &0.49)?);
+
+        let measurement = make_base_gaussian_zcdp::<VectorDomain<_>>(1.)?;
+        assert_eq!(measurement.function.eval(&vec![0., 1.])?.len(), 2);
+        assert!(measurement.privacy_relation.eval(&1., &0.5)?);
+        assert!(make_base_gaussian_zcdp::<AllDomain<f64>>(-1.).is_err());
+        Ok(())
+    }
 }
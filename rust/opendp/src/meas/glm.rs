@@ -0,0 +1,294 @@
+//! Differentially private generalized linear models.
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{HammingDistance, MaxDivergence};
+use crate::dom::{AllDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::{SampleGaussian, SampleUniform};
+use crate::trans::clamp::clamp_l2_norm;
+
+// the second derivative of the logistic loss is at most 1/4
+const LOGISTIC_CURVATURE: f64 = 0.25;
+const MAX_ITERATIONS: usize = 10_000;
+
+fn dot(left: &[f64], right: &[f64]) -> f64 {
+    left.iter().zip(right.iter()).map(|(l, r)| l * r).sum()
+}
+
+fn norm(vector: &[f64]) -> f64 {
+    dot(vector, vector).sqrt()
+}
+
+// a vector whose density is proportional to exp(-||b|| / scale):
+//     a uniformly random direction, with a Gamma(dim, scale) norm
+fn sample_norm_laplace(dim: usize, scale: f64) -> Fallible<Vec<f64>> {
+    let direction = (0..dim).map(|_| f64::sample_gaussian(0., 1., false)).collect::<Fallible<Vec<f64>>>()?;
+    let length = (0..dim)
+        .map(|_| Ok(-scale * (1. - f64::sample_standard_uniform(false)?).ln()))
+        .sum::<Fallible<f64>>()?;
+    let direction_norm = norm(&direction);
+    Ok(direction.iter().map(|v| v / direction_norm * length).collect())
+}
+
+// minimize the perturbed objective by gradient descent, with a step size of 1 / smoothness
+fn minimize_logistic(
+    data: &[(Vec<f64>, bool)], regularization: f64, noise: &[f64], smoothness: f64
+) -> Vec<f64> {
+    let n = data.len() as f64;
+    let mut coefficients = vec![0.; noise.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut gradient = coefficients.iter().zip(noise.iter())
+            .map(|(w, b)| regularization * w + b / n)
+            .collect::<Vec<f64>>();
+        data.iter().for_each(|(x, y)| {
+            let label = if *y { 1. } else { -1. };
+            // derivative of ln(1 + exp(-z)) at z = label * <w, x>
+            let weight = -label / (1. + (label * dot(&coefficients, x)).exp()) / n;
+            gradient.iter_mut().zip(x.iter()).for_each(|(g, v)| *g += weight * v);
+        });
+        if norm(&gradient) < 1e-10 { break }
+        coefficients.iter_mut().zip(gradient.iter()).for_each(|(w, g)| *w -= g / smoothness);
+    }
+    coefficients
+}
+
+// minimize the perturbed objective by projected gradient descent onto the ball of radius `bound`,
+// with a step size of 1 / smoothness
+fn minimize_linear(
+    data: &[(Vec<f64>, f64)], regularization: f64, noise: &[f64], smoothness: f64, bound: f64
+) -> Vec<f64> {
+    let n = data.len() as f64;
+    let mut coefficients = vec![0.; noise.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut gradient = coefficients.iter().zip(noise.iter())
+            .map(|(w, b)| regularization * w + b / n)
+            .collect::<Vec<f64>>();
+        data.iter().for_each(|(x, y)| {
+            // derivative of (y - <w, x>)^2 / 2
+            let weight = (dot(&coefficients, x) - y) / n;
+            gradient.iter_mut().zip(x.iter()).for_each(|(g, v)| *g += weight * v);
+        });
+        let step = coefficients.iter().zip(gradient.iter()).map(|(w, g)| w - g / smoothness).collect::<Vec<f64>>();
+        let step = clamp_l2_norm(&step, bound);
+        let change = norm(&step.iter().zip(coefficients.iter()).map(|(s, w)| s - w).collect::<Vec<f64>>());
+        coefficients = step;
+        if change < 1e-12 { break }
+    }
+    coefficients
+}
+
+/// A [`Measurement`] that fits an L2-regularized logistic regression by objective perturbation
+/// (Chaudhuri, Monteleoni and Sarwate, 2011, Algorithm 2).
+///
+/// Each of the `n` records is a feature vector with `num_features` features and a boolean label.
+/// Feature vectors are clipped to L2 norm `clip_norm`. The released coefficients minimize
+/// `mean(logistic loss) + regularization / 2 * ||w||^2 + <b, w> / n`, where `b` is random with density proportional to
+/// `exp(-epsilon' ||b|| / (2 clip_norm))`. When `regularization` is too small for the budget, the regularization is
+/// increased and `epsilon' = epsilon / 2`. There is no intercept; add a constant feature to fit one.
+/// The mechanism is `d_in * epsilon`-DP, where `d_in` is the number of records that differ.
+///
+/// The guarantee assumes that the released coefficients are the exact minimizer. They are computed by gradient descent,
+/// which stops after a fixed number of steps or once the gradient is small, so the release is only approximately
+/// the minimizer covered by the analysis.
+pub fn make_objective_perturbation_logistic(
+    n: usize, num_features: usize, clip_norm: f64, regularization: f64, epsilon: f64
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<(Vec<f64>, bool)>>>, AllDomain<Vec<f64>>, HammingDistance, MaxDivergence<f64>>> {
+    if n == 0 || num_features == 0 { return fallible!(MakeMeasurement, "n and num_features must be positive") }
+    if !(clip_norm.is_finite() && clip_norm > 0.) { return fallible!(MakeMeasurement, "clip_norm must be positive and finite") }
+    if !(regularization.is_finite() && regularization > 0.) { return fallible!(MakeMeasurement, "regularization must be positive and finite") }
+    if !(epsilon.is_finite() && epsilon > 0.) { return fallible!(MakeMeasurement, "epsilon must be positive and finite") }
+
+    // the curvature of the loss, with features scaled by the clipping norm
+    let curvature = LOGISTIC_CURVATURE * clip_norm.powi(2);
+    let ratio = curvature / (n as f64 * regularization);
+    let mut epsilon_noise = epsilon - (1. + 2. * ratio + ratio.powi(2)).ln();
+    let mut extra_regularization = 0.;
+    if epsilon_noise <= 0. {
+        extra_regularization = curvature / (n as f64 * ((epsilon / 4.).exp() - 1.)) - regularization;
+        epsilon_noise = epsilon / 2.;
+    }
+    let total_regularization = regularization + extra_regularization;
+    let noise_scale = 2. * clip_norm / epsilon_noise;
+    let smoothness = curvature + total_regularization;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), n),
+        AllDomain::new(),
+        Function::new_fallible(move |data: &Vec<(Vec<f64>, bool)>| {
+            if data.len() != n { return fallible!(FailedFunction, "expected {} records", n) }
+            if data.iter().any(|(x, _)| x.len() != num_features || x.iter().any(|v| !v.is_finite())) {
+                return fallible!(FailedFunction, "each record must have {} finite features", num_features)
+            }
+            let data = data.iter().map(|(x, y)| (clamp_l2_norm(x, clip_norm), *y)).collect::<Vec<_>>();
+            let noise = sample_norm_laplace(num_features, noise_scale)?;
+            Ok(minimize_logistic(&data, total_regularization, &noise, smoothness))
+        }),
+        HammingDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+/// A [`Measurement`] that fits an L2-regularized linear regression by objective perturbation
+/// (Kifer, Smith and Thakurta, 2012).
+///
+/// Each of the `n` records is a feature vector with `num_features` features and a real label.
+/// Feature vectors are clipped to L2 norm `clip_norm` and labels are clamped to `[-label_bound, label_bound]`.
+/// The released coefficients minimize `mean((y - <w, x>)^2 / 2) + regularization / 2 * ||w||^2 + <b, w> / n`
+/// over coefficients with L2 norm at most `coefficient_bound`, which keeps the squared loss Lipschitz.
+/// The loss has curvature at most `clip_norm^2` and gradient norm at most `zeta = clip_norm * (label_bound + coefficient_bound * clip_norm)`
+/// on this ball. Following the constrained analysis, the regularization is increased by `2 clip_norm^2 / (n epsilon)`
+/// and `b` is random with density proportional to `exp(-epsilon ||b|| / (2 zeta))`.
+/// There is no intercept; add a constant feature to fit one.
+/// The mechanism is `d_in * epsilon`-DP, where `d_in` is the number of records that differ.
+///
+/// The guarantee assumes that the released coefficients are the exact constrained minimizer. They are computed by
+/// projected gradient descent, which stops after a fixed number of steps or once the steps are small, so the release
+/// is only approximately the minimizer covered by the analysis.
+pub fn make_objective_perturbation_linear(
+    n: usize, num_features: usize, clip_norm: f64, label_bound: f64, coefficient_bound: f64, regularization: f64, epsilon: f64
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<(Vec<f64>, f64)>>>, AllDomain<Vec<f64>>, HammingDistance, MaxDivergence<f64>>> {
+    if n == 0 || num_features == 0 { return fallible!(MakeMeasurement, "n and num_features must be positive") }
+    if !(clip_norm.is_finite() && clip_norm > 0.) { return fallible!(MakeMeasurement, "clip_norm must be positive and finite") }
+    if !(label_bound.is_finite() && label_bound > 0.) { return fallible!(MakeMeasurement, "label_bound must be positive and finite") }
+    if !(coefficient_bound.is_finite() && coefficient_bound > 0.) { return fallible!(MakeMeasurement, "coefficient_bound must be positive and finite") }
+    if !(regularization.is_finite() && regularization > 0.) { return fallible!(MakeMeasurement, "regularization must be positive and finite") }
+    if !(epsilon.is_finite() && epsilon > 0.) { return fallible!(MakeMeasurement, "epsilon must be positive and finite") }
+
+    // the Hessian of the squared loss is x x^T, whose eigenvalues are at most ||x||^2
+    let curvature = clip_norm.powi(2);
+    let lipschitz = clip_norm * (label_bound + coefficient_bound * clip_norm);
+    // Kifer, Smith and Thakurta, 2012, Theorem 2: in the sum form, the extra regularization is 2 * curvature / epsilon,
+    // and half of the budget is spent on noise
+    let total_regularization = regularization + 2. * curvature / (n as f64 * epsilon);
+    let noise_scale = 2. * lipschitz / epsilon;
+    let smoothness = curvature + total_regularization;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), n),
+        AllDomain::new(),
+        Function::new_fallible(move |data: &Vec<(Vec<f64>, f64)>| {
+            if data.len() != n { return fallible!(FailedFunction, "expected {} records", n) }
+            if data.iter().any(|(x, y)| x.len() != num_features || x.iter().any(|v| !v.is_finite()) || y.is_nan()) {
+                return fallible!(FailedFunction, "each record must have {} finite features and a label", num_features)
+            }
+            let data = data.iter()
+                .map(|(x, y)| (clamp_l2_norm(x, clip_norm), y.clamp(-label_bound, label_bound)))
+                .collect::<Vec<_>>();
+            let noise = sample_norm_laplace(num_features, noise_scale)?;
+            Ok(minimize_linear(&data, total_regularization, &noise, smoothness, coefficient_bound))
+        }),
+        HammingDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+/// A [`Measurement`] that releases the parameters returned by a non-private `solver`, perturbed with noise.
+///
+/// `solver` must minimize a regularized empirical risk over `n` records, where the loss is `lipschitz`-Lipschitz
+/// in the parameters and the objective is `regularization`-strongly convex. Under these conditions the minimizer has
+/// L2 sensitivity `2 * lipschitz / (n * regularization)` (Chaudhuri, Monteleoni and Sarwate, 2011).
+/// The noise vector has density proportional to `exp(-||b|| / scale)`, so the mechanism is `d_in * sensitivity / scale`-DP.
+pub fn make_output_perturbation<T, F>(
+    n: usize, num_params: usize, solver: F, lipschitz: f64, regularization: f64, scale: f64
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<T>>>, AllDomain<Vec<f64>>, HammingDistance, MaxDivergence<f64>>>
+    where T: 'static + Clone,
+          F: 'static + Fn(&Vec<T>) -> Fallible<Vec<f64>> {
+    if n == 0 || num_params == 0 { return fallible!(MakeMeasurement, "n and num_params must be positive") }
+    if !(lipschitz.is_finite() && lipschitz > 0.) { return fallible!(MakeMeasurement, "lipschitz must be positive and finite") }
+    if !(regularization.is_finite() && regularization > 0.) { return fallible!(MakeMeasurement, "regularization must be positive and finite") }
+    if !(scale.is_finite() && scale > 0.) { return fallible!(MakeMeasurement, "scale must be positive and finite") }
+    let sensitivity = 2. * lipschitz / (n as f64 * regularization);
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), n),
+        AllDomain::new(),
+        Function::new_fallible(move |data: &Vec<T>| {
+            if data.len() != n { return fallible!(FailedFunction, "expected {} records", n) }
+            let params = solver(data)?;
+            if params.len() != num_params { return fallible!(FailedFunction, "solver must return {} parameters", num_params) }
+            let noise = sample_norm_laplace(num_params, scale)?;
+            Ok(params.iter().zip(noise.iter()).map(|(p, b)| p + b).collect())
+        }),
+        HammingDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(sensitivity / scale)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_objective_perturbation_logistic() -> Fallible<()> {
+        // the label is the sign of the first feature
+        let data = (0..1000).map(|i| {
+            let x = (i % 20) as f64 / 10. - 0.95;
+            (vec![x, 1.], x > 0.)
+        }).collect::<Vec<_>>();
+        let measurement = make_objective_perturbation_logistic(1000, 2, 1., 0.01, 10.)?;
+        let coefficients = measurement.function.eval(&data)?;
+        assert_eq!(coefficients.len(), 2);
+        assert!(coefficients[0] > 1., "coefficients were {:?}", coefficients);
+        assert!(coefficients[0] > coefficients[1].abs(), "coefficients were {:?}", coefficients);
+
+        assert!(measurement.privacy_relation.eval(&1, &10.)?);
+        assert!(!measurement.privacy_relation.eval(&2, &10.)?);
+        assert!(measurement.function.eval(&data[..10].to_vec()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_objective_perturbation_logistic_small_regularization() -> Fallible<()> {
+        // the budget cannot absorb the curvature, so the regularization is increased
+        let measurement = make_objective_perturbation_logistic(10, 1, 1., 1e-6, 0.1)?;
+        let coefficients = measurement.function.eval(&vec![(vec![1.], true); 10])?;
+        assert!(coefficients[0].is_finite());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_objective_perturbation_linear() -> Fallible<()> {
+        // the label is half of the first feature, plus an outlier that is clamped
+        let mut data = (0..1000).map(|i| {
+            let x = (i % 20) as f64 / 10. - 0.95;
+            (vec![x, 1.], x / 2.)
+        }).collect::<Vec<_>>();
+        data[0].1 = 1e6;
+        let measurement = make_objective_perturbation_linear(1000, 2, 2., 1., 2., 1e-4, 100.)?;
+        let coefficients = measurement.function.eval(&data)?;
+        assert_eq!(coefficients.len(), 2);
+        assert!((coefficients[0] - 0.5).abs() < 0.05, "coefficients were {:?}", coefficients);
+        assert!(coefficients[1].abs() < 0.05, "coefficients were {:?}", coefficients);
+
+        assert!(measurement.privacy_relation.eval(&1, &100.)?);
+        assert!(!measurement.privacy_relation.eval(&2, &100.)?);
+        assert!(measurement.function.eval(&data[..10].to_vec()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_objective_perturbation_linear_bounded() -> Fallible<()> {
+        // the unconstrained fit is w = 10, but the coefficients are confined to the unit ball
+        let measurement = make_objective_perturbation_linear(10, 1, 1., 10., 1., 1e-6, 0.1)?;
+        let coefficients = measurement.function.eval(&vec![(vec![1.], 10.); 10])?;
+        assert!(coefficients[0].abs() <= 1. + 1e-12, "coefficients were {:?}", coefficients);
+        assert!(make_objective_perturbation_linear(10, 1, 1., 0., 1., 1., 1.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_output_perturbation() -> Fallible<()> {
+        // ridge regression of a mean: minimizes mean((w - x)^2 / 2) + w^2 / 2
+        let solver = |data: &Vec<f64>| Ok(vec![data.iter().sum::<f64>() / data.len() as f64 / 2.]);
+        let measurement = make_output_perturbation(100, 1, solver, 1., 1., 1e-6)?;
+        let params = measurement.function.eval(&vec![1.; 100])?;
+        assert!((params[0] - 0.5).abs() < 1e-3, "params were {:?}", params);
+
+        // the sensitivity is 2 / (100 * 1) = 0.02
+        assert!(measurement.privacy_relation.eval(&1, &20_000.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &19_000.)?);
+        assert!(make_output_perturbation(100, 1, solver, 1., 0., 1.).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use num::ToPrimitive;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{L1Distance, MaxDivergence};
+use crate::dom::{AllDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::SampleLaplace;
+
+/// Noisy counts at every node of a tree over the bins of a histogram, made consistent so that
+/// the count at each node is the sum of the counts at its children.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HierarchicalHistogram {
+    branching: usize,
+    num_bins: usize,
+    /// The counts at each level of the tree, from the root to the leaves.
+    levels: Vec<Vec<f64>>,
+}
+
+impl HierarchicalHistogram {
+    /// The estimated count of the bins in `[lower, upper)`.
+    pub fn range_count(&self, lower: usize, upper: usize) -> Fallible<f64> {
+        if lower > upper || upper > self.num_bins {
+            return fallible!(FailedFunction, "range must be within [0, {}]", self.num_bins)
+        }
+        Ok(self.range_count_at(0, 0, lower, upper))
+    }
+
+    // sum over the fewest nodes that cover the range, starting at `node` on `level`
+    fn range_count_at(&self, level: usize, node: usize, lower: usize, upper: usize) -> f64 {
+        let width = self.branching.pow((self.levels.len() - 1 - level) as u32);
+        let (start, end) = (node * width, (node + 1) * width);
+        if upper <= start || end <= lower { return 0. }
+        if lower <= start && end <= upper { return self.levels[level][node] }
+        (0..self.branching)
+            .map(|child| self.range_count_at(level + 1, node * self.branching + child, lower, upper))
+            .sum()
+    }
+
+    /// The estimated count of each bin.
+    pub fn bin_counts(&self) -> Vec<f64> {
+        self.levels[self.levels.len() - 1][..self.num_bins].to_vec()
+    }
+}
+
+/// Make noisy tree counts consistent, by the least-squares estimator of Hay et al. (2010).
+/// `levels` holds the counts at each level of a complete `branching`-ary tree, from the root to the leaves.
+fn make_consistent(levels: Vec<Vec<f64>>, branching: usize) -> Vec<Vec<f64>> {
+    let height = levels.len();
+    let b = branching as f64;
+
+    // bottom-up: weighted average of each node and the sum of its children
+    let mut z = levels;
+    for level in (0..height - 1).rev() {
+        // a node at this level is the root of a subtree with this many levels
+        let l = (height - level) as i32;
+        let weight = (b.powi(l) - b.powi(l - 1)) / (b.powi(l) - 1.);
+        for node in 0..z[level].len() {
+            let children = z[level + 1][node * branching..(node + 1) * branching].iter().sum::<f64>();
+            z[level][node] = weight * z[level][node] + (1. - weight) * children;
+        }
+    }
+
+    // top-down: distribute the difference between each parent and the sum of its children evenly
+    let mut consistent = z.clone();
+    for level in 1..height {
+        for parent in 0..consistent[level - 1].len() {
+            let children = parent * branching..(parent + 1) * branching;
+            let gap = (consistent[level - 1][parent] - z[level][children.clone()].iter().sum::<f64>()) / b;
+            children.for_each(|child| consistent[level][child] = z[level][child] + gap);
+        }
+    }
+    consistent
+}
+
+/// A [`Measurement`] that releases a [`HierarchicalHistogram`] over a histogram of `num_bins` counts.
+///
+/// The bins are the leaves of a complete `branching`-ary tree with `L` levels, padded with empty bins.
+/// Each node is released with Laplace(`scale`) noise, and the noisy counts are post-processed to be consistent.
+/// Each bin is in one node at each level, so the mechanism is `d_in * L / scale`-DP.
+pub fn make_hierarchical_histogram<TS>(
+    num_bins: usize, branching: usize, scale: f64
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<HierarchicalHistogram>, L1Distance<f64>, MaxDivergence<f64>>>
+    where TS: 'static + Clone + ToPrimitive {
+    if num_bins == 0 { return fallible!(MakeMeasurement, "there must be at least one bin") }
+    if branching < 2 { return fallible!(MakeMeasurement, "branching must be at least 2") }
+    if scale.is_nan() || scale <= 0. { return fallible!(MakeMeasurement, "scale must be positive") }
+
+    let mut num_leaves = 1usize;
+    let mut height = 1;
+    while num_leaves < num_bins {
+        num_leaves = num_leaves.checked_mul(branching)
+            .ok_or_else(|| err!(MakeMeasurement, "the tree is too large"))?;
+        height += 1;
+    }
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), num_bins),
+        AllDomain::new(),
+        Function::new_fallible(move |counts: &Vec<TS>| {
+            if counts.len() != num_bins { return fallible!(FailedFunction, "expected {} bins", num_bins) }
+            let mut leaves = counts.iter()
+                .map(|c| num_cast!(c.clone(); f64))
+                .collect::<Fallible<Vec<f64>>>()?;
+            leaves.resize(num_leaves, 0.);
+
+            // exact counts at each level, from the leaves up to the root
+            let mut levels = vec![leaves];
+            while levels[0].len() > 1 {
+                let parents = levels[0].chunks(branching).map(|c| c.iter().sum()).collect();
+                levels.insert(0, parents);
+            }
+            let noisy = levels.into_iter()
+                .map(|level| level.into_iter().map(|c| f64::sample_laplace(c, scale, false)).collect())
+                .collect::<Fallible<Vec<Vec<f64>>>>()?;
+
+            Ok(HierarchicalHistogram { branching, num_bins, levels: make_consistent(noisy, branching) })
+        }),
+        L1Distance::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(height as f64 / scale)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_hierarchical_histogram() -> Fallible<()> {
+        let measurement = make_hierarchical_histogram(5, 2, 1e-6)?;
+        let histogram = measurement.function.eval(&vec![1, 2, 3, 4, 5])?;
+        assert!((histogram.range_count(1, 4)? - 9.).abs() < 1e-3);
+        assert!((histogram.range_count(0, 5)? - 15.).abs() < 1e-3);
+        assert_eq!(histogram.range_count(2, 2)?, 0.);
+        assert!(histogram.range_count(0, 6).is_err());
+        assert_eq!(histogram.bin_counts().len(), 5);
+
+        // 5 bins are padded to 8 leaves, in a tree with 4 levels
+        assert!(measurement.privacy_relation.eval(&1., &4e6)?);
+        assert!(!measurement.privacy_relation.eval(&1., &3.9e6)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_hierarchical_histogram_consistent() -> Fallible<()> {
+        let measurement = make_hierarchical_histogram(9, 3, 10.)?;
+        let histogram = measurement.function.eval(&vec![0; 9])?;
+        // with large noise, every node is still the sum of its children
+        for level in 0..histogram.levels.len() - 1 {
+            for (parent, count) in histogram.levels[level].iter().enumerate() {
+                let children = histogram.levels[level + 1][parent * 3..(parent + 1) * 3].iter().sum::<f64>();
+                assert!((count - children).abs() < 1e-9);
+            }
+        }
+        assert!((histogram.range_count(0, 9)? - histogram.bin_counts().iter().sum::<f64>()).abs() < 1e-9);
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
-use num::Float;
+use std::collections::Bound;
 
+use num::{Float, Zero};
+
+use crate::accuracy::laplace_confidence_interval;
 use crate::core::{Measurement, Function, PrivacyRelation, Domain, SensitivityMetric};
-use crate::dist::{L1Distance, MaxDivergence, AbsoluteDistance};
-use crate::dom::{AllDomain, VectorDomain};
-use crate::samplers::{SampleLaplace};
+use crate::dist::{L1Distance, MaxDivergence, AbsoluteDistance, CoordinateDistance};
+use crate::dom::{AllDomain, IntervalDomain, SizedDomain, VectorDomain};
+use crate::samplers::{SampleLaplace, SampleUniform};
 use crate::error::*;
 use crate::traits::DistanceCast;
 
@@ -38,11 +41,15 @@ impl<T> LaplaceDomain for VectorDomain<AllDomain<T>>
     }
 }
 
+/// A [`Measurement`] that adds noise from the Laplace(`scale`) distribution to the input.
+/// Satisfies `d_out`-DP (MaxDivergence) for `d_in`-sensitive inputs when `d_out >= d_in / scale`.
+/// Use `VectorDomain<AllDomain<T>>` to add independent noise to each element,
+/// where `d_in` is the L1 norm of the coordinate-wise differences between neighboring vectors.
 pub fn make_base_laplace<D>(scale: D::Atom) -> Fallible<Measurement<D, D, D::Metric, MaxDivergence<D::Atom>>>
     where D: LaplaceDomain,
           D::Atom: 'static + Clone + SampleLaplace + Float + DistanceCast {
-    if scale.is_sign_negative() {
-        return fallible!(MakeMeasurement, "scale must not be negative")
+    if scale.is_nan() || scale <= D::Atom::zero() {
+        return fallible!(MakeMeasurement, "scale must be positive")
     }
     Ok(Measurement::new(
         D::new(),
@@ -51,7 +58,87 @@ pub fn make_base_laplace<D>(scale: D::Atom) -> Fallible<Measurement<D, D, D::Met
         D::Metric::default(),
         MaxDivergence::default(),
         PrivacyRelation::new_from_constant(scale.recip())
-    ))
+    ).with_confidence_interval(move |released, alpha|
+        laplace_confidence_interval(num_cast!(scale; f64)?, released, alpha)))
+}
+
+/// A [`Measurement`] that releases a draw from Laplace(`scale`) noise around an input in `[lower, upper]`,
+/// truncated to `[lower, upper]`.
+///
+/// The release is sampled by inverting the CDF of the truncated distribution, so it always lies in bounds.
+/// Truncation renormalizes the density by the probability mass within bounds.
+/// The masses of `d_in`-close inputs differ by at most a factor of `exp(d_in / scale)`,
+/// so the mechanism is `2 * d_in / scale`-DP. If point masses at the bounds are acceptable,
+/// clamping the output of [`make_base_laplace`] is cheaper, as it costs only `d_in / scale`.
+pub fn make_base_bounded_laplace<T>(
+    lower: T, upper: T, scale: T
+) -> Fallible<Measurement<IntervalDomain<T>, IntervalDomain<T>, AbsoluteDistance<T>, MaxDivergence<T>>>
+    where T: 'static + Clone + SampleUniform + Float + DistanceCast {
+    if !(lower.is_finite() && upper.is_finite()) { return fallible!(MakeMeasurement, "bounds must be finite") }
+    if lower >= upper { return fallible!(MakeMeasurement, "lower must be less than upper") }
+    if scale.is_nan() || scale <= T::zero() {
+        return fallible!(MakeMeasurement, "scale must be positive")
+    }
+    let _2 = num_cast!(2; T)?;
+    let domain = IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?;
+
+    Ok(Measurement::new(
+        domain.clone(),
+        domain,
+        Function::new_fallible(move |arg: &T| {
+            // the distance from arg to each bound, and the (doubled) Laplace mass on each side within bounds
+            let (left, right) = (*arg - lower, upper - *arg);
+            let (mass_left, mass_right) = (-(-left / scale).exp_m1(), -(-right / scale).exp_m1());
+            let go_left = T::sample_standard_uniform(false)? * (mass_left + mass_right) < mass_left;
+            let (width, sign) = if go_left { (left, -T::one()) } else { (right, T::one()) };
+            // invert the CDF of an exponential distribution truncated to [0, width]
+            let uniform = T::sample_standard_uniform(false)?;
+            let distance = -scale * (uniform * (-width / scale).exp_m1()).ln_1p();
+            Ok((*arg + sign * distance.min(width)).max(lower).min(upper))
+        }),
+        AbsoluteDistance::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(_2 / scale)))
+}
+
+/// A [`Measurement`] that adds Laplace(`scales[i]`) noise to the `i`th coordinate of a vector.
+/// Each coordinate has its own sensitivity, so `d_in` is a vector of per-coordinate sensitivities,
+/// and the mechanism satisfies `d_out`-DP when `d_out >= sum_i d_in[i] / scales[i]`.
+/// This is useful for releasing statistics of different magnitudes together, like a sum and a count.
+pub fn make_base_laplace_heterogeneous<T>(
+    scales: Vec<T>
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<T>>>, SizedDomain<VectorDomain<AllDomain<T>>>, CoordinateDistance<T>, MaxDivergence<T>>>
+    where T: 'static + Clone + SampleLaplace + Float + DistanceCast {
+    if scales.is_empty() { return fallible!(MakeMeasurement, "there must be at least one scale") }
+    if scales.iter().any(|s| s.is_nan() || *s <= T::zero()) {
+        return fallible!(MakeMeasurement, "scales must be positive")
+    }
+    let length = scales.len();
+    let domain = SizedDomain::new(VectorDomain::new_all(), length);
+    let relation_scales = scales.clone();
+
+    Ok(Measurement::new(
+        domain.clone(),
+        domain,
+        Function::new_fallible(move |arg: &Vec<T>| {
+            if arg.len() != length { return fallible!(FailedFunction, "expected {} coordinates", length) }
+            arg.iter().zip(scales.iter())
+                .map(|(v, scale)| T::sample_laplace(*v, *scale, false))
+                .collect()
+        }),
+        CoordinateDistance::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &Vec<T>, d_out: &T| {
+            if d_in.len() != length {
+                return fallible!(InvalidDistance, "laplace mechanism: expected {} sensitivities", length)
+            }
+            if d_in.iter().any(|d| d.is_nan() || d.is_sign_negative()) {
+                return fallible!(InvalidDistance, "laplace mechanism: input sensitivities must be non-negative")
+            }
+            let epsilon = d_in.iter().zip(relation_scales.iter())
+                .fold(T::zero(), |sum, (d, scale)| sum + *d / *scale);
+            Ok(*d_out >= epsilon)
+        })))
 }
 
 
@@ -80,6 +167,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_make_laplace_relation() -> Fallible<()> {
+        let measurement = make_base_laplace::<AllDomain<_>>(2.0)?;
+        // epsilon is d_in / scale
+        assert!(measurement.privacy_relation.eval(&1., &0.5)?);
+        assert!(!measurement.privacy_relation.eval(&1., &0.49)?);
+        assert!(measurement.privacy_relation.eval(&0., &1e-10)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_laplace_perturbs() -> Fallible<()> {
+        let measurement = make_base_laplace::<AllDomain<_>>(1.0)?;
+        let noisy = (0..10).map(|_| measurement.function.eval(&0.0)).collect::<Fallible<Vec<_>>>()?;
+        assert!(noisy.iter().any(|v| *v != 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_laplace_invalid_scale() {
+        assert!(make_base_laplace::<AllDomain<f64>>(0.0).is_err());
+        assert!(make_base_laplace::<AllDomain<f64>>(-1.0).is_err());
+        assert!(make_base_laplace::<AllDomain<f64>>(f64::NAN).is_err());
+    }
+
     #[test]
     fn test_make_vector_laplace_mechanism() -> Fallible<()> {
         let measurement = make_base_laplace::<VectorDomain<_>>(1.0)?;
@@ -89,5 +201,55 @@ mod tests {
         assert!(measurement.privacy_relation.eval(&1., &1.)?);
         Ok(())
     }
-}
 
+    #[test]
+    fn test_make_vector_laplace_relation() -> Fallible<()> {
+        let measurement = make_base_laplace::<VectorDomain<_>>(2.0)?;
+        let noisy = measurement.function.eval(&vec![0.0; 100])?;
+        assert_eq!(noisy.len(), 100);
+        // each coordinate is perturbed independently
+        assert!(noisy.iter().any(|v| *v != noisy[0]));
+
+        // the budget depends on the L1 norm, not on the number of coordinates
+        assert!(measurement.privacy_relation.eval(&3., &1.5)?);
+        assert!(!measurement.privacy_relation.eval(&3., &1.49)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_base_bounded_laplace() -> Fallible<()> {
+        let measurement = make_base_bounded_laplace(0., 10., 5.)?;
+        for _ in 0..100 {
+            let ret = measurement.function.eval(&0.)?;
+            assert!((0. ..=10.).contains(&ret));
+        }
+        assert!(measurement.invoke(&11.).is_err());
+
+        assert!(measurement.privacy_relation.eval(&1., &0.4)?);
+        assert!(!measurement.privacy_relation.eval(&1., &0.39)?);
+        assert!(make_base_bounded_laplace(10., 0., 1.).is_err());
+        assert!(make_base_bounded_laplace(0., f64::INFINITY, 1.).is_err());
+
+        // a scale much larger than the interval is nearly uniform, and never fails
+        let measurement = make_base_bounded_laplace(0., 1., 1e7)?;
+        let mean = (0..1000).map(|_| measurement.function.eval(&1.)).sum::<Fallible<f64>>()? / 1000.;
+        assert!((mean - 0.5).abs() < 0.1, "mean was {}", mean);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_laplace_heterogeneous() -> Fallible<()> {
+        // a sum with sensitivity 100 and a count with sensitivity 1
+        let measurement = make_base_laplace_heterogeneous(vec![200., 2.])?;
+        let noisy = measurement.function.eval(&vec![1000., 10.])?;
+        assert_eq!(noisy.len(), 2);
+        assert!(measurement.function.eval(&vec![1.]).is_err());
+
+        // each coordinate costs 0.5
+        assert!(measurement.privacy_relation.eval(&vec![100., 1.], &1.)?);
+        assert!(!measurement.privacy_relation.eval(&vec![100., 1.], &0.99)?);
+        assert!(measurement.privacy_relation.eval(&vec![1.], &1.).is_err());
+        assert!(make_base_laplace_heterogeneous(vec![1., 0.]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{MaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, MapDomain};
+use crate::error::*;
+use crate::samplers::SampleLaplace;
+use crate::trans::{DataFrame, DataFrameDomain};
+
+/// A noisy contingency table, mapping each combination of attribute values to its count.
+pub type ContingencyTable<T> = HashMap<Vec<T>, f64>;
+
+// every combination of values for the given attributes, in the order of the attributes
+fn cartesian_product<T: Clone>(categories: &[&Vec<T>]) -> Vec<Vec<T>> {
+    categories.iter().fold(vec![vec![]], |cells, values| cells.iter()
+        .flat_map(|cell| values.iter().map(move |v| {
+            let mut cell = cell.clone();
+            cell.push(v.clone());
+            cell
+        }))
+        .collect())
+}
+
+/// A [`Measurement`] that releases a noisy contingency table for each of `marginals`, a list of attribute subsets.
+///
+/// Each attribute takes values in its public `categories`, and every combination of values is released,
+/// so the released cells do not depend on the data. Records with a value outside of the categories are ignored.
+/// Each cell is released with Laplace(`scale`) noise. A record contributes to one cell of each of the `m` marginals,
+/// so the mechanism is `d_in * m / scale`-DP, or equivalently, the budget is split evenly across the marginals.
+pub fn make_private_marginals<K, T>(
+    categories: HashMap<K, Vec<T>>, marginals: Vec<Vec<K>>, scale: f64
+) -> Fallible<Measurement<DataFrameDomain<K>, MapDomain<AllDomain<Vec<K>>, AllDomain<ContingencyTable<T>>>, SymmetricDistance, MaxDivergence<f64>>>
+    where K: 'static + Eq + Hash + Clone + Debug,
+          T: 'static + Eq + Hash + Clone + Debug {
+    if marginals.is_empty() { return fallible!(MakeMeasurement, "there must be at least one marginal") }
+    if let Some(key) = marginals.iter().flatten().find(|k| !categories.contains_key(k)) {
+        return fallible!(MakeMeasurement, "no categories for attribute {:?}", key)
+    }
+    if scale.is_nan() || scale <= 0. { return fallible!(MakeMeasurement, "scale must be positive") }
+    let num_marginals = marginals.len() as f64;
+
+    Ok(Measurement::new(
+        MapDomain::new_all(),
+        MapDomain::new_all(),
+        Function::new_fallible(move |data: &DataFrame<K>| marginals.iter().map(|marginal| {
+            let columns = marginal.iter()
+                .map(|key| data.get(key)
+                    .ok_or_else(|| err!(FailedFunction, "column does not exist: {:?}", key))?
+                    .as_form::<Vec<T>>())
+                .collect::<Fallible<Vec<&Vec<T>>>>()?;
+            let num_rows = columns.first().map(|c| c.len()).unwrap_or(0);
+            if columns.iter().any(|c| c.len() != num_rows) {
+                return fallible!(FailedFunction, "columns must have the same length")
+            }
+
+            let mut table = cartesian_product(&marginal.iter().map(|k| &categories[k]).collect::<Vec<_>>())
+                .into_iter().map(|cell| (cell, 0.)).collect::<ContingencyTable<T>>();
+            (0..num_rows).for_each(|row| {
+                let cell = columns.iter().map(|c| c[row].clone()).collect::<Vec<T>>();
+                table.get_mut(&cell).into_iter().for_each(|count| *count += 1.);
+            });
+            let table = table.into_iter()
+                .map(|(cell, count)| Ok((cell, f64::sample_laplace(count, scale, false)?)))
+                .collect::<Fallible<ContingencyTable<T>>>()?;
+            Ok((marginal.clone(), table))
+        }).collect()),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(num_marginals / scale)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Column;
+
+    #[test]
+    fn test_make_private_marginals() -> Fallible<()> {
+        let mut data = DataFrame::new();
+        data.insert("sex", Column::new(vec!["F", "M", "F", "F"]));
+        data.insert("age", Column::new(vec!["young", "old", "old", "unknown"]));
+        let mut categories = HashMap::new();
+        categories.insert("sex", vec!["F", "M"]);
+        categories.insert("age", vec!["young", "old"]);
+
+        let measurement = make_private_marginals(categories, vec![vec!["sex"], vec!["sex", "age"]], 1e-6)?;
+        let release = measurement.function.eval(&data)?;
+        let one_way = &release[&vec!["sex"]];
+        assert!((one_way[&vec!["F"]] - 3.).abs() < 1e-3);
+        let two_way = &release[&vec!["sex", "age"]];
+        assert_eq!(two_way.len(), 4);
+        // the record with an unknown age is not counted
+        assert!((two_way[&vec!["F", "old"]] - 1.).abs() < 1e-3);
+        assert!(two_way[&vec!["M", "young"]].abs() < 1e-3);
+
+        assert!(measurement.privacy_relation.eval(&1, &2e6)?);
+        assert!(!measurement.privacy_relation.eval(&1, &1.9e6)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_private_marginals_invalid() {
+        let categories = vec![("a", vec![1])].into_iter().collect::<HashMap<_, _>>();
+        assert!(make_private_marginals(categories.clone(), vec![vec!["b"]], 1.).is_err());
+        assert!(make_private_marginals(categories, vec![], 1.).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use num::ToPrimitive;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{L1Distance, MaxDivergence};
+use crate::dom::{AllDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::SampleLaplace;
+
+/// A dense matrix, as a vector of rows.
+pub type Matrix = Vec<Vec<f64>>;
+
+fn check_matrix(matrix: &[Vec<f64>], num_columns: usize, name: &str) -> Fallible<()> {
+    if matrix.is_empty() { return fallible!(MakeMeasurement, "the {} must have at least one row", name) }
+    if matrix.iter().any(|row| row.len() != num_columns) {
+        return fallible!(MakeMeasurement, "each row of the {} must have {} columns", name, num_columns)
+    }
+    if matrix.iter().flatten().any(|v| !v.is_finite()) {
+        return fallible!(MakeMeasurement, "the {} must be finite", name)
+    }
+    Ok(())
+}
+
+fn transpose(matrix: &[Vec<f64>]) -> Matrix {
+    (0..matrix[0].len()).map(|j| matrix.iter().map(|row| row[j]).collect()).collect()
+}
+
+fn multiply(left: &[Vec<f64>], right: &[Vec<f64>]) -> Matrix {
+    left.iter().map(|row| (0..right[0].len())
+        .map(|j| row.iter().zip(right.iter()).map(|(l, r_row)| l * r_row[j]).sum())
+        .collect()).collect()
+}
+
+fn multiply_vector(matrix: &[Vec<f64>], vector: &[f64]) -> Vec<f64> {
+    matrix.iter().map(|row| row.iter().zip(vector.iter()).map(|(l, r)| l * r).sum()).collect()
+}
+
+// invert a square matrix by Gauss-Jordan elimination with partial pivoting
+fn invert(matrix: &[Vec<f64>]) -> Fallible<Matrix> {
+    let n = matrix.len();
+    let mut augmented = matrix.iter().enumerate()
+        .map(|(i, row)| row.iter().cloned().chain((0..n).map(|j| (i == j) as u8 as f64)).collect())
+        .collect::<Matrix>();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|l, r| augmented[*l][col].abs().partial_cmp(&augmented[*r][col].abs()).unwrap_assert("entries are finite"))
+            .unwrap_assert("the column is not empty");
+        if augmented[pivot][col].abs() < 1e-12 { return fallible!(MakeMeasurement, "the matrix is singular") }
+        augmented.swap(col, pivot);
+        let scale = augmented[col][col];
+        augmented[col].iter_mut().for_each(|v| *v /= scale);
+        for row in 0..n {
+            if row == col { continue }
+            let factor = augmented[row][col];
+            let pivot_row = augmented[col].clone();
+            augmented[row].iter_mut().zip(pivot_row.iter()).for_each(|(v, p)| *v -= factor * p);
+        }
+    }
+    Ok(augmented.into_iter().map(|row| row[n..].to_vec()).collect())
+}
+
+// eigendecompose a symmetric matrix by cyclic Jacobi rotations,
+//     returning the eigenvalues and the eigenvectors as the columns of a matrix
+pub(crate) fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Matrix) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut vectors = (0..n).map(|i| (0..n).map(|j| (i == j) as u8 as f64).collect()).collect::<Matrix>();
+    let scale = a.iter().flatten().map(|v| v * v).sum::<f64>().sqrt();
+
+    for _ in 0..100 {
+        let off_diagonal = (0..n).flat_map(|i| (0..n).filter(move |j| *j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j]).sum::<f64>().sqrt();
+        if off_diagonal <= 1e-14 * scale { break }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0. { continue }
+                // the rotation that zeroes a[p][q]
+                let theta = (a[q][q] - a[p][p]) / (2. * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                let c = (t * t + 1.).sqrt().recip();
+                let s = t * c;
+                for row in a.iter_mut().chain(vectors.iter_mut()) {
+                    let (rp, rq) = (row[p], row[q]);
+                    row[p] = c * rp - s * rq;
+                    row[q] = s * rp + c * rq;
+                }
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                a[p] = row_p.iter().zip(row_q.iter()).map(|(pk, qk)| c * pk - s * qk).collect();
+                a[q] = row_p.iter().zip(row_q.iter()).map(|(pk, qk)| s * pk + c * qk).collect();
+            }
+        }
+    }
+    ((0..n).map(|i| a[i][i]).collect(), vectors)
+}
+
+/// A [`Measurement`] that answers a `workload` of linear queries over a histogram with the matrix mechanism.
+///
+/// The histogram is measured through the `strategy` queries with Laplace(`scale`) noise,
+/// the histogram is reconstructed from the noisy strategy answers by least squares,
+/// and the workload is answered from the reconstruction. The strategy defaults to the identity,
+/// and must have full column rank. Changing the histogram by `d_in` in the L1 distance changes the strategy answers
+/// by at most `d_in` times the largest L1 norm of a column of the strategy, `||strategy||_1`,
+/// so the mechanism is `d_in * ||strategy||_1 / scale`-DP.
+pub fn make_matrix_mechanism<TS>(
+    workload: Matrix, strategy: Option<Matrix>, scale: f64
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, VectorDomain<AllDomain<f64>>, L1Distance<f64>, MaxDivergence<f64>>>
+    where TS: 'static + Clone + ToPrimitive {
+    let num_bins = workload.first().map(|row| row.len()).unwrap_or(0);
+    if num_bins == 0 { return fallible!(MakeMeasurement, "the workload must have at least one row and column") }
+    check_matrix(&workload, num_bins, "workload")?;
+    let strategy = strategy.unwrap_or_else(|| (0..num_bins)
+        .map(|i| (0..num_bins).map(|j| (i == j) as u8 as f64).collect())
+        .collect());
+    check_matrix(&strategy, num_bins, "strategy")?;
+    if scale.is_nan() || scale <= 0. { return fallible!(MakeMeasurement, "scale must be positive") }
+
+    let strategy_t = transpose(&strategy);
+    let sensitivity = strategy_t.iter()
+        .map(|column| column.iter().map(|v| v.abs()).sum::<f64>())
+        .fold(0., f64::max);
+    // maps the noisy strategy answers to least-squares workload answers: W (A^T A)^-1 A^T
+    let reconstruction = multiply(&workload, &multiply(&invert(&multiply(&strategy_t, &strategy))?, &strategy_t));
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), num_bins),
+        VectorDomain::new_all(),
+        Function::new_fallible(move |counts: &Vec<TS>| {
+            if counts.len() != num_bins { return fallible!(FailedFunction, "expected {} bins", num_bins) }
+            let counts = counts.iter()
+                .map(|c| num_cast!(c.clone(); f64))
+                .collect::<Fallible<Vec<f64>>>()?;
+            let noisy = multiply_vector(&strategy, &counts).into_iter()
+                .map(|v| f64::sample_laplace(v, scale, false))
+                .collect::<Fallible<Vec<f64>>>()?;
+            Ok(multiply_vector(&reconstruction, &noisy))
+        }),
+        L1Distance::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(sensitivity / scale)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invert() -> Fallible<()> {
+        let inverse = invert(&[vec![0., 2.], vec![4., 0.]])?;
+        assert_eq!(inverse, vec![vec![0., 0.25], vec![0.5, 0.]]);
+        assert!(invert(&[vec![1., 2.], vec![2., 4.]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let matrix = vec![vec![2., 1., 0.], vec![1., 2., 0.], vec![0., 0., 5.]];
+        let (values, vectors) = symmetric_eigen(&matrix);
+        let mut sorted = values.clone();
+        sorted.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        [1., 3., 5.].iter().zip(sorted.iter()).for_each(|(e, v)| assert!((e - v).abs() < 1e-9));
+        // each column is an eigenvector
+        (0..3).for_each(|j| {
+            let column = vectors.iter().map(|row| row[j]).collect::<Vec<f64>>();
+            multiply_vector(&matrix, &column).iter().zip(column.iter())
+                .for_each(|(l, r)| assert!((l - values[j] * r).abs() < 1e-9));
+        });
+    }
+
+    #[test]
+    fn test_make_matrix_mechanism() -> Fallible<()> {
+        // all prefix sums over 3 bins
+        let workload = vec![vec![1., 0., 0.], vec![1., 1., 0.], vec![1., 1., 1.]];
+        // measure each bin and the total
+        let strategy = vec![vec![1., 0., 0.], vec![0., 1., 0.], vec![0., 0., 1.], vec![1., 1., 1.]];
+        let measurement = make_matrix_mechanism(workload, Some(strategy), 1e-6)?;
+        let answers = measurement.function.eval(&vec![1, 2, 3])?;
+        [1., 3., 6.].iter().zip(answers.iter()).for_each(|(e, a)| assert!((e - a).abs() < 1e-3));
+
+        // each column of the strategy has an L1 norm of 2
+        assert!(measurement.privacy_relation.eval(&1., &2e6)?);
+        assert!(!measurement.privacy_relation.eval(&1., &1.9e6)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_matrix_mechanism_invalid() {
+        let workload = || vec![vec![1., 1.]];
+        assert!(make_matrix_mechanism::<i32>(vec![], None, 1.).is_err());
+        assert!(make_matrix_mechanism::<i32>(workload(), Some(vec![vec![1., 1.]]), 1.).is_err());
+        assert!(make_matrix_mechanism::<i32>(workload(), Some(vec![vec![1.]]), 1.).is_err());
+        assert!(make_matrix_mechanism::<i32>(workload(), None, 0.).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::Bound;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{MaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, IntervalDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::SampleLaplace;
+
+/// A [`Measurement`] that releases the mean of a dataset of unknown size, as the ratio of a noisy sum and a noisy count.
+///
+/// The `epsilon` budget is split between the two releases: a `sum_fraction` share goes to the sum, and the rest to the count.
+/// Adding or removing a record changes the sum by at most `max(|lower|, |upper|)` and the count by 1,
+/// and Laplace noise is scaled so that the sum and count cost `sum_fraction * epsilon` and `(1 - sum_fraction) * epsilon`.
+/// The ratio is postprocessing: the noisy count is floored at 1, and the mean is clamped to `[lower, upper]`.
+/// The mechanism is `d_in * epsilon`-DP.
+pub fn make_private_mean_laplace(
+    lower: f64, upper: f64, epsilon: f64, sum_fraction: f64
+) -> Fallible<Measurement<VectorDomain<IntervalDomain<f64>>, AllDomain<f64>, SymmetricDistance, MaxDivergence<f64>>> {
+    if !(lower.is_finite() && upper.is_finite()) { return fallible!(MakeMeasurement, "bounds must be finite") }
+    if lower > upper { return fallible!(MakeMeasurement, "lower may not be greater than upper") }
+    if !(epsilon.is_finite() && epsilon > 0.) { return fallible!(MakeMeasurement, "epsilon must be positive and finite") }
+    if !(sum_fraction > 0. && sum_fraction < 1.) { return fallible!(MakeMeasurement, "sum_fraction must be within (0, 1)") }
+
+    let sum_sensitivity = lower.abs().max(upper.abs());
+    let sum_scale = sum_sensitivity / (sum_fraction * epsilon);
+    let count_scale = 1. / ((1. - sum_fraction) * epsilon);
+
+    Ok(Measurement::new(
+        VectorDomain::new(IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &Vec<f64>| {
+            let sum = arg.iter().map(|v| v.max(lower).min(upper)).sum::<f64>();
+            let noisy_sum = if sum_scale > 0. { f64::sample_laplace(sum, sum_scale, false)? } else { sum };
+            let noisy_count = f64::sample_laplace(arg.len() as f64, count_scale, false)?;
+            Ok((noisy_sum / noisy_count.max(1.)).max(lower).min(upper))
+        }),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_private_mean_laplace() -> Fallible<()> {
+        let measurement = make_private_mean_laplace(0., 10., 1., 0.5)?;
+        let data = (0..10_000).map(|i| (i % 5) as f64).collect::<Vec<f64>>();
+        let mean = measurement.function.eval(&data)?;
+        assert!((mean - 2.).abs() < 0.1, "mean was {}", mean);
+        // the release is clamped, even when the count is noised below 1
+        let mean = measurement.function.eval(&vec![])?;
+        assert!((0. ..=10.).contains(&mean));
+
+        assert!(measurement.privacy_relation.eval(&1, &1.)?);
+        assert!(!measurement.privacy_relation.eval(&2, &1.)?);
+        assert!(make_private_mean_laplace(0., 10., 1., 1.).is_err());
+        assert!(make_private_mean_laplace(10., 0., 1., 0.5).is_err());
+        Ok(())
+    }
+}
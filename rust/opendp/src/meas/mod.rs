@@ -7,8 +7,44 @@ pub mod laplace;
 pub mod gaussian;
 pub mod geometric;
 pub mod stability;
+pub mod count;
+pub mod quantile;
+pub mod discrete_gaussian;
+pub mod exponential;
+pub mod noisy_max;
+pub mod randomized_response;
+pub mod rappor;
+pub mod frequency_oracle;
+pub mod smooth_sensitivity;
+pub mod hierarchical;
+pub mod matrix;
+pub mod mwem;
+pub mod marginals;
+pub mod glm;
+pub mod sgd;
+pub mod pca;
+pub mod mean;
+pub mod cauchy;
 
 pub use crate::meas::laplace::*;
 pub use crate::meas::gaussian::*;
 pub use crate::meas::geometric::*;
 pub use crate::meas::stability::*;
+pub use crate::meas::count::*;
+pub use crate::meas::quantile::*;
+pub use crate::meas::discrete_gaussian::*;
+pub use crate::meas::exponential::*;
+pub use crate::meas::noisy_max::*;
+pub use crate::meas::randomized_response::*;
+pub use crate::meas::rappor::*;
+pub use crate::meas::frequency_oracle::*;
+pub use crate::meas::smooth_sensitivity::*;
+pub use crate::meas::hierarchical::*;
+pub use crate::meas::matrix::*;
+pub use crate::meas::mwem::*;
+pub use crate::meas::marginals::*;
+pub use crate::meas::glm::*;
+pub use crate::meas::sgd::*;
+pub use crate::meas::pca::*;
+pub use crate::meas::mean::*;
+pub use crate::meas::cauchy::*;
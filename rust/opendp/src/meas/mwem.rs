@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{MaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::meas::exponential::sample_log_weighted_index;
+use crate::samplers::SampleLaplace;
+
+fn answer(query: &[bool], histogram: &[f64]) -> f64 {
+    query.iter().zip(histogram.iter()).filter(|(q, _)| **q).map(|(_, h)| h).sum()
+}
+
+/// A [`Measurement`] that releases a synthetic dataset of `num_records` records with MWEM (Hardt, Ligett and McSherry, 2012).
+///
+/// Records are drawn from a public `universe`, and records outside of it are ignored.
+/// Each query is a counting query, given as an indicator over the `universe`.
+/// Starting from the uniform distribution, each of the `iterations` selects the query with the largest error
+/// via the exponential mechanism, measures it with Laplace(`scale`) noise, and updates the synthetic distribution
+/// with multiplicative weights on all measurements so far. The synthetic records are sampled from the final distribution.
+///
+/// Each iteration composes a `2 * d_in / scale`-DP selection with a `d_in / scale`-DP measurement,
+/// so the mechanism is `3 * iterations * d_in / scale`-DP.
+pub fn make_mwem<T>(
+    universe: Vec<T>, queries: Vec<Vec<bool>>, num_records: usize, iterations: usize, scale: f64
+) -> Fallible<Measurement<VectorDomain<AllDomain<T>>, VectorDomain<AllDomain<T>>, SymmetricDistance, MaxDivergence<f64>>>
+    where T: 'static + Eq + Hash + Clone {
+    if universe.is_empty() { return fallible!(MakeMeasurement, "the universe must not be empty") }
+    if queries.is_empty() { return fallible!(MakeMeasurement, "there must be at least one query") }
+    if queries.iter().any(|q| q.len() != universe.len()) {
+        return fallible!(MakeMeasurement, "each query must have one indicator per member of the universe")
+    }
+    if num_records == 0 || iterations == 0 { return fallible!(MakeMeasurement, "num_records and iterations must be positive") }
+    if scale.is_nan() || scale <= 0. { return fallible!(MakeMeasurement, "scale must be positive") }
+    let indexes = universe.iter().cloned().enumerate().map(|(i, v)| (v, i)).collect::<HashMap<T, usize>>();
+    if indexes.len() != universe.len() { return fallible!(MakeMeasurement, "the universe must be distinct") }
+    let total = num_records as f64;
+
+    Ok(Measurement::new(
+        VectorDomain::new_all(),
+        VectorDomain::new_all(),
+        Function::new_fallible(move |data: &Vec<T>| {
+            let mut histogram = vec![0.; universe.len()];
+            data.iter().filter_map(|v| indexes.get(v)).for_each(|i| histogram[*i] += 1.);
+            let true_answers = queries.iter().map(|q| answer(q, &histogram)).collect::<Vec<f64>>();
+
+            let mut synthetic = vec![total / universe.len() as f64; universe.len()];
+            let mut measurements = Vec::new();
+            for _ in 0..iterations {
+                // select the query with the largest error, which changes by at most d_in
+                let log_weights = queries.iter().zip(true_answers.iter())
+                    .map(|(q, a)| (answer(q, &synthetic) - a).abs() / scale)
+                    .collect::<Vec<f64>>();
+                let selected = sample_log_weighted_index(&log_weights)?;
+                measurements.push((selected, f64::sample_laplace(true_answers[selected], scale, false)?));
+
+                for (i, measured) in measurements.iter() {
+                    let error = measured - answer(&queries[*i], &synthetic);
+                    synthetic.iter_mut().zip(queries[*i].iter())
+                        .filter(|(_, q)| **q)
+                        .for_each(|(s, _)| *s *= (error / (2. * total)).exp());
+                    let mass = synthetic.iter().sum::<f64>();
+                    synthetic.iter_mut().for_each(|s| *s *= total / mass);
+                }
+            }
+
+            let log_weights = synthetic.iter().map(|s| s.ln()).collect::<Vec<f64>>();
+            (0..num_records)
+                .map(|_| Ok(universe[sample_log_weighted_index(&log_weights)?].clone()))
+                .collect()
+        }),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(3. * iterations as f64 / scale)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_mwem() -> Fallible<()> {
+        let universe = vec![0, 1, 2, 3];
+        // the count of each member, and of the lower half
+        let queries = vec![
+            vec![true, false, false, false], vec![false, true, false, false],
+            vec![false, false, true, false], vec![false, false, false, true],
+            vec![true, true, false, false],
+        ];
+        let measurement = make_mwem(universe, queries, 100, 20, 0.1)?;
+        let data = [vec![0; 70], vec![3; 30]].concat();
+        let synthetic = measurement.function.eval(&data)?;
+        assert_eq!(synthetic.len(), 100);
+        let zeros = synthetic.iter().filter(|v| **v == 0).count();
+        assert!(zeros > 50, "synthetic data had {} zeros", zeros);
+
+        assert!(measurement.privacy_relation.eval(&1, &600.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &599.)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_mwem_invalid() {
+        assert!(make_mwem(vec![0, 1], vec![vec![true]], 10, 1, 1.).is_err());
+        assert!(make_mwem(vec![0, 0], vec![vec![true, false]], 10, 1, 1.).is_err());
+        assert!(make_mwem(vec![0, 1], vec![vec![true, false]], 10, 0, 1.).is_err());
+    }
+}
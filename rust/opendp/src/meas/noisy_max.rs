@@ -0,0 +1,195 @@
+use std::cmp::Ordering;
+
+use num::{Float, ToPrimitive};
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{MaxDivergence, ScoreMetric};
+use crate::dom::{AllDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::{SampleLaplace, SampleUniform};
+use crate::traits::DistanceConstant;
+
+fn make_report_noisy_max_with<TS, Q, MI>(
+    length: usize, scale: Q, sample_noise: impl Fn(Q) -> Fallible<Q> + 'static
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<usize>, MI, MaxDivergence<Q>>>
+    where TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float,
+          MI: ScoreMetric<Distance=Q> {
+    if length == 0 { return fallible!(MakeMeasurement, "there must be at least one score") }
+    if scale.is_nan() || scale <= Q::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    let _2 = num_cast!(2; Q)?;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), length),
+        AllDomain::new(),
+        Function::new_fallible(move |scores: &Vec<TS>| {
+            if scores.len() != length { return fallible!(FailedFunction, "expected {} scores", length) }
+            let mut best = (0, Q::neg_infinity());
+            for (i, score) in scores.iter().enumerate() {
+                let noisy = sample_noise(num_cast!(score.clone(); Q)?)?;
+                if noisy.is_nan() { return fallible!(FailedFunction, "scores may not be nan") }
+                if noisy > best.1 { best = (i, noisy) }
+            }
+            Ok(best.0)
+        }),
+        MI::default(),
+        MaxDivergence::default(),
+        // each score may move by d_in, and the comparison between any two scores by 2 * d_in
+        PrivacyRelation::new_from_constant(_2 / scale)))
+}
+
+/// A [`Measurement`] that releases the index of the largest of `length` scores, after adding Laplace(`scale`) noise to each.
+/// The mechanism is `2 * d_in / scale`-DP, regardless of the number of scores.
+pub fn make_report_noisy_max_laplace<TS, Q, MI>(
+    length: usize, scale: Q
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<usize>, MI, MaxDivergence<Q>>>
+    where TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float + SampleLaplace,
+          MI: ScoreMetric<Distance=Q> {
+    make_report_noisy_max_with(length, scale, move |score| Q::sample_laplace(score, scale, false))
+}
+
+/// A [`Measurement`] that releases the index of the largest of `length` scores, after adding Exponential(`scale`) noise to each.
+/// Releases are distributed as in the exponential mechanism, and the mechanism is `2 * d_in / scale`-DP.
+pub fn make_report_noisy_max_exponential<TS, Q, MI>(
+    length: usize, scale: Q
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<usize>, MI, MaxDivergence<Q>>>
+    where TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float + SampleUniform,
+          MI: ScoreMetric<Distance=Q> {
+    make_report_noisy_max_with(length, scale, move |score| {
+        // inverse cdf of the exponential distribution, where 1 - U is in (0, 1]
+        let uniform = Q::one() - Q::sample_standard_uniform(false)?;
+        Ok(score - scale * uniform.ln())
+    })
+}
+
+// the inverse cdf of the Gumbel distribution
+fn sample_gumbel<Q: Float + SampleUniform>(shift: Q, scale: Q) -> Fallible<Q> {
+    Ok(shift - scale * (-Q::sample_standard_uniform(false)?.ln()).ln())
+}
+
+/// A [`Measurement`] that releases the index of the largest of `length` scores, after adding Gumbel(`scale`) noise to each.
+/// Releases are distributed exactly as in the exponential mechanism, and the mechanism is `2 * d_in / scale`-DP.
+pub fn make_report_noisy_max_gumbel<TS, Q, MI>(
+    length: usize, scale: Q
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<usize>, MI, MaxDivergence<Q>>>
+    where TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float + SampleUniform,
+          MI: ScoreMetric<Distance=Q> {
+    make_report_noisy_max_with(length, scale, move |score| sample_gumbel(score, scale))
+}
+
+/// A [`Measurement`] that releases the indices of the `k` largest of `length` scores, after adding Gumbel(`scale`) noise to each.
+/// The indices are ordered by decreasing noisy score. Releases are distributed as in the peeling exponential mechanism,
+/// so the mechanism is `2 * k * d_in / scale`-DP.
+pub fn make_report_noisy_top_k_gumbel<TS, Q, MI>(
+    length: usize, k: usize, scale: Q
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, VectorDomain<AllDomain<usize>>, MI, MaxDivergence<Q>>>
+    where TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float + SampleUniform,
+          MI: ScoreMetric<Distance=Q> {
+    if k == 0 || k > length { return fallible!(MakeMeasurement, "k must be within [1, length]") }
+    if scale.is_nan() || scale <= Q::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    let _2k = num_cast!(2 * k; Q)?;
+
+    Ok(Measurement::new(
+        SizedDomain::new(VectorDomain::new_all(), length),
+        VectorDomain::new_all(),
+        Function::new_fallible(move |scores: &Vec<TS>| {
+            if scores.len() != length { return fallible!(FailedFunction, "expected {} scores", length) }
+            let mut noisy = scores.iter().enumerate()
+                .map(|(i, score)| Ok((i, sample_gumbel(num_cast!(score.clone(); Q)?, scale)?)))
+                .collect::<Fallible<Vec<(usize, Q)>>>()?;
+            if noisy.iter().any(|(_, v)| v.is_nan()) { return fallible!(FailedFunction, "scores may not be nan") }
+            noisy.sort_by(|(_, l), (_, r)| r.partial_cmp(l).unwrap_or(Ordering::Equal));
+            Ok(noisy.into_iter().take(k).map(|(i, _)| i).collect())
+        }),
+        MI::default(),
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(_2k / scale)))
+}
+
+/// The distribution of the noise added to each score in [`make_report_noisy_max`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoisyMaxNoise {
+    Laplace,
+    /// One-sided: releases are distributed as in the permute-and-flip mechanism.
+    Exponential,
+    /// Releases are distributed as in the exponential mechanism.
+    Gumbel,
+}
+
+/// A [`Measurement`] that releases the index of the largest of `length` scores, after adding `noise` with `scale` to each.
+/// The mechanism is `2 * d_in / scale`-DP for every choice of noise distribution. At the same scale, exponential noise
+/// has the highest expected score, as its noise is one-sided.
+pub fn make_report_noisy_max<TS, Q, MI>(
+    length: usize, scale: Q, noise: NoisyMaxNoise
+) -> Fallible<Measurement<SizedDomain<VectorDomain<AllDomain<TS>>>, AllDomain<usize>, MI, MaxDivergence<Q>>>
+    where TS: 'static + Clone + ToPrimitive,
+          Q: 'static + DistanceConstant + Float + SampleLaplace + SampleUniform,
+          MI: ScoreMetric<Distance=Q> {
+    match noise {
+        NoisyMaxNoise::Laplace => make_report_noisy_max_laplace(length, scale),
+        NoisyMaxNoise::Exponential => make_report_noisy_max_exponential(length, scale),
+        NoisyMaxNoise::Gumbel => make_report_noisy_max_gumbel(length, scale),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dist::L1Distance;
+
+    #[test]
+    fn test_make_report_noisy_max_laplace() -> Fallible<()> {
+        let measurement = make_report_noisy_max_laplace::<i32, f64, L1Distance<f64>>(3, 1.)?;
+        assert_eq!(measurement.function.eval(&vec![0, 1000, 0])?, 1);
+        assert!(measurement.function.eval(&vec![0, 1]).is_err());
+        assert!(measurement.privacy_relation.eval(&1., &2.)?);
+        assert!(!measurement.privacy_relation.eval(&1., &1.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_report_noisy_max_exponential() -> Fallible<()> {
+        let measurement = make_report_noisy_max_exponential::<f64, f64, L1Distance<f64>>(3, 1.)?;
+        assert_eq!(measurement.function.eval(&vec![0., 0., 1000.])?, 2);
+        assert!(measurement.privacy_relation.eval(&0.5, &1.)?);
+        assert!(make_report_noisy_max_exponential::<f64, f64, L1Distance<f64>>(0, 1.).is_err());
+        assert!(make_report_noisy_max_exponential::<f64, f64, L1Distance<f64>>(3, f64::NAN).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_report_noisy_max_gumbel() -> Fallible<()> {
+        let measurement = make_report_noisy_max_gumbel::<i32, f64, L1Distance<f64>>(3, 1.)?;
+        assert_eq!(measurement.function.eval(&vec![0, 1000, 0])?, 1);
+        assert!(measurement.privacy_relation.eval(&1., &2.)?);
+        assert!(!measurement.privacy_relation.eval(&1., &1.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_report_noisy_top_k_gumbel() -> Fallible<()> {
+        let measurement = make_report_noisy_top_k_gumbel::<i32, f64, L1Distance<f64>>(4, 2, 1.)?;
+        assert_eq!(measurement.function.eval(&vec![0, 1000, 0, 2000])?, vec![3, 1]);
+        assert!(measurement.privacy_relation.eval(&1., &4.)?);
+        assert!(!measurement.privacy_relation.eval(&1., &3.9)?);
+        assert!(make_report_noisy_top_k_gumbel::<i32, f64, L1Distance<f64>>(4, 5, 1.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_report_noisy_max() -> Fallible<()> {
+        for noise in &[NoisyMaxNoise::Laplace, NoisyMaxNoise::Exponential, NoisyMaxNoise::Gumbel] {
+            let measurement = make_report_noisy_max::<i32, f64, L1Distance<f64>>(3, 1., *noise)?;
+            assert_eq!(measurement.function.eval(&vec![0, 1000, 0])?, 1);
+            assert!(measurement.privacy_relation.eval(&1., &2.)?);
+            assert!(!measurement.privacy_relation.eval(&1., &1.9)?);
+        }
+        assert!(make_report_noisy_max::<i32, f64, L1Distance<f64>>(0, 1., NoisyMaxNoise::Exponential).is_err());
+        Ok(())
+    }
+}
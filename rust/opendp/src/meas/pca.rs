@@ -0,0 +1,86 @@
+use std::cmp::Ordering;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{SmoothedMaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::meas::gaussian::analytic_gaussian_delta;
+use crate::meas::matrix::{Matrix, symmetric_eigen};
+use crate::samplers::SampleGaussian;
+use crate::trans::clamp::clamp_l2_norm;
+
+/// A [`Measurement`] that releases the top `k` principal components of a dataset, by perturbing its covariance
+/// (the Analyze Gauss algorithm of Dwork, Talwar, Thakurta and Zhang, 2014).
+///
+/// Each row has `num_features` features, and is clipped to L2 norm `clip_norm`. The unnormalized covariance `X^T X`
+/// is perturbed with a symmetric matrix, where each entry on or above the diagonal is independent Gaussian(`scale`) noise.
+/// Adding or removing a row changes the covariance by at most `clip_norm^2` in Frobenius norm, so the privacy relation
+/// is that of the analytic Gaussian mechanism with sensitivity `d_in * clip_norm^2`.
+/// The released eigenvectors are postprocessing, and are ordered by decreasing noisy eigenvalue.
+pub fn make_private_pca(
+    num_features: usize, k: usize, clip_norm: f64, scale: f64
+) -> Fallible<Measurement<VectorDomain<VectorDomain<AllDomain<f64>>>, VectorDomain<VectorDomain<AllDomain<f64>>>, SymmetricDistance, SmoothedMaxDivergence<f64>>> {
+    if k == 0 || k > num_features { return fallible!(MakeMeasurement, "k must be within [1, num_features]") }
+    if !(clip_norm.is_finite() && clip_norm > 0.) { return fallible!(MakeMeasurement, "clip_norm must be positive and finite") }
+    if !(scale.is_finite() && scale > 0.) { return fallible!(MakeMeasurement, "scale must be positive and finite") }
+    let sensitivity = clip_norm * clip_norm;
+
+    Ok(Measurement::new(
+        VectorDomain::new(VectorDomain::new_all()),
+        VectorDomain::new(VectorDomain::new_all()),
+        Function::new_fallible(move |data: &Vec<Vec<f64>>| {
+            let mut covariance: Matrix = vec![vec![0.; num_features]; num_features];
+            for row in data {
+                if row.len() != num_features || row.iter().any(|v| !v.is_finite()) {
+                    return fallible!(FailedFunction, "each row must have {} finite features", num_features)
+                }
+                let row = clamp_l2_norm(row, clip_norm);
+                covariance.iter_mut().zip(row.iter())
+                    .for_each(|(cov_row, l)| cov_row.iter_mut().zip(row.iter()).for_each(|(c, r)| *c += l * r));
+            }
+            for (i, j) in (0..num_features).flat_map(|i| (i..num_features).map(move |j| (i, j))) {
+                let noisy = f64::sample_gaussian(covariance[i][j], scale, false)?;
+                covariance[i][j] = noisy;
+                covariance[j][i] = noisy;
+            }
+
+            let (values, vectors) = symmetric_eigen(&covariance);
+            let mut order = (0..num_features).collect::<Vec<usize>>();
+            order.sort_by(|l, r| values[*r].partial_cmp(&values[*l]).unwrap_or(Ordering::Equal));
+            Ok(order.into_iter().take(k).map(|j| vectors.iter().map(|row| row[j]).collect()).collect())
+        }),
+        SymmetricDistance,
+        SmoothedMaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &u32, &(eps, del): &(f64, f64)| {
+            if eps.is_nan() || eps <= 0. { return fallible!(InvalidDistance, "epsilon must be positive") }
+            if del.is_nan() || del <= 0. { return fallible!(InvalidDistance, "delta must be positive") }
+            if *d_in == 0 { return Ok(true) }
+            Ok(analytic_gaussian_delta(*d_in as f64 * sensitivity, scale, eps) <= del)
+        })))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meas::analytic_gaussian_scale;
+
+    #[test]
+    fn test_make_private_pca() -> Fallible<()> {
+        // the data lies along (1, 1) / sqrt(2)
+        let data = (0..100).map(|i| { let v = (i % 10) as f64 / 10.; vec![v, v] }).collect::<Vec<_>>();
+        let measurement = make_private_pca(2, 1, 2., 1e-6)?;
+        let components = measurement.function.eval(&data)?;
+        assert_eq!(components.len(), 1);
+        let alignment = (components[0][0] + components[0][1]).abs() / 2f64.sqrt();
+        assert!((alignment - 1.).abs() < 1e-6, "components were {:?}", components);
+        assert!(measurement.function.eval(&vec![vec![1.]]).is_err());
+
+        let (eps, del) = (1., 1e-6);
+        let measurement = make_private_pca(2, 1, 2., analytic_gaussian_scale(4., eps, del)?)?;
+        assert!(measurement.privacy_relation.eval(&1, &(eps, del))?);
+        assert!(!measurement.privacy_relation.eval(&2, &(eps, del))?);
+        assert!(make_private_pca(2, 3, 1., 1.).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use std::cmp::Ordering;
+use std::collections::Bound;
+
+use num::Float;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{MaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, IntervalDomain, PairDomain, VectorDomain};
+use crate::error::*;
+use crate::meas::exponential::sample_log_weighted_index;
+use crate::samplers::SampleUniform;
+use crate::traits::DistanceConstant;
+
+fn check_quantile_parameters<T: Float>(lower: T, upper: T, alpha: T, scale: T) -> Fallible<()> {
+    if !(lower.is_finite() && upper.is_finite()) { return fallible!(MakeMeasurement, "bounds must be finite") }
+    if lower > upper { return fallible!(MakeMeasurement, "lower may not be greater than upper") }
+    if !(alpha >= T::zero() && alpha <= T::one()) { return fallible!(MakeMeasurement, "alpha must be within [0, 1]") }
+    if scale.is_nan() || scale <= T::zero() { return fallible!(MakeMeasurement, "scale must be positive") }
+    Ok(())
+}
+
+/// Sample the `alpha`-quantile of `data` with the exponential mechanism.
+///
+/// The sorted data partitions `[lower, upper]` into `n + 1` intervals.
+/// Interval `i` is selected with probability proportional to `length_i * exp(-|i - alpha * n| / scale)`,
+/// and the release is drawn uniformly from the selected interval.
+fn sample_quantile<T>(data: &[T], lower: T, upper: T, alpha: T, scale: T) -> Fallible<T>
+    where T: Float + SampleUniform {
+    let mut points = data.iter().map(|v| v.max(lower).min(upper)).collect::<Vec<T>>();
+    points.sort_by(|l, r| l.partial_cmp(r).unwrap_or(Ordering::Equal));
+    points.insert(0, lower);
+    points.push(upper);
+
+    let target = alpha * num_cast!(data.len(); T)?;
+    let log_weights = points.windows(2).enumerate()
+        .map(|(i, w)| Ok((w[1] - w[0]).ln() - (num_cast!(i; T)? - target).abs() / scale))
+        .collect::<Fallible<Vec<T>>>()?;
+
+    // the interval between equal bounds is the only candidate
+    if lower == upper { return Ok(lower) }
+    let index = sample_log_weighted_index(&log_weights)?;
+
+    let (left, right) = (points[index], points[index + 1]);
+    Ok(left + T::sample_standard_uniform(false)? * (right - left))
+}
+
+/// A [`Measurement`] that releases the `alpha`-quantile of a dataset via the exponential mechanism.
+///
+/// Adding or removing a record changes the rank error of any interval by at most 1,
+/// so the mechanism is `2 * d_in / scale`-DP.
+pub fn make_base_quantile<T>(
+    lower: T, upper: T, alpha: T, scale: T
+) -> Fallible<Measurement<VectorDomain<IntervalDomain<T>>, AllDomain<T>, SymmetricDistance, MaxDivergence<T>>>
+    where T: 'static + DistanceConstant + Float + SampleUniform {
+    check_quantile_parameters(lower, upper, alpha, scale)?;
+    let _2 = num_cast!(2; T)?;
+
+    Ok(Measurement::new(
+        VectorDomain::new(IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &Vec<T>| sample_quantile(arg, lower, upper, alpha, scale)),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(_2 / scale)))
+}
+
+/// A [`Measurement`] that releases the `alpha_lower`- and `alpha_upper`-quantiles of a dataset,
+/// each via the exponential mechanism at the same `scale`.
+///
+/// The pair is post-processed to be ordered, and the budget is the sum of the budgets of both quantiles.
+pub fn make_quantile_interval<T>(
+    lower: T, upper: T, alpha_lower: T, alpha_upper: T, scale: T
+) -> Fallible<Measurement<VectorDomain<IntervalDomain<T>>, PairDomain<AllDomain<T>, AllDomain<T>>, SymmetricDistance, MaxDivergence<T>>>
+    where T: 'static + DistanceConstant + Float + SampleUniform {
+    check_quantile_parameters(lower, upper, alpha_lower, scale)?;
+    check_quantile_parameters(lower, upper, alpha_upper, scale)?;
+    if alpha_lower > alpha_upper { return fallible!(MakeMeasurement, "alpha_lower may not be greater than alpha_upper") }
+    let _4 = num_cast!(4; T)?;
+
+    Ok(Measurement::new(
+        VectorDomain::new(IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?),
+        PairDomain::new(AllDomain::new(), AllDomain::new()),
+        Function::new_fallible(move |arg: &Vec<T>| {
+            let quantile_lower = sample_quantile(arg, lower, upper, alpha_lower, scale)?;
+            let quantile_upper = sample_quantile(arg, lower, upper, alpha_upper, scale)?;
+            Ok(if quantile_lower <= quantile_upper {
+                (quantile_lower, quantile_upper)
+            } else {
+                (quantile_upper, quantile_lower)
+            })
+        }),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(_4 / scale)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_base_quantile() -> Fallible<()> {
+        let measurement = make_base_quantile(0., 100., 0.5, 0.01)?;
+        let arg = (1..=99).map(f64::from).collect::<Vec<_>>();
+        let ret = measurement.function.eval(&arg)?;
+        assert!((ret - 50.).abs() <= 2.);
+
+        assert!(measurement.privacy_relation.eval(&1, &200.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &199.)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_quantile_interval() -> Fallible<()> {
+        let arg = (1..=99).map(f64::from).collect::<Vec<_>>();
+
+        let measurement = make_quantile_interval(0., 100., 0.25, 0.75, 0.01)?;
+        let (lower, upper) = measurement.function.eval(&arg)?;
+        assert!((lower - 25.).abs() <= 2.);
+        assert!((upper - 75.).abs() <= 2.);
+        // the budget is the sum of both quantiles
+        assert!(measurement.privacy_relation.eval(&1, &400.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &399.)?);
+
+        // with little budget the selections are noisy, but remain ordered
+        let measurement = make_quantile_interval(0., 100., 0.25, 0.75, 100.)?;
+        for _ in 0..100 {
+            let (lower, upper) = measurement.function.eval(&arg)?;
+            assert!(lower <= upper);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_base_quantile_bounds() -> Fallible<()> {
+        // data outside the bounds is clamped, so releases always lie within the bounds
+        let measurement = make_base_quantile(0., 10., 0.9, 1.)?;
+        for _ in 0..100 {
+            let ret = measurement.function.eval(&vec![-5., 20., 30., 40.])?;
+            assert!((0. ..=10.).contains(&ret));
+        }
+        assert_eq!(make_base_quantile(3., 3., 0.5, 1.)?.function.eval(&vec![3.])?, 3.);
+        assert!(make_base_quantile(0., 10., 1.5, 1.).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use num::Float;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{DiscreteDistance, MaxDivergence};
+use crate::dom::AllDomain;
+use crate::error::*;
+use crate::samplers::{SampleBernoulli, sample_uniform_u128_below};
+use crate::traits::DistanceConstant;
+
+/// A [`Measurement`] that releases a boolean truthfully with probability `prob`, and flipped otherwise.
+/// The mechanism is `d_in * ln(prob / (1 - prob))`-DP, where `prob` must be in [0.5, 1).
+pub fn make_randomized_response_bool<Q>(
+    prob: Q, constant_time: bool
+) -> Fallible<Measurement<AllDomain<bool>, AllDomain<bool>, DiscreteDistance, MaxDivergence<Q>>>
+    where Q: 'static + DistanceConstant + Float {
+    let _half = num_cast!(0.5; Q)?;
+    if !(prob >= _half && prob < Q::one()) { return fallible!(MakeMeasurement, "prob must be within [0.5, 1)") }
+    let prob_f64 = num_cast!(prob; f64)?;
+
+    Ok(Measurement::new(
+        AllDomain::new(),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &bool|
+            Ok(arg ^ !bool::sample_bernoulli(prob_f64, constant_time)?)),
+        DiscreteDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant((prob / (Q::one() - prob)).ln())))
+}
+
+/// A [`Measurement`] that releases a member of `categories` truthfully with probability `prob`,
+/// and otherwise releases one of the other categories uniformly at random.
+/// With `k` categories, the mechanism is `d_in * ln(prob * (k - 1) / (1 - prob))`-DP, where `prob` must be in [1/k, 1).
+/// Inputs outside of `categories` are rejected when the measurement is invoked.
+pub fn make_randomized_response<T, Q>(
+    categories: HashSet<T>, prob: Q, constant_time: bool
+) -> Fallible<Measurement<AllDomain<T>, AllDomain<T>, DiscreteDistance, MaxDivergence<Q>>>
+    where T: 'static + Eq + Hash + Clone,
+          Q: 'static + DistanceConstant + Float {
+    let categories = categories.into_iter().collect::<Vec<T>>();
+    if categories.len() < 2 { return fallible!(MakeMeasurement, "there must be at least two categories") }
+    let num_categories = num_cast!(categories.len(); Q)?;
+    if !(prob >= Q::one() / num_categories && prob < Q::one()) {
+        return fallible!(MakeMeasurement, "prob must be within [1/k, 1), where k is the number of categories")
+    }
+    let prob_f64 = num_cast!(prob; f64)?;
+    let epsilon = (prob / (Q::one() - prob) * (num_categories - Q::one())).ln();
+
+    Ok(Measurement::new(
+        AllDomain::new(),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &T| {
+            let index = categories.iter().position(|cat| cat == arg)
+                .ok_or_else(|| err!(FailedFunction, "input is not one of the categories"))?;
+            if bool::sample_bernoulli(prob_f64, constant_time)? { return Ok(arg.clone()) }
+            // sample among the other k - 1 categories, skipping over the input
+            let mut other = sample_uniform_u128_below(categories.len() as u128 - 1)? as usize;
+            if other >= index { other += 1 }
+            Ok(categories[other].clone())
+        }),
+        DiscreteDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_randomized_response_bool() -> Fallible<()> {
+        let measurement = make_randomized_response_bool(0.75, false)?;
+        let releases = (0..1000).map(|_| measurement.function.eval(&true))
+            .collect::<Fallible<Vec<bool>>>()?;
+        let frac = releases.iter().filter(|v| **v).count() as f64 / 1000.;
+        assert!((frac - 0.75).abs() < 0.1, "fraction of truthful responses was {}", frac);
+
+        // ln(0.75 / 0.25) = ln(3)
+        assert!(measurement.privacy_relation.eval(&1, &3f64.ln())?);
+        assert!(!measurement.privacy_relation.eval(&1, &1.09)?);
+        assert!(measurement.privacy_relation.eval(&0, &0.)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_randomized_response_bool_invalid() {
+        assert!(make_randomized_response_bool(0.4, false).is_err());
+        assert!(make_randomized_response_bool(1., false).is_err());
+        assert!(make_randomized_response_bool(f64::NAN, false).is_err());
+    }
+
+    #[test]
+    fn test_make_randomized_response() -> Fallible<()> {
+        let categories = vec!["a", "b", "c", "d"].into_iter().collect::<HashSet<_>>();
+        let measurement = make_randomized_response(categories, 0.5, false)?;
+        let releases = (0..1000).map(|_| measurement.function.eval(&"a"))
+            .collect::<Fallible<Vec<_>>>()?;
+        let frac = releases.iter().filter(|v| **v == "a").count() as f64 / 1000.;
+        assert!((frac - 0.5).abs() < 0.1, "fraction of truthful responses was {}", frac);
+        assert!(["b", "c", "d"].iter().all(|c| releases.contains(c)));
+        assert!(measurement.function.eval(&"e").is_err());
+
+        // ln(0.5 * 3 / 0.5) = ln(3)
+        assert!(measurement.privacy_relation.eval(&1, &3f64.ln())?);
+        assert!(!measurement.privacy_relation.eval(&1, &1.09)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_randomized_response_invalid() {
+        let categories = || vec![1, 2, 3].into_iter().collect::<HashSet<_>>();
+        assert!(make_randomized_response(categories(), 0.3, false).is_err());
+        assert!(make_randomized_response(categories(), 1., false).is_err());
+        assert!(make_randomized_response(vec![1].into_iter().collect(), 0.5, false).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::hash::Hash;
+
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{DiscreteDistance, MaxDivergence};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::SampleBernoulli;
+use crate::traits::stable_hash;
+
+/// Encode `value` into a Bloom filter of `num_bits` bits, with `num_hashes` hash functions.
+/// The encoding is deterministic, so that the server can encode candidate values when decoding reports.
+pub fn encode_bloom_filter<T: Hash>(value: &T, num_bits: usize, num_hashes: usize) -> Vec<bool> {
+    let mut bits = vec![false; num_bits];
+    for i in 0..num_hashes {
+        // each hash function is the stable hasher, seeded with its index
+        bits[(stable_hash(i as u64, value) % num_bits as u64) as usize] = true;
+    }
+    bits
+}
+
+/// A [`Measurement`] that encodes a value into a Bloom filter and perturbs it with RAPPOR.
+///
+/// Each bit of the Bloom filter is first perturbed by the permanent randomized response:
+/// it is set to 1 with probability `f / 2`, 0 with probability `f / 2`, and kept otherwise.
+/// Each bit of the permanent response is then reported by the instantaneous randomized response:
+/// as 1 with probability `q` if it is set, and with probability `p` if not.
+///
+/// A single report is `d_in * h * ln(q* (1 - p*) / (p* (1 - q*)))`-DP, where `h` is `num_hashes`,
+/// `q* = f (p + q) / 2 + (1 - f) q` and `p* = f (p + q) / 2 + (1 - f) p`.
+/// Since the permanent response is redrawn on every invocation, this does not cover longitudinal reports.
+pub fn make_rappor<T>(
+    num_bits: usize, num_hashes: usize, f: f64, p: f64, q: f64
+) -> Fallible<Measurement<AllDomain<T>, VectorDomain<AllDomain<bool>>, DiscreteDistance, MaxDivergence<f64>>>
+    where T: 'static + Hash {
+    if num_bits == 0 || num_hashes == 0 { return fallible!(MakeMeasurement, "num_bits and num_hashes must be positive") }
+    if !(0.0..=1.0).contains(&f) || !(0.0..=1.0).contains(&p) || !(0.0..=1.0).contains(&q) {
+        return fallible!(MakeMeasurement, "f, p and q must be probabilities")
+    }
+    let q_star = f * (p + q) / 2. + (1. - f) * q;
+    let p_star = f * (p + q) / 2. + (1. - f) * p;
+    if !(0. < p_star && p_star < q_star && q_star < 1.) {
+        return fallible!(MakeMeasurement, "the reported bits must be informative and randomized: need 0 < p* < q* < 1")
+    }
+    let epsilon = num_hashes as f64 * (q_star * (1. - p_star) / (p_star * (1. - q_star))).ln();
+
+    Ok(Measurement::new(
+        AllDomain::new(),
+        VectorDomain::new_all(),
+        Function::new_fallible(move |arg: &T| encode_bloom_filter(arg, num_bits, num_hashes).into_iter()
+            .map(|bit| {
+                let permanent = if bool::sample_bernoulli(f, false)? {
+                    bool::sample_standard_bernoulli()?
+                } else { bit };
+                bool::sample_bernoulli(if permanent { q } else { p }, false)
+            })
+            .collect()),
+        DiscreteDistance,
+        MaxDivergence::default(),
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_bloom_filter() {
+        let bits = encode_bloom_filter(&"a", 16, 2);
+        assert_eq!(bits.len(), 16);
+        assert!((1..=2).contains(&bits.iter().filter(|b| **b).count()));
+        assert_eq!(bits, encode_bloom_filter(&"a", 16, 2));
+    }
+
+    #[test]
+    fn test_make_rappor() -> Fallible<()> {
+        let measurement = make_rappor::<&str>(16, 2, 0.5, 0.25, 0.75)?;
+        assert_eq!(measurement.function.eval(&"a")?.len(), 16);
+
+        // q* = 0.625, p* = 0.375
+        let epsilon = 2. * (0.625f64 * 0.625 / (0.375 * 0.375)).ln();
+        assert!(measurement.privacy_relation.eval(&1, &epsilon)?);
+        assert!(!measurement.privacy_relation.eval(&1, &(epsilon - 1e-6))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_rappor_invalid() {
+        assert!(make_rappor::<i32>(0, 2, 0.5, 0.25, 0.75).is_err());
+        assert!(make_rappor::<i32>(16, 2, 1.5, 0.25, 0.75).is_err());
+        // no randomization
+        assert!(make_rappor::<i32>(16, 2, 0., 0., 1.).is_err());
+        // uninformative
+        assert!(make_rappor::<i32>(16, 2, 0.5, 0.75, 0.25).is_err());
+    }
+}
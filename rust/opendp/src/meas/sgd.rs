@@ -0,0 +1,93 @@
+use crate::chain::make_chain_mt;
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{SmoothedMaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::meas::gaussian::analytic_gaussian_delta;
+use crate::samplers::SampleGaussian;
+use crate::trans::clamp::clamp_l2_norm;
+use crate::trans::make_random_projection;
+
+/// A [`Measurement`] that clips each per-example gradient in a batch to L2 norm `clip_norm`, sums them,
+/// and adds Gaussian(`scale`) noise to each coordinate of the sum. This is the core step of DP-SGD.
+///
+/// Each record is the gradient of one example, with `num_params` coordinates. The batch may be of any size,
+/// so the step can be applied to a subsampled minibatch; the privacy relation does not include amplification by subsampling.
+/// Adding or removing an example changes the sum by at most `clip_norm` in L2 norm, so the privacy relation is
+/// that of the analytic Gaussian mechanism with sensitivity `d_in * clip_norm`.
+pub fn make_clipped_gradient_sum_gaussian(
+    num_params: usize, clip_norm: f64, scale: f64
+) -> Fallible<Measurement<VectorDomain<VectorDomain<AllDomain<f64>>>, VectorDomain<AllDomain<f64>>, SymmetricDistance, SmoothedMaxDivergence<f64>>> {
+    if num_params == 0 { return fallible!(MakeMeasurement, "num_params must be positive") }
+    if !(clip_norm.is_finite() && clip_norm > 0.) { return fallible!(MakeMeasurement, "clip_norm must be positive and finite") }
+    if !(scale.is_finite() && scale > 0.) { return fallible!(MakeMeasurement, "scale must be positive and finite") }
+
+    Ok(Measurement::new(
+        VectorDomain::new(VectorDomain::new_all()),
+        VectorDomain::new_all(),
+        Function::new_fallible(move |gradients: &Vec<Vec<f64>>| {
+            let mut sum = vec![0.; num_params];
+            for gradient in gradients {
+                if gradient.len() != num_params || gradient.iter().any(|v| !v.is_finite()) {
+                    return fallible!(FailedFunction, "each gradient must have {} finite coordinates", num_params)
+                }
+                sum.iter_mut().zip(clamp_l2_norm(gradient, clip_norm)).for_each(|(s, v)| *s += v);
+            }
+            sum.into_iter().map(|v| f64::sample_gaussian(v, scale, false)).collect()
+        }),
+        SymmetricDistance,
+        SmoothedMaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |d_in: &u32, &(eps, del): &(f64, f64)| {
+            if eps.is_nan() || eps <= 0. { return fallible!(InvalidDistance, "epsilon must be positive") }
+            if del.is_nan() || del <= 0. { return fallible!(InvalidDistance, "delta must be positive") }
+            if *d_in == 0 { return Ok(true) }
+            Ok(analytic_gaussian_delta(*d_in as f64 * clip_norm, scale, eps) <= del)
+        })))
+}
+
+/// A [`Measurement`] that projects each record by a public `projection` matrix,
+/// and releases the noisy sum of the projected records, clipped to L2 norm `clip_norm`.
+/// This is [`make_random_projection`] chained with [`make_clipped_gradient_sum_gaussian`],
+/// and has the same privacy relation as the latter.
+pub fn make_projected_sum_gaussian(
+    projection: Vec<Vec<f64>>, clip_norm: f64, scale: f64
+) -> Fallible<Measurement<VectorDomain<VectorDomain<AllDomain<f64>>>, VectorDomain<AllDomain<f64>>, SymmetricDistance, SmoothedMaxDivergence<f64>>> {
+    let output_dim = projection.len();
+    make_chain_mt(
+        &make_clipped_gradient_sum_gaussian(output_dim, clip_norm, scale)?,
+        &make_random_projection(projection)?,
+        None)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meas::analytic_gaussian_scale;
+
+    #[test]
+    fn test_make_clipped_gradient_sum_gaussian() -> Fallible<()> {
+        let measurement = make_clipped_gradient_sum_gaussian(2, 1., 1e-6)?;
+        // the first gradient is clipped to (0.6, 0.8)
+        let sum = measurement.function.eval(&vec![vec![3., 4.], vec![0.1, -0.2]])?;
+        assert!((sum[0] - 0.7).abs() < 1e-4 && (sum[1] - 0.6).abs() < 1e-4, "sum was {:?}", sum);
+        assert!(measurement.function.eval(&vec![vec![1.]]).is_err());
+        assert_eq!(measurement.function.eval(&vec![])?.len(), 2);
+
+        let (eps, del) = (1., 1e-6);
+        let scale = analytic_gaussian_scale(2., eps, del)?;
+        let measurement = make_clipped_gradient_sum_gaussian(2, 2., scale)?;
+        assert!(measurement.privacy_relation.eval(&1, &(eps, del))?);
+        assert!(!measurement.privacy_relation.eval(&2, &(eps, del))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_projected_sum_gaussian() -> Fallible<()> {
+        let measurement = make_projected_sum_gaussian(vec![vec![1., 1., 0.]], 10., 1e-6)?;
+        let sum = measurement.function.eval(&vec![vec![1., 2., 3.], vec![1., 0., 100.]])?;
+        assert!((sum[0] - 4.).abs() < 1e-4, "sum was {:?}", sum);
+        assert!(make_projected_sum_gaussian(vec![], 1., 1.).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use crate::core::{Function, Measurement, PrivacyRelation};
+use crate::dist::{MaxDivergence, SymmetricDistance};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::{SampleCauchy, SampleGaussian};
+
+/// A statistic whose local sensitivity can be bounded, for use with smooth sensitivity.
+pub trait LocalSensitivity<T> {
+    /// The statistic to be released.
+    fn statistic(&self, data: &[T]) -> Fallible<f64>;
+    /// An upper bound on the local sensitivity of the statistic, over all datasets within symmetric distance `k` of `data`.
+    fn local_sensitivity_at_distance(&self, data: &[T], k: u32) -> Fallible<f64>;
+    /// An upper bound on the local sensitivity of the statistic at any dataset.
+    fn global_sensitivity(&self) -> f64;
+}
+
+/// The `beta`-smooth upper bound on the local sensitivity of `statistic` at `data`,
+/// `max_k exp(-beta k) A_k(data)`, where `A_k` is the local sensitivity at distance `k`.
+pub fn smooth_sensitivity<T, S: LocalSensitivity<T>>(statistic: &S, data: &[T], beta: f64) -> Fallible<f64> {
+    if beta.is_nan() || beta <= 0. { return fallible!(FailedFunction, "beta must be positive") }
+    let global = statistic.global_sensitivity();
+    let mut smooth = 0f64;
+    let mut k = 0u32;
+    // terms beyond the point where even the global sensitivity is discounted below the running max cannot contribute
+    while (-beta * k as f64).exp() * global > smooth {
+        let local = statistic.local_sensitivity_at_distance(data, k)?;
+        smooth = smooth.max((-beta * k as f64).exp() * local.min(global));
+        if local >= global { break }
+        k = k.checked_add(1).ok_or_else(|| err!(FailedFunction, "smooth sensitivity did not converge"))?;
+    }
+    Ok(smooth)
+}
+
+// Student's t with `degrees_of_freedom` degrees of freedom, as a standard normal over the root of a scaled chi-squared
+fn sample_student_t(degrees_of_freedom: u32) -> Fallible<f64> {
+    // with one degree of freedom, the distribution is the standard Cauchy, which can be sampled directly
+    if degrees_of_freedom == 1 { return f64::sample_cauchy(0., 1., false) }
+    let numer = f64::sample_gaussian(0., 1., false)?;
+    let chi_squared = (0..degrees_of_freedom)
+        .map(|_| f64::sample_gaussian(0., 1., false).map(|z| z * z))
+        .sum::<Fallible<f64>>()?;
+    Ok(numer / (chi_squared / degrees_of_freedom as f64).sqrt())
+}
+
+/// A [`Measurement`] that releases `statistic` with noise from Student's t distribution, scaled to its smooth sensitivity.
+///
+/// With `d` degrees of freedom, the log-density of the noise changes by at most `|shift| (d + 1) / (2 sqrt(d))`
+/// under a shift, and by at most `d |log(dilation)|` under a dilation. Calibrating both to `epsilon / 2`,
+/// the release is `statistic + smooth_sensitivity * (d + 1) / (epsilon sqrt(d)) * T`,
+/// where the smooth sensitivity is taken with `beta = epsilon / (2 d)`.
+/// The mechanism is `d_in * epsilon`-DP. Setting `degrees_of_freedom` to 1 gives Cauchy noise.
+pub fn make_smooth_sensitivity_student_t<T, S>(
+    statistic: S, epsilon: f64, degrees_of_freedom: u32
+) -> Fallible<Measurement<VectorDomain<AllDomain<T>>, AllDomain<f64>, SymmetricDistance, MaxDivergence<f64>>>
+    where T: 'static,
+          S: 'static + LocalSensitivity<T> {
+    if epsilon.is_nan() || epsilon <= 0. { return fallible!(MakeMeasurement, "epsilon must be positive") }
+    if degrees_of_freedom == 0 { return fallible!(MakeMeasurement, "degrees_of_freedom must be positive") }
+    let d = degrees_of_freedom as f64;
+    let alpha = epsilon * d.sqrt() / (d + 1.);
+    let beta = epsilon / (2. * d);
+
+    Ok(Measurement::new(
+        VectorDomain::new_all(),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &Vec<T>| {
+            let scale = smooth_sensitivity(&statistic, arg, beta)? / alpha;
+            Ok(statistic.statistic(arg)? + scale * sample_student_t(degrees_of_freedom)?)
+        }),
+        SymmetricDistance,
+        MaxDivergence::default(),
+        // privacy for d_in > 1 follows from group privacy
+        PrivacyRelation::new_from_constant(epsilon)))
+}
+
+/// A [`Measurement`] that releases `statistic` with Cauchy noise, scaled to its smooth sensitivity.
+/// See [`make_smooth_sensitivity_student_t`], of which this is the case with one degree of freedom.
+///
+/// The Cauchy density `1 / (pi (1 + z^2))` is admissible under both shifts and dilations, so the release is pure
+/// `epsilon`-DP, with noise of scale `2 * smooth_sensitivity / epsilon` and `beta = epsilon / 2`.
+/// The noise has no mean or variance, so releases should be summarized by their median, not their mean.
+pub fn make_smooth_sensitivity_cauchy<T, S>(
+    statistic: S, epsilon: f64
+) -> Fallible<Measurement<VectorDomain<AllDomain<T>>, AllDomain<f64>, SymmetricDistance, MaxDivergence<f64>>>
+    where T: 'static,
+          S: 'static + LocalSensitivity<T> {
+    make_smooth_sensitivity_student_t(statistic, epsilon, 1)
+}
+
+/// The median of data clamped to `[lower, upper]`, with a rank-based bound on its local sensitivity.
+///
+/// The median of `n` records is the `m = ceil(n / 2)`th smallest, where the sorted data is padded with `lower`
+/// below and `upper` above. The median of any dataset within symmetric distance `j` of `data` lies between the
+/// `(m - j)`th and `(m + j)`th records, so the local sensitivity at distance `k` is at most `x_(m+k+1) - x_(m-k-1)`.
+#[derive(Clone, Debug)]
+pub struct Median {
+    lower: f64,
+    upper: f64,
+}
+
+impl Median {
+    pub fn new(lower: f64, upper: f64) -> Fallible<Self> {
+        if !(lower.is_finite() && upper.is_finite()) { return fallible!(MakeMeasurement, "bounds must be finite") }
+        if lower > upper { return fallible!(MakeMeasurement, "lower may not be greater than upper") }
+        Ok(Median { lower, upper })
+    }
+
+    fn sorted(&self, data: &[f64]) -> Fallible<Vec<f64>> {
+        if data.iter().any(|v| v.is_nan()) { return fallible!(FailedFunction, "data may not contain nan") }
+        let mut sorted = data.iter().map(|v| v.max(self.lower).min(self.upper)).collect::<Vec<f64>>();
+        sorted.sort_by(|l, r| l.partial_cmp(r).unwrap_assert("nan values have been rejected"));
+        Ok(sorted)
+    }
+
+    // the `rank`th smallest record, counting from 1, where out-of-range ranks are padded with the bounds
+    fn order_statistic(&self, sorted: &[f64], rank: i64) -> f64 {
+        if rank < 1 { self.lower } else { sorted.get(rank as usize - 1).cloned().unwrap_or(self.upper) }
+    }
+}
+
+impl LocalSensitivity<f64> for Median {
+    fn statistic(&self, data: &[f64]) -> Fallible<f64> {
+        let sorted = self.sorted(data)?;
+        let median_rank = (sorted.len() as i64 + 1) / 2;
+        Ok(self.order_statistic(&sorted, median_rank))
+    }
+    fn local_sensitivity_at_distance(&self, data: &[f64], k: u32) -> Fallible<f64> {
+        let sorted = self.sorted(data)?;
+        let median_rank = (sorted.len() as i64 + 1) / 2;
+        let k = k as i64;
+        Ok(self.order_statistic(&sorted, median_rank + k + 1) - self.order_statistic(&sorted, median_rank - k - 1))
+    }
+    fn global_sensitivity(&self) -> f64 { self.upper - self.lower }
+}
+
+/// A [`Measurement`] that releases the median of data clamped to `[lower, upper]`,
+/// with Cauchy noise scaled to the smooth sensitivity of the [`Median`].
+/// The release is clamped to `[lower, upper]`, and the mechanism is `d_in * epsilon`-DP.
+pub fn make_private_median(
+    lower: f64, upper: f64, epsilon: f64
+) -> Fallible<Measurement<VectorDomain<AllDomain<f64>>, AllDomain<f64>, SymmetricDistance, MaxDivergence<f64>>> {
+    let measurement = make_smooth_sensitivity_cauchy(Median::new(lower, upper)?, epsilon)?;
+    let function = measurement.function.function.clone();
+
+    Ok(Measurement::new(
+        measurement.input_domain,
+        measurement.output_domain,
+        // clamping is postprocessing
+        Function::new_fallible(move |arg: &Vec<f64>| Ok(function(arg)?.max(lower).min(upper))),
+        measurement.input_metric,
+        measurement.output_measure,
+        measurement.privacy_relation))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the maximum of data in [0, 1], where the local sensitivity is the gap to the nearest record,
+    //     and k changes may remove the k largest records
+    struct Max;
+    impl LocalSensitivity<f64> for Max {
+        fn statistic(&self, data: &[f64]) -> Fallible<f64> {
+            Ok(data.iter().cloned().fold(0., f64::max))
+        }
+        fn local_sensitivity_at_distance(&self, data: &[f64], k: u32) -> Fallible<f64> {
+            let mut sorted = data.to_vec();
+            sorted.sort_by(|l, r| r.partial_cmp(l).unwrap());
+            let k = k as usize;
+            if k + 1 >= sorted.len() { return Ok(self.global_sensitivity()) }
+            Ok(1. - sorted[k + 1])
+        }
+        fn global_sensitivity(&self) -> f64 { 1. }
+    }
+
+    #[test]
+    fn test_smooth_sensitivity() -> Fallible<()> {
+        let data = vec![1., 1., 1., 1.];
+        // A_0 = A_1 = A_2 = 0, A_3 = 1
+        let smooth = smooth_sensitivity(&Max, &data, 0.5)?;
+        assert!((smooth - (-1.5f64).exp()).abs() < 1e-12);
+        // the bound never exceeds the global sensitivity
+        assert_eq!(smooth_sensitivity(&Max, &[0.], 0.5)?, 1.);
+        assert!(smooth_sensitivity(&Max, &data, 0.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_smooth_sensitivity_cauchy() -> Fallible<()> {
+        let data = vec![1.; 100];
+        let measurement = make_smooth_sensitivity_cauchy(Max, 1.)?;
+        let releases = (0..101).map(|_| measurement.function.eval(&data)).collect::<Fallible<Vec<f64>>>()?;
+        let mut sorted = releases.clone();
+        sorted.sort_by(|l, r| l.partial_cmp(r).unwrap());
+        // the local sensitivity vanishes on large datasets, so the median release is close to the statistic
+        assert!((sorted[50] - 1.).abs() < 1e-6, "median release was {}", sorted[50]);
+
+        assert!(measurement.privacy_relation.eval(&1, &1.)?);
+        assert!(!measurement.privacy_relation.eval(&2, &1.)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_smooth_sensitivity_student_t() -> Fallible<()> {
+        let measurement = make_smooth_sensitivity_student_t(Max, 1., 3)?;
+        assert!(measurement.function.eval(&vec![0.5, 0.25])?.is_finite());
+        assert!(make_smooth_sensitivity_student_t(Max, 1., 0).is_err());
+        assert!(make_smooth_sensitivity_student_t(Max, 0., 3).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_median_local_sensitivity() -> Fallible<()> {
+        let median = Median::new(0., 10.)?;
+        let data = vec![1., 2., 3., 4., 5.];
+        assert_eq!(median.statistic(&data)?, 3.);
+        // x_4 - x_2, then x_5 - x_1, then the bounds
+        assert_eq!(median.local_sensitivity_at_distance(&data, 0)?, 2.);
+        assert_eq!(median.local_sensitivity_at_distance(&data, 1)?, 4.);
+        assert_eq!(median.local_sensitivity_at_distance(&data, 2)?, 10.);
+        // records are clamped
+        assert_eq!(median.statistic(&[-5., 20., 20.])?, 10.);
+        assert!(median.statistic(&[f64::NAN]).is_err());
+        assert!(Median::new(1., 0.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_private_median() -> Fallible<()> {
+        let data = vec![5.; 1000];
+        let measurement = make_private_median(0., 10., 1.)?;
+        let release = measurement.function.eval(&data)?;
+        assert!((0. ..=10.).contains(&release));
+        // the local sensitivity is zero far from the median
+        assert!((release - 5.).abs() < 1e-6, "release was {}", release);
+        assert!(measurement.privacy_relation.eval(&1, &1.)?);
+        assert!(!measurement.privacy_relation.eval(&2, &1.)?);
+        Ok(())
+    }
+}
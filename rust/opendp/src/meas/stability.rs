@@ -7,7 +7,7 @@ use crate::core::{Measurement, Function, PrivacyRelation, SensitivityMetric};
 use crate::dist::{L1Distance, L2Distance, SmoothedMaxDivergence};
 use crate::dom::{AllDomain, MapDomain, SizedDomain};
 use crate::samplers::{SampleLaplace, SampleGaussian};
-use crate::error::Fallible;
+use crate::error::*;
 
 // TIK: Type of Input Key
 // TIC: Type of Input Count
@@ -95,12 +95,96 @@ pub fn make_base_stability<MI, TIK, TIC>(
     ))
 }
 
+/// A [`Measurement`] that releases a histogram over a data-dependent set of keys.
+///
+/// Laplace(`scale`) noise is added to each count, and keys whose noisy count falls below `threshold` are suppressed.
+/// Keys with a count of zero are dropped before noise is added, so they are never released.
+/// Neighboring histograms may differ in keys that only one of them contains, which can only be released when
+/// their noisy counts exceed the threshold. When the counts are `d_in`-close in the L1 distance,
+/// at most `d_in` such keys exist, and by a union bound the mechanism is
+/// (`d_in / scale`, `max(exp((d_in - threshold) / scale), d_in * exp((1 - threshold) / scale)) / 2`)-DP,
+/// for any `threshold >= d_in`.
+/// To satisfy a given delta, use `threshold = max(d_in + scale * ln(1 / (2 delta)), 1 + scale * ln(d_in / (2 delta)))`.
+pub fn make_base_threshold_histogram<K>(
+    scale: f64, threshold: f64
+) -> Fallible<Measurement<MapDomain<AllDomain<K>, AllDomain<u32>>, MapDomain<AllDomain<K>, AllDomain<f64>>, L1Distance<f64>, SmoothedMaxDivergence<f64>>>
+    where K: 'static + Eq + Hash + Clone {
+    if scale.is_nan() || scale <= 0. {
+        return fallible!(MakeMeasurement, "scale must be positive")
+    }
+    if threshold.is_nan() || threshold.is_sign_negative() {
+        return fallible!(MakeMeasurement, "threshold must not be negative")
+    }
+
+    Ok(Measurement::new(
+        MapDomain::new_all(),
+        MapDomain::new_all(),
+        Function::new_fallible(move |data: &HashMap<K, u32>| {
+            data.iter()
+                .filter(|(_k, c)| **c > 0)
+                .map(|(k, c)| Ok((k.clone(), f64::sample_laplace(*c as f64, scale, false)?)))
+                .filter(|res| res.as_ref().map(|(_k, c)| *c >= threshold).unwrap_or(true))
+                .collect()
+        }),
+        L1Distance::default(),
+        SmoothedMaxDivergence::default(),
+        PrivacyRelation::new_fallible(move |&d_in: &f64, &(eps, del): &(f64, f64)| {
+            if d_in.is_sign_negative() {
+                return fallible!(InvalidDistance, "threshold histogram: input sensitivity must be non-negative")
+            }
+            if eps.is_sign_negative() || del.is_sign_negative() {
+                return fallible!(InvalidDistance, "threshold histogram: epsilon and delta must be non-negative")
+            }
+            // zero counts are dropped, so the counts of keys only in one histogram are at least one and total at most d_in.
+            // By a union bound, the chance of releasing any of them is at most the sum of their release probabilities,
+            //     which is convex in the counts, so it is greatest when they are all in one key,
+            //     or spread over d_in keys with a count of one each
+            if threshold < d_in { return Ok(false) }
+            let concentrated = ((d_in - threshold) / scale).exp() / 2.;
+            let spread = d_in * ((1. - threshold) / scale).exp() / 2.;
+            Ok(eps >= d_in / scale && del >= concentrated.max(spread))
+        })
+    ))
+}
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_base_threshold_histogram() -> Fallible<()> {
+        let mut arg = HashMap::new();
+        arg.insert("common", 1000);
+        arg.insert("rare", 1);
+        let (scale, del) = (1., 1e-6);
+        let threshold = 1. + scale * (1. / (2. * del)).ln();
+        let measurement = make_base_threshold_histogram(scale, threshold)?;
+        let ret = measurement.function.eval(&arg)?;
+        assert!(ret.contains_key("common"));
+        assert!(!ret.contains_key("rare"));
+
+        // a key with an explicit zero count is never released, even with no threshold
+        let measurement_zero = make_base_threshold_histogram(scale, 0.)?;
+        let zeros = (0..100).map(|i| (i, 0)).collect::<HashMap<_, _>>();
+        assert!(measurement_zero.function.eval(&zeros)?.is_empty());
+
+        assert!(measurement.privacy_relation.eval(&1., &(1., del * 1.001))?);
+        assert!(!measurement.privacy_relation.eval(&1., &(1., del / 2.))?);
+        assert!(!measurement.privacy_relation.eval(&1., &(0.9, del))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_base_threshold_histogram_spread() -> Fallible<()> {
+        // two one-sided keys with a count of one are each released with probability exp(-0.1) / 2
+        let measurement = make_base_threshold_histogram::<&str>(10., 2.)?;
+        let released_either = 1. - (1. - (-0.1f64).exp() / 2.).powi(2);
+        assert!(!measurement.privacy_relation.eval(&2., &(1., released_either))?);
+        assert!(measurement.privacy_relation.eval(&2., &(1., (-0.1f64).exp()))?);
+        Ok(())
+    }
+
     #[test]
     fn test_base_stability() -> Fallible<()> {
         let mut arg = HashMap::new();
@@ -0,0 +1,126 @@
+//! A builder for chaining a pipeline under a fixed input distance.
+//!
+//! A [`Pipeline`] carries a declared `d_in` alongside the chained [`Transformation`].
+//! Each stage pushes the running intermediate distance `d_mid` through its forward map,
+//! so that the budget of the final [`Measurement`] can be read off without supplying hints.
+
+use std::rc::Rc;
+
+use num::{One, Zero};
+
+use crate::chain::{make_chain_mt, make_chain_tt};
+use crate::core::{Domain, Measure, Measurement, Metric, StabilityRelation, Transformation};
+use crate::error::*;
+use crate::traits::Bisect;
+
+fn forward<MI: Metric, MO: Metric>(relation: &StabilityRelation<MI, MO>, d_in: &MI::Distance) -> Fallible<MO::Distance> {
+    let forward_map = relation.forward_map.as_ref()
+        .ok_or_else(|| err!(FailedRelation, "every stage of a pipeline must have a forward map"))?;
+    forward_map(d_in).map(|v| *v)
+}
+
+/// A chain of transformations, together with the input distance it was built for,
+/// and the distance between outputs that the chain guarantees for that input distance.
+pub struct Pipeline<DI: Domain, DO: Domain, MI: Metric, MO: Metric> {
+    pub transformation: Transformation<DI, DO, MI, MO>,
+    pub d_in: MI::Distance,
+    pub d_mid: MO::Distance,
+}
+
+impl<DI, DO, MI, MO> Pipeline<DI, DO, MI, MO>
+    where DI: 'static + Domain,
+          DO: 'static + Domain,
+          MI: 'static + Metric,
+          MO: 'static + Metric {
+    pub fn new(transformation: Transformation<DI, DO, MI, MO>, d_in: MI::Distance) -> Fallible<Self> {
+        let d_mid = forward(&transformation.stability_relation, &d_in)?;
+        Ok(Pipeline { transformation, d_in, d_mid })
+    }
+
+    /// Append a transformation to the pipeline, updating `d_mid` with the stage's forward map.
+    pub fn then<DX, MX>(self, transformation1: &Transformation<DO, DX, MO, MX>) -> Fallible<Pipeline<DI, DX, MI, MX>>
+        where DX: 'static + Domain,
+              MX: 'static + Metric {
+        let d_mid = forward(&transformation1.stability_relation, &self.d_mid)?;
+        Ok(Pipeline {
+            transformation: make_chain_tt(transformation1, &self.transformation, None)?,
+            d_in: self.d_in,
+            d_mid,
+        })
+    }
+
+    /// Terminate the pipeline with a measurement.
+    pub fn release<DX, MX>(self, measurement1: &Measurement<DO, DX, MO, MX>) -> Fallible<MeasurementPipeline<DI, DX, MI, MX, MO>>
+        where DX: 'static + Domain,
+              MX: 'static + Measure {
+        Ok(MeasurementPipeline {
+            measurement: make_chain_mt(measurement1, &self.transformation, None)?,
+            d_in: self.d_in,
+            d_mid: self.d_mid,
+            relation1: measurement1.privacy_relation.relation.clone(),
+        })
+    }
+}
+
+/// A [`Pipeline`] that has been terminated with a measurement.
+pub struct MeasurementPipeline<DI: Domain, DO: Domain, MI: Metric, MO: Measure, MX: Metric> {
+    pub measurement: Measurement<DI, DO, MI, MO>,
+    pub d_in: MI::Distance,
+    pub d_mid: MX::Distance,
+    relation1: Rc<dyn Fn(&MX::Distance, &MO::Distance) -> Fallible<bool>>,
+}
+
+impl<DI: Domain, DO: Domain, MI: Metric, MO: Measure, MX: Metric> MeasurementPipeline<DI, DO, MI, MO, MX> {
+    /// Check if the measurement is `d_out`-private for the declared `d_in`.
+    pub fn check(&self, d_out: &MO::Distance) -> Fallible<bool> {
+        self.measurement.privacy_relation.eval(&self.d_in, d_out)
+    }
+
+    /// The smallest privacy loss of the pipeline for the declared `d_in`.
+    /// The final measurement has no forward map, so its relation is searched by bisection at `d_mid`.
+    pub fn privacy_loss(&self) -> Fallible<MO::Distance> where MO::Distance: Bisect {
+        let relation1 = |d_out: &MO::Distance| (self.relation1)(&self.d_mid, d_out);
+        let mut lower = MO::Distance::zero();
+        if relation1(&lower)? { return Ok(lower) }
+
+        // grow the upper bound until the relation holds
+        let mut upper = MO::Distance::one();
+        while !relation1(&upper)? {
+            lower = upper.clone();
+            upper = upper.double().ok_or_else(|| err!(FailedRelation, "privacy loss is not representable"))?;
+        }
+        // the relation holds at upper and fails at lower
+        while let Some(mid) = MO::Distance::midpoint(&lower, &upper) {
+            if relation1(&mid)? { upper = mid } else { lower = mid }
+        }
+        Ok(upper)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::dist::SymmetricDistance;
+    use crate::dom::{AllDomain, VectorDomain};
+    use crate::meas::make_base_laplace;
+    use crate::trans::{make_bounded_sum, make_clamp};
+
+    use super::*;
+
+    #[test]
+    fn test_pipeline() -> Fallible<()> {
+        let pipeline = Pipeline::new(make_clamp::<VectorDomain<AllDomain<f64>>, SymmetricDistance>(0., 10.)?, 1)?;
+        assert_eq!(pipeline.d_mid, 1);
+        let pipeline = pipeline.then(&make_bounded_sum(0., 10.)?)?;
+        assert_eq!(pipeline.d_mid, 10.);
+        let pipeline = pipeline.release(&make_base_laplace::<AllDomain<f64>>(2.)?)?;
+
+        // epsilon = d_mid / scale
+        assert!(pipeline.check(&5.)?);
+        assert!(!pipeline.check(&4.9)?);
+        assert_eq!(pipeline.privacy_loss()?, 5.);
+
+        let _ret = pipeline.measurement.function.eval(&vec![1., 2., 30.])?;
+        Ok(())
+    }
+}
@@ -90,13 +90,13 @@ mod tests {
 
     #[test]
     fn test_poly_measurement() -> Fallible<()> {
-        let op_plain = meas::make_base_laplace::<AllDomain<_>>(0.0)?;
-        let arg = 99.9;
+        let op_plain = meas::make_base_laplace::<AllDomain<_>>(1e-10)?;
+        let arg: f64 = 99.9;
         let res_plain = op_plain.function.eval(&arg)?;
-        assert_eq!(res_plain, arg);
+        assert!((res_plain - arg).abs() < 1e-6);
         let op_poly = op_plain.into_poly();
         let res_poly = op_poly.function.eval_poly::<f64>(&arg)?;
-        assert_eq!(res_poly, arg);
+        assert!((res_poly - arg).abs() < 1e-6);
         let res_bogus = op_poly.function.eval_poly::<i32>(&arg);
         assert_eq!(res_bogus.err().unwrap_test().variant, ErrorVariant::FailedCast);
         Ok(())
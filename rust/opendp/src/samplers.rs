@@ -1,4 +1,5 @@
 use std::cmp;
+use std::convert::TryFrom;
 use std::ops::{AddAssign, Neg, SubAssign, Sub};
 
 use ieee754::Ieee754;
@@ -522,4 +523,140 @@ impl SampleGaussian for f32 {
         let uniform_sample = f64::sample_standard_uniform(constant_time)?;
         Ok(shift + scale * std::f32::consts::SQRT_2 * (erf::erfc_inv(2.0 * uniform_sample) as f32))
     }
-}
\ No newline at end of file
+}
+
+pub trait SampleCauchy: Sized {
+    /// Generates a draw from a Cauchy(shift, scale) distribution by inversion of the cdf.
+    ///
+    /// The Cauchy distribution has no mean or variance, so averaging many draws does not reduce the noise.
+    ///
+    /// # Arguments
+    /// * `shift` - The median of the Cauchy distribution.
+    /// * `scale` - The half-width at half-maximum of the Cauchy distribution.
+    /// * `constant_time` - Force underlying computations to run in constant time.
+    ///
+    /// # Return
+    /// Draw from Cauchy(shift, scale)
+    ///
+    /// # Example
+    /// ```
+    /// use opendp::samplers::SampleCauchy;
+    /// let cauchy = f64::sample_cauchy(0.0, 1.0, false);
+    /// ```
+    fn sample_cauchy(shift: Self, scale: Self, constant_time: bool) -> Fallible<Self>;
+}
+
+impl<T: num::Float + SampleUniform> SampleCauchy for T {
+    fn sample_cauchy(shift: Self, scale: Self, constant_time: bool) -> Fallible<Self> {
+        let half = num_cast!(0.5; T)?;
+        let pi = num_cast!(std::f64::consts::PI; T)?;
+        let uniform = T::sample_standard_uniform(constant_time)?;
+        Ok(shift + scale * (pi * (uniform - half)).tan())
+    }
+}
+
+// EXACT DISCRETE SAMPLERS
+// The following samplers use only integer arithmetic, following
+// Canonne, Kamath and Steinke, "The Discrete Gaussian for Differential Privacy" (2020).
+
+/// Sample uniformly from {0, 1, ..., upper - 1}, by rejection.
+pub(crate) fn sample_uniform_u128_below(upper: u128) -> Fallible<u128> {
+    if upper == 0 { return fallible!(FailedFunction, "upper must be positive") }
+    // largest multiple of upper that fits in a u128, to avoid modulo bias
+    let threshold = u128::MAX - u128::MAX % upper;
+    loop {
+        let mut buffer = [0u8; 16];
+        fill_bytes(&mut buffer)?;
+        let sample = u128::from_ne_bytes(buffer);
+        if sample < threshold { return Ok(sample % upper) }
+    }
+}
+
+/// Shuffle `data` uniformly at random, with the Fisher-Yates algorithm.
+pub(crate) fn shuffle<T>(data: &mut [T]) -> Fallible<()> {
+    for i in (1..data.len()).rev() {
+        let j = sample_uniform_u128_below(i as u128 + 1)? as usize;
+        data.swap(i, j);
+    }
+    Ok(())
+}
+
+/// Sample a bit with probability `numer / denom`.
+fn sample_bernoulli_rational(numer: u128, denom: u128) -> Fallible<bool> {
+    if numer > denom { return fallible!(FailedFunction, "probability is not within [0, 1]") }
+    Ok(sample_uniform_u128_below(denom)? < numer)
+}
+
+/// Sample a bit with probability `exp(-numer / denom)`.
+fn sample_bernoulli_exp(numer: u128, denom: u128) -> Fallible<bool> {
+    if denom == 0 { return fallible!(FailedFunction, "denominator must be positive") }
+    // exp(-gamma) = exp(-1)^floor(gamma) * exp(-(gamma - floor(gamma)))
+    for _ in 0..numer / denom {
+        if !sample_bernoulli_exp_unit(1, 1)? { return Ok(false) }
+    }
+    sample_bernoulli_exp_unit(numer % denom, denom)
+}
+
+/// Sample a bit with probability `exp(-numer / denom)`, where `numer / denom` is in [0, 1].
+fn sample_bernoulli_exp_unit(numer: u128, denom: u128) -> Fallible<bool> {
+    let mut k: u128 = 1;
+    loop {
+        let scaled_denom = denom.checked_mul(k)
+            .ok_or_else(|| err!(FailedFunction, "overflow when sampling bernoulli"))?;
+        if !sample_bernoulli_rational(numer, scaled_denom)? { return Ok(k % 2 == 1) }
+        k += 1;
+    }
+}
+
+/// Sample from the discrete Laplace distribution with integer scale `scale`.
+fn sample_discrete_laplace(scale: u128) -> Fallible<i128> {
+    loop {
+        let u = sample_uniform_u128_below(scale)?;
+        if !sample_bernoulli_exp(u, scale)? { continue }
+        let mut v: u128 = 0;
+        while sample_bernoulli_exp(1, 1)? { v += 1 }
+        let magnitude = u + scale * v;
+        let negative = bool::sample_standard_bernoulli()?;
+        if negative && magnitude == 0 { continue }
+        let magnitude = i128::try_from(magnitude)
+            .map_err(|_| err!(FailedFunction, "discrete laplace sample overflowed"))?;
+        return Ok(if negative { -magnitude } else { magnitude })
+    }
+}
+
+pub trait SampleDiscreteGaussian: Sized {
+    /// Sample from the discrete Gaussian distribution centered at `shift`, with variance `var_numer / var_denom`.
+    /// No floating-point arithmetic is used.
+    fn sample_discrete_gaussian(shift: Self, var_numer: u64, var_denom: u64) -> Fallible<Self>;
+}
+
+impl SampleDiscreteGaussian for i64 {
+    fn sample_discrete_gaussian(shift: Self, var_numer: u64, var_denom: u64) -> Fallible<Self> {
+        if var_denom == 0 { return fallible!(FailedFunction, "variance denominator must be positive") }
+        if var_numer == 0 { return Ok(shift) }
+        let (numer, denom) = (var_numer as u128, var_denom as u128);
+
+        // t = floor(sigma) + 1, where floor(sigma) is the largest s such that s^2 <= numer / denom
+        let (mut lower, mut upper) = (0_u128, 1_u128 << 32);
+        while upper - lower > 1 {
+            let mid = lower + (upper - lower) / 2;
+            if mid * mid * denom <= numer { lower = mid } else { upper = mid }
+        }
+        let t = lower + 1;
+
+        let overflow = || err!(FailedFunction, "discrete gaussian sample overflowed");
+        let accept_denom = (2 * numer).checked_mul(t * t).and_then(|v| v.checked_mul(denom)).ok_or_else(overflow)?;
+        loop {
+            let y = sample_discrete_laplace(t)?;
+            // accept with probability exp(-(|y| - sigma^2 / t)^2 / (2 sigma^2))
+            //     = exp(-(|y| t denom - numer)^2 / (2 numer t^2 denom))
+            let scaled_y = y.unsigned_abs().checked_mul(t * denom).ok_or_else(overflow)?;
+            let diff = scaled_y.max(numer) - scaled_y.min(numer);
+            let accept_numer = diff.checked_pow(2).ok_or_else(overflow)?;
+            if sample_bernoulli_exp(accept_numer, accept_denom)? {
+                let y = i64::try_from(y).map_err(|_| overflow())?;
+                return shift.checked_add(y).ok_or_else(overflow)
+            }
+        }
+    }
+}
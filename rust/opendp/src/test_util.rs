@@ -0,0 +1,124 @@
+//! Utilities for testing implementations of the core traits.
+//!
+//! Only compiled under `cfg(test)`, or with the `test-util` feature.
+
+use std::fmt::Debug;
+
+use crate::core::{Domain, Metric, Transformation};
+use crate::error::*;
+
+/// Check that equality of domains is consistent with membership.
+/// If `domain0 == domain1`, then both domains must agree on the membership of every sample.
+pub fn check_domain_contract<D: Domain>(domain0: &D, domain1: &D, samples: &[D::Carrier]) -> Fallible<()>
+    where D::Carrier: Debug {
+    if domain0 != domain1 { return Ok(()) }
+    match samples.iter().find(|v| domain0.member(v) != domain1.member(v)) {
+        Some(v) => fallible!(DomainMismatch, "equal domains disagree on the membership of {:?}", v),
+        None => Ok(())
+    }
+}
+
+/// Equality up to a small relative tolerance on floats.
+pub trait ApproxEq {
+    fn approx_eq(&self, other: &Self) -> bool;
+}
+macro_rules! impl_approx_eq_exact {
+    ($($ty:ty),+) => ($(impl ApproxEq for $ty {
+        fn approx_eq(&self, other: &Self) -> bool { self == other }
+    })+)
+}
+impl_approx_eq_exact!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool, String);
+macro_rules! impl_approx_eq_float {
+    ($($ty:ty),+) => ($(impl ApproxEq for $ty {
+        fn approx_eq(&self, other: &Self) -> bool {
+            self == other || (self - other).abs() <= <$ty>::EPSILON.sqrt() * self.abs().max(other.abs()).max(1.)
+        }
+    })+)
+}
+impl_approx_eq_float!(f32, f64);
+impl<T: ApproxEq> ApproxEq for Vec<T> {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(l, r)| l.approx_eq(r))
+    }
+}
+
+/// Assert that two transformations compute the same function on every sample.
+/// Float outputs are compared with a small relative tolerance.
+pub fn assert_functionally_equal<DI, DO, MI0, MO0, MI1, MO1>(
+    transformation0: &Transformation<DI, DO, MI0, MO0>,
+    transformation1: &Transformation<DI, DO, MI1, MO1>,
+    samples: &[DI::Carrier],
+) where DI: Domain, DO: Domain,
+        MI0: Metric, MO0: Metric, MI1: Metric, MO1: Metric,
+        DI::Carrier: Debug,
+        DO::Carrier: Debug + ApproxEq {
+    for sample in samples {
+        let (ret0, ret1) = (transformation0.function.eval(sample), transformation1.function.eval(sample));
+        match (ret0, ret1) {
+            (Ok(ret0), Ok(ret1)) => assert!(ret0.approx_eq(&ret1),
+                "transformations differ on {:?}: {:?} != {:?}", sample, ret0, ret1),
+            (Err(_), Err(_)) => (),
+            (ret0, ret1) => panic!("transformations differ on {:?}: {:?} != {:?}", sample, ret0, ret1)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{Function, StabilityRelation};
+    use crate::dist::{AbsoluteDistance, SymmetricDistance};
+    use crate::dom::{AllDomain, VectorDomain};
+    use crate::trans::{make_bounded_sum, make_clamp};
+
+    use super::*;
+
+    /// Broken on purpose: equality ignores `max`, but membership depends on it.
+    #[derive(Clone)]
+    struct BrokenDomain { max: i32 }
+    impl PartialEq for BrokenDomain {
+        fn eq(&self, _other: &Self) -> bool { true }
+    }
+    impl Domain for BrokenDomain {
+        type Carrier = i32;
+        fn member(&self, val: &Self::Carrier) -> bool { *val <= self.max }
+    }
+
+    #[test]
+    fn test_check_domain_contract() -> Fallible<()> {
+        check_domain_contract(&AllDomain::<i32>::new(), &AllDomain::new(), &[-1, 0, 1])?;
+        assert!(check_domain_contract(&BrokenDomain { max: 0 }, &BrokenDomain { max: 2 }, &[-1, 0, 1]).is_err());
+        Ok(())
+    }
+
+    fn make_clamp_sum(
+        lower: f64, upper: f64, buggy: bool
+    ) -> Fallible<Transformation<VectorDomain<AllDomain<f64>>, AllDomain<f64>, SymmetricDistance, AbsoluteDistance<f64>>> {
+        Ok(Transformation::new(
+            VectorDomain::new_all(),
+            AllDomain::new(),
+            Function::new(move |arg: &Vec<f64>| arg.iter()
+                .map(|v| if buggy { v.min(upper) } else { v.max(lower).min(upper) })
+                .sum()),
+            SymmetricDistance,
+            AbsoluteDistance::default(),
+            StabilityRelation::new_from_constant(upper)))
+    }
+
+    #[test]
+    fn test_assert_functionally_equal() -> Fallible<()> {
+        let chained = (make_clamp::<VectorDomain<AllDomain<f64>>, SymmetricDistance>(0., 10.)? >> make_bounded_sum(0., 10.)?)?;
+        let samples = vec![vec![], vec![1., 2., 3.], vec![-5., 0.1, 0.2, 20.]];
+        assert_functionally_equal(&make_clamp_sum(0., 10., false)?, &chained, &samples);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_functionally_equal_buggy() {
+        let chained = (make_clamp::<VectorDomain<AllDomain<f64>>, SymmetricDistance>(0., 10.).unwrap_test()
+            >> make_bounded_sum(0., 10.).unwrap_test()).unwrap_test();
+        let samples = vec![vec![-5., 0.1, 0.2, 20.]];
+        assert_functionally_equal(&make_clamp_sum(0., 10., true).unwrap_test(), &chained, &samples);
+    }
+}
@@ -1,3 +1,4 @@
+use std::hash::{Hash, Hasher};
 use std::ops::{Div, Mul, Sub};
 
 use num::{NumCast, One, ToPrimitive, Zero};
@@ -89,6 +90,38 @@ impl<T> MeasureDistance for T where T: PartialOrd + for<'a> FallibleSub<&'a Self
 pub trait MetricDistance: PartialOrd {}
 impl<T> MetricDistance for T where T: PartialOrd {}
 
+/// A distance type that can be searched by bisection.
+pub trait Bisect: Sized + Clone + PartialOrd + Zero + One {
+    /// A value strictly between `lower` and `upper`, if one exists.
+    fn midpoint(lower: &Self, upper: &Self) -> Option<Self>;
+    /// Twice the value, if it is representable.
+    fn double(&self) -> Option<Self>;
+}
+macro_rules! impl_bisect_float {
+    ($($ty:ty),+) => ($(impl Bisect for $ty {
+        fn midpoint(lower: &Self, upper: &Self) -> Option<Self> {
+            let mid = lower + (upper - lower) / 2.;
+            if lower < &mid && &mid < upper { Some(mid) } else { None }
+        }
+        fn double(&self) -> Option<Self> {
+            let doubled = self * 2.;
+            if doubled.is_finite() { Some(doubled) } else { None }
+        }
+    })+)
+}
+impl_bisect_float!(f32, f64);
+
+macro_rules! impl_bisect_int {
+    ($($ty:ty),+) => ($(impl Bisect for $ty {
+        fn midpoint(lower: &Self, upper: &Self) -> Option<Self> {
+            let gap = upper.checked_sub(*lower)?;
+            if gap > 1 { Some(lower + gap / 2) } else { None }
+        }
+        fn double(&self) -> Option<Self> { self.checked_mul(2) }
+    })+)
+}
+impl_bisect_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
 /// Fallible casting on distances where the casted value is gte the original value.
 /// The trait is unspecified for negative values.
 /// For example, casting a 128_u8 to i8 shouldn't saturate to i8::MAX (127),
@@ -219,3 +252,61 @@ impl CastFrom<bool> for String {
         Ok(v.to_string())
     }
 }
+
+
+/// A [`Hasher`] whose output is fully specified, so that hashes agree between clients and servers, and across platforms and Rust releases.
+/// Unlike `std::collections::hash_map::DefaultHasher`, the algorithm is fixed:
+/// 64-bit FNV-1a over the written bytes, followed by the MurmurHash3 64-bit finalizer so that the low bits are well mixed.
+/// Integers are written in little-endian byte order, and `usize`/`isize` are written as 64-bit integers.
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self { StableHasher(0xcbf2_9ce4_8422_2325) }
+}
+
+macro_rules! impl_stable_write {
+    ($($method:ident: $ty:ty),+) => ($(fn $method(&mut self, i: $ty) { self.write(&i.to_le_bytes()) })+)
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        let mut h = self.0;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        h ^ (h >> 33)
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    impl_stable_write!(write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+        write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128);
+    fn write_usize(&mut self, i: usize) { self.write_u64(i as u64) }
+    fn write_isize(&mut self, i: isize) { self.write_i64(i as i64) }
+}
+
+/// Hash `value` with the [`StableHasher`], in the hash function family indexed by `seed`.
+pub fn stable_hash<T: Hash + ?Sized>(seed: u64, value: &T) -> u64 {
+    let mut hasher = StableHasher::default();
+    seed.hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_hash() {
+        // the hashes must never change, or encodings from older clients would no longer decode
+        assert_eq!(stable_hash(0, &1u32), 0x4289_f74e_27ba_8046);
+        assert_eq!(stable_hash(7, "a"), 0x4b5b_8b07_90cd_6fd2);
+        assert_eq!(stable_hash(7, &1usize), stable_hash(7, &1u64));
+        assert_ne!(stable_hash(0, "a"), stable_hash(1, "a"));
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::Bound;
+
+use num::Zero;
+
+use crate::core::{DatasetMetric, Transformation};
+use crate::dom::{IntervalDomain, VectorDomain};
+use crate::error::*;
+use crate::traits::Abs;
+use crate::trans::make_row_by_row;
+
+/// A [`Transformation`] that takes the absolute value of each element.
+/// The output elements are bounded by `[0, max(|lower|, |upper|)]`.
+/// The absolute value of a signed integer minimum is not representable, so it saturates to the maximum, as in [`Abs`].
+pub fn make_abs<M, T>(
+    lower: T, upper: T
+) -> Fallible<Transformation<VectorDomain<IntervalDomain<T>>, VectorDomain<IntervalDomain<T>>, M, M>>
+    where M: DatasetMetric,
+          T: 'static + Clone + PartialOrd + Abs + Zero {
+    let (abs_lower, abs_upper) = (lower.clone().abs(), upper.clone().abs());
+    let bound = if abs_lower > abs_upper { abs_lower } else { abs_upper };
+
+    make_row_by_row(
+        IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?,
+        IntervalDomain::new(Bound::Included(T::zero()), Bound::Included(bound))?,
+        |v: &T| v.clone().abs())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dist::SymmetricDistance;
+
+    #[test]
+    fn test_make_abs() -> Fallible<()> {
+        let transformation = make_abs::<SymmetricDistance, i32>(-5, 2)?;
+        let ret = transformation.function.eval(&vec![-3, 2, -5])?;
+        assert_eq!(ret, vec![3, 2, 5]);
+        assert!(transformation.output_domain == VectorDomain::new(
+            IntervalDomain::new(Bound::Included(0), Bound::Included(5))?));
+        assert!(transformation.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_abs_min() -> Fallible<()> {
+        let transformation = make_abs::<SymmetricDistance, i32>(i32::MIN, 0)?;
+        assert_eq!(transformation.function.eval(&vec![i32::MIN, -1])?, vec![i32::MAX, 1]);
+        assert!(transformation.output_domain == VectorDomain::new(
+            IntervalDomain::new(Bound::Included(0), Bound::Included(i32::MAX))?));
+
+        let transformation = make_abs::<SymmetricDistance, i8>(i8::MIN, i8::MAX)?;
+        assert_eq!(transformation.function.eval(&vec![i8::MIN, i8::MAX])?, vec![i8::MAX, i8::MAX]);
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use crate::core::{DatasetMetric, Transformation};
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::trans::make_row_by_row;
+
+/// A [`Transformation`] that maps each element to the index of the bracket it falls in.
+/// Brackets are delimited by the public, strictly increasing `breakpoints`: bracket `i` is `[breakpoints[i], breakpoints[i + 1])`.
+/// Values below the first breakpoint fall into the first bracket, and values at or above the last breakpoint fall into the last bracket,
+/// so the output is always in `0..breakpoints.len()`.
+pub fn make_bucketize_custom<M, T>(
+    breakpoints: Vec<T>
+) -> Fallible<Transformation<VectorDomain<AllDomain<T>>, VectorDomain<AllDomain<usize>>, M, M>>
+    where M: DatasetMetric,
+          T: 'static + PartialOrd {
+    if breakpoints.is_empty() { return fallible!(MakeTransformation, "must have at least one breakpoint") }
+    if !breakpoints.windows(2).all(|w| w[0] < w[1]) {
+        return fallible!(MakeTransformation, "breakpoints must be strictly increasing")
+    }
+
+    make_row_by_row(
+        AllDomain::new(),
+        AllDomain::new(),
+        move |v| breakpoints.partition_point(|b| b <= v).saturating_sub(1))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dist::SymmetricDistance;
+
+    #[test]
+    fn test_make_bucketize_custom() -> Fallible<()> {
+        let transformation = make_bucketize_custom::<SymmetricDistance, f64>(vec![0., 50., 100.])?;
+        let ret = transformation.function.eval(&vec![25., 75., 150., -1., 50.])?;
+        assert_eq!(ret, vec![0, 1, 2, 0, 1]);
+        assert!(transformation.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_bucketize_custom_unsorted() {
+        assert!(make_bucketize_custom::<SymmetricDistance, f64>(vec![0., 100., 50.]).is_err());
+        assert!(make_bucketize_custom::<SymmetricDistance, f64>(vec![0., f64::NAN]).is_err());
+    }
+}
@@ -36,6 +36,18 @@ pub fn make_cast_inherent<TI, TO>(
         |v| TO::cast(v.clone()).unwrap_or(TO::NULL))
 }
 
+/// A [`Transformation`] that casts elements between types, where the input may already contain nulls.
+/// Nulls are preserved, and elements that fail to cast become null.
+/// Maps a Vec<Option<TI>> -> Vec<Option<TO>>
+pub fn make_cast_option<TI, TO>(
+) -> Fallible<Transformation<VectorDomain<OptionNullDomain<AllDomain<TI>>>, VectorDomain<OptionNullDomain<AllDomain<TO>>>, SymmetricDistance, SymmetricDistance>>
+    where TI: 'static + Clone, TO: 'static + CastFrom<TI> {
+    make_row_by_row(
+        OptionNullDomain::new(AllDomain::new()),
+        OptionNullDomain::new(AllDomain::new()),
+        |v: &Option<TI>| v.clone().and_then(|v| TO::cast(v).ok()))
+}
+
 pub trait DatasetMetricCast {
     fn stability_constant() -> u32;
 }
@@ -176,6 +188,15 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_cast_option() -> Fallible<()> {
+        let data = vec![Some("1".to_string()), None, Some("a".to_string())];
+        let caster = make_cast_option::<String, u8>()?;
+        assert_eq!(caster.function.eval(&data)?, vec![Some(1), None, None]);
+        assert!(caster.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
     #[test]
     fn test_cast_metric() -> Fallible<()> {
         let data = vec!["abc".to_string(), "1".to_string(), "1.".to_string()];
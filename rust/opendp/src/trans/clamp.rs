@@ -11,6 +11,13 @@ use crate::dist::{AbsoluteDistance, SymmetricDistance};
 
 
 fn min<T: PartialOrd>(a: T, b: T) -> T { if a < b {a} else {b} }
+
+/// Scale `vector` down to have L2 norm at most `norm`.
+pub(crate) fn clamp_l2_norm(vector: &[f64], norm: f64) -> Vec<f64> {
+    let scale = (norm / vector.iter().map(|v| v * v).sum::<f64>().sqrt()).min(1.);
+    vector.iter().map(|v| v * scale).collect()
+}
+
 fn clamp<'a, T: PartialOrd>(lower: &'a T, upper: &'a T, x: &'a T) -> &'a T {
     if x < lower { lower } else if x > upper { upper } else { x }
 }
@@ -135,6 +142,28 @@ pub fn make_unclamp<DI, M>(lower: Bound<DI::Atom>, upper: Bound<DI::Atom>) -> Fa
 }
 
 
+/// A [`Transformation`] that scales each row of a dataset down to have L2 norm at most `norm`.
+/// Rows are clipped independently, so the transformation is 1-stable in the symmetric distance.
+/// Rows with non-finite entries are rejected.
+pub fn make_clamp_row_norm(
+    norm: f64
+) -> Fallible<Transformation<VectorDomain<VectorDomain<AllDomain<f64>>>, VectorDomain<VectorDomain<AllDomain<f64>>>, SymmetricDistance, SymmetricDistance>> {
+    if !(norm.is_finite() && norm > 0.) { return fallible!(MakeTransformation, "norm must be positive and finite") }
+    Ok(Transformation::new(
+        VectorDomain::new(VectorDomain::new_all()),
+        VectorDomain::new(VectorDomain::new_all()),
+        Function::new_fallible(move |arg: &Vec<Vec<f64>>| arg.iter()
+            .map(|row| if row.iter().all(|v| v.is_finite()) {
+                Ok(clamp_l2_norm(row, norm))
+            } else {
+                fallible!(FailedFunction, "rows must be finite")
+            })
+            .collect()),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1)))
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -180,4 +209,16 @@ mod tests {
         assert!(transformation.stability_relation.eval(&15, &15)?);
         Ok(())
     }
+
+    #[test]
+    fn test_make_clamp_row_norm() -> Fallible<()> {
+        let transformation = make_clamp_row_norm(1.)?;
+        let ret = transformation.function.eval(&vec![vec![3., 4.], vec![0.3, 0.4]])?;
+        assert!((ret[0][0] - 0.6).abs() < 1e-12 && (ret[0][1] - 0.8).abs() < 1e-12);
+        assert_eq!(ret[1], vec![0.3, 0.4]);
+        assert!(transformation.function.eval(&vec![vec![f64::NAN]]).is_err());
+        assert!(transformation.stability_relation.eval(&1, &1)?);
+        assert!(make_clamp_row_norm(0.).is_err());
+        Ok(())
+    }
 }
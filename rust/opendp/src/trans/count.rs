@@ -7,11 +7,14 @@ use std::ops::AddAssign;
 use num::{Bounded, Integer, One, Zero};
 
 use crate::core::{Function, SensitivityMetric, StabilityRelation, Transformation};
-use crate::dist::{AbsoluteDistance, SymmetricDistance, LpDistance};
+use crate::dist::{AbsoluteDistance, SymmetricDistance, LInfDistance, LpDistance};
 use crate::dom::{AllDomain, MapDomain, SizedDomain, VectorDomain};
 use crate::error::*;
 use crate::traits::DistanceConstant;
 
+/// A [`Transformation`] that counts the records in a dataset, saturating at the largest value of `TO`.
+/// Adding or removing a record changes the count by 1, so the count is `d_in`-stable in the symmetric distance.
+/// Changing records does not change the count, so under the Hamming distance the count is public and needs no noise.
 pub fn make_count<TIA, TO>(
 ) -> Fallible<Transformation<VectorDomain<AllDomain<TIA>>, AllDomain<TO>, SymmetricDistance, AbsoluteDistance<TO>>>
     where TO: TryFrom<usize> + Bounded + One + DistanceConstant {
@@ -25,7 +28,8 @@ pub fn make_count<TIA, TO>(
         StabilityRelation::new_from_constant(TO::one())))
 }
 
-
+/// A [`Transformation`] that counts the distinct records in a dataset, saturating at the largest value of `TO`.
+/// Adding or removing a record changes the number of distinct records by at most 1, so the count is `d_in`-stable.
 pub fn make_count_distinct<TIA, TO>() -> Fallible<Transformation<VectorDomain<AllDomain<TIA>>, AllDomain<TO>, SymmetricDistance, AbsoluteDistance<TO>>>
     where TIA: Eq + Hash,
           TO: TryFrom<usize> + Bounded + One + DistanceConstant {
@@ -47,8 +51,14 @@ pub trait CountByConstant<QO> {
 impl<Q: One, const P: usize> CountByConstant<Q> for LpDistance<Q, P> {
     fn get_stability_constant() -> Q { Q::one() }
 }
+impl<Q: One> CountByConstant<Q> for LInfDistance<Q> {
+    fn get_stability_constant() -> Q { Q::one() }
+}
 
-// count with unknown n, known categories
+/// A [`Transformation`] that counts the records in each of the public `categories`, followed by the count of all other records.
+/// The output always has `categories.len() + 1` counts, so it can be chained into vector noise mechanisms.
+/// Adding or removing a record changes exactly one count by 1,
+/// so the counts are `d_in`-stable in any Lp distance, as well as the L-infinity distance.
 pub fn make_count_by_categories<MO, TI, TO>(categories: Vec<TI>) -> Fallible<Transformation<VectorDomain<AllDomain<TI>>, SizedDomain<VectorDomain<AllDomain<TO>>>, SymmetricDistance, MO>>
     where MO: CountByConstant<MO::Distance> + SensitivityMetric,
           MO::Distance: DistanceConstant + One,
@@ -105,10 +115,36 @@ pub fn make_count_by<MO, TI, TO>(n: usize) -> Fallible<Transformation<SizedDomai
         StabilityRelation::new_from_constant(MO::get_stability_constant())))
 }
 
+/// A [`Transformation`] that counts the records of each distinct value in a dataset of unknown size.
+/// Values that do not appear in the data are absent from the map, and are treated as having a count of zero.
+/// Adding or removing a record changes one count by 1, so the counts are `d_in`-stable in any Lp distance over keys.
+/// The set of keys depends on the data, so the counts should be released with a stability-based histogram.
+pub fn make_count_by_unsized<MO, TI, TO>() -> Fallible<Transformation<VectorDomain<AllDomain<TI>>, MapDomain<AllDomain<TI>, AllDomain<TO>>, SymmetricDistance, MO>>
+    where MO: CountByConstant<MO::Distance> + SensitivityMetric,
+          MO::Distance: DistanceConstant,
+          TI: 'static + Eq + Hash + Clone,
+          TO: Integer + Zero + One + AddAssign {
+    Ok(Transformation::new(
+        VectorDomain::new_all(),
+        MapDomain::new_all(),
+        Function::new(move |data: &Vec<TI>| {
+            let mut counts = HashMap::new();
+            data.iter().for_each(|v|
+                *counts.entry(v.clone()).or_insert_with(TO::zero) += TO::one()
+            );
+            counts
+        }),
+        SymmetricDistance,
+        MO::default(),
+        StabilityRelation::new_from_constant(MO::get_stability_constant())))
+}
+
 
 #[cfg(test)]
 mod tests {
-    use crate::dist::{L2Distance};
+    use crate::chain::make_chain_mt;
+    use crate::dist::{L1Distance, L2Distance};
+    use crate::meas::make_base_threshold_histogram;
     use crate::trans::count::make_count_by_categories;
 
     use super::*;
@@ -131,6 +167,14 @@ mod tests {
         assert_eq!(ret, expected);
     }
 
+    #[test]
+    fn test_make_count_relation() -> Fallible<()> {
+        let transformation = make_count::<i64, u32>()?;
+        assert!(transformation.stability_relation.eval(&3, &3)?);
+        assert!(!transformation.stability_relation.eval(&3, &2)?);
+        Ok(())
+    }
+
     #[test]
     fn test_make_count_distinct() {
         let transformation = make_count_distinct::<_, i32>().unwrap_test();
@@ -154,6 +198,15 @@ mod tests {
         assert!(transformation.stability_relation.eval(&5, &5.0).unwrap_test());
     }
 
+    #[test]
+    fn test_make_count_by_categories_linf() -> Fallible<()> {
+        let transformation = make_count_by_categories::<LInfDistance<u32>, _, u32>(vec!["a", "b"])?;
+        assert_eq!(transformation.function.eval(&vec!["a", "c", "a"])?, vec![2, 0, 1]);
+        assert!(transformation.stability_relation.eval(&2, &2)?);
+        assert!(!transformation.stability_relation.eval(&2, &1)?);
+        Ok(())
+    }
+
     #[test]
     fn test_make_count_by() -> Fallible<()> {
         let arg = vec![true, true, true, false, true, false, false, false, true, true];
@@ -167,4 +220,19 @@ mod tests {
         assert!(transformation.stability_relation.eval(&5, &5.0)?);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_make_count_by_unsized() -> Fallible<()> {
+        let transformation = make_count_by_unsized::<L1Distance<f64>, _, u32>()?;
+        let ret = transformation.function.eval(&vec!["a", "b", "a"])?;
+        assert_eq!(ret.get("a"), Some(&2));
+        assert_eq!(ret.len(), 2);
+        assert!(transformation.stability_relation.eval(&1, &1.)?);
+
+        let histogram = make_base_threshold_histogram(1e-6, 1.)?;
+        let chain = make_chain_mt(&histogram, &transformation, None)?;
+        let release = chain.function.eval(&vec!["a"; 10])?;
+        assert!((release["a"] - 10.).abs() < 1e-3);
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::iter::repeat;
@@ -42,10 +42,23 @@ fn create_dataframe_domain<K: Eq + Hash>() -> DataFrameDomain<K> {
     MapDomain::new(AllDomain::new(), AllDomain::new())
 }
 
+fn check_col_names<K: Eq + Hash>(col_names: &[K]) -> Fallible<()> {
+    let mut uniques = HashSet::new();
+    if col_names.iter().any(|name| !uniques.insert(name)) {
+        return fallible!(MakeTransformation, "column names must be distinct")
+    }
+    Ok(())
+}
+
+/// A [`Transformation`] that transposes records of cells into a dataframe, with one [`Column`] of strings per name in `col_names`.
+///
+/// Records with too few cells are padded with empty strings, and extra cells are dropped.
+/// Each record contributes one row to every column, so the transformation is 1-stable.
 pub fn make_create_dataframe<K>(
     col_names: Vec<K>
 ) -> Fallible<Transformation<VectorDomain<VectorDomain<AllDomain<String>>>, DataFrameDomain<K>, SymmetricDistance, SymmetricDistance>>
     where K: 'static + Eq + Hash + Clone {
+    check_col_names(&col_names)?;
     Ok(Transformation::new(
         VectorDomain::new(VectorDomain::new_all()),
         create_dataframe_domain(),
@@ -70,7 +83,8 @@ pub fn make_split_dataframe<K>(
     separator: Option<&str>, col_names: Vec<K>
 ) -> Fallible<Transformation<AllDomain<String>, DataFrameDomain<K>, SymmetricDistance, SymmetricDistance>>
     where K: 'static + Hash + Eq + Clone {
-    let separator = separator.unwrap_or(",").to_owned();
+    check_col_names(&col_names)?;
+    let separator = get_separator(separator)?;
     Ok(Transformation::new(
         AllDomain::new(),
         create_dataframe_domain(),
@@ -112,6 +126,33 @@ pub fn make_parse_column<K, T>(key: K, impute: bool) -> Fallible<Transformation<
         StabilityRelation::new_from_constant(1_u32)))
 }
 
+/// A [`Transformation`] that parses the column `key` of a dataframe from strings into `Option<T>`, in place.
+///
+/// Cells that fail to parse become `None`, as in [`super::make_cast`], so that they can be imputed or dropped explicitly.
+/// Each row is parsed independently, so the transformation is 1-stable.
+pub fn make_parse_column_option<K, T>(key: K) -> Fallible<Transformation<DataFrameDomain<K>, DataFrameDomain<K>, SymmetricDistance, SymmetricDistance>>
+    where K: 'static + Hash + Eq + Debug + Clone,
+          T: 'static + Debug + FromStr + Clone + PartialEq {
+    Ok(Transformation::new(
+        create_dataframe_domain(),
+        create_dataframe_domain(),
+        Function::new_fallible(move |arg: &DataFrame<K>| {
+            let col: &Vec<String> = arg.get(&key)
+                .ok_or_else(|| err!(FailedFunction, "column does not exist: {:?}", key))?
+                .as_form()?;
+            let col = col.iter().map(|v| v.parse::<T>().ok()).collect::<Vec<Option<T>>>();
+            replace_col(&key, arg, col.into())
+        }),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+/// A [`Transformation`] that retrieves the column `key` from a dataframe, as a vector of type `T`.
+///
+/// `T` is `String` for columns that have not been parsed, or the type given to [`make_parse_column`].
+/// Fails at runtime if the column does not exist or has a different type.
+/// Each row of the dataframe is one element of the column, so the transformation is 1-stable.
 pub fn make_select_column<K, T>(key: K) -> Fallible<Transformation<DataFrameDomain<K>, VectorDomain<AllDomain<T>>, SymmetricDistance, SymmetricDistance>>
     where K: 'static + Eq + Hash + Debug,
           T: 'static + Debug + Clone + PartialEq {
@@ -129,6 +170,29 @@ pub fn make_select_column<K, T>(key: K) -> Fallible<Transformation<DataFrameDoma
         StabilityRelation::new_from_constant(1_u32)))
 }
 
+/// A [`Transformation`] that keeps the rows of a dataframe where the value in column `key` satisfies `predicate`.
+///
+/// The column must have type `T`, as in [`make_select_column`], and every column must have the same length.
+/// The predicate must only depend on the value it is given, so each row is kept or dropped independently
+/// and the transformation is 1-stable.
+pub fn make_filter_dataframe<K, T, F>(key: K, predicate: F) -> Fallible<Transformation<DataFrameDomain<K>, DataFrameDomain<K>, SymmetricDistance, SymmetricDistance>>
+    where K: 'static + Eq + Hash + Debug + Clone,
+          T: 'static + Debug + Clone + PartialEq,
+          F: 'static + Fn(&T) -> bool {
+    Ok(Transformation::new(
+        create_dataframe_domain(),
+        create_dataframe_domain(),
+        Function::new_fallible(move |arg: &DataFrame<K>| {
+            let mask = arg.get(&key).ok_or_else(|| err!(FailedFunction, "column does not exist: {:?}", key))?
+                .as_form::<Vec<T>>()?
+                .iter().map(&predicate).collect::<Vec<bool>>();
+            Ok(arg.iter().map(|(k, col)| (k.clone(), col.subset(&mask))).collect())
+        }),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
 fn vec_string_to_str(src: &[String]) -> Vec<&str> {
     src.iter().map(|e| e.as_str()).collect()
 }
@@ -142,6 +206,9 @@ fn split_lines(s: &str) -> Vec<&str> {
 }
 
 /// A [`Transformation`] that takes a `String` and splits it into a `Vec<String>` of its lines.
+///
+/// Lines end with `\n` or `\r\n`, and a trailing newline does not create an empty line.
+/// The input distance counts the lines that are added or removed, so the transformation is 1-stable.
 pub fn make_split_lines() -> Fallible<Transformation<AllDomain<String>, VectorDomain<AllDomain<String>>, SymmetricDistance, SymmetricDistance>> {
     Ok(Transformation::new(
         AllDomain::<String>::new(),
@@ -164,6 +231,13 @@ fn parse_series<T>(col: &[&str], default_on_error: bool) -> Fallible<Vec<T>> whe
     }
 }
 
+fn get_separator(separator: Option<&str>) -> Fallible<String> {
+    let separator = separator.unwrap_or(",");
+    // splitting on an empty string would separate every character
+    if separator.is_empty() { return fallible!(MakeTransformation, "separator may not be empty") }
+    Ok(separator.to_owned())
+}
+
 fn split_records<'a>(separator: &str, lines: &[&'a str]) -> Vec<Vec<&'a str>> {
     fn split<'a>(line: &'a str, separator: &str) -> Vec<&'a str> {
         line.split(separator).into_iter().map(|e| e.trim()).collect()
@@ -171,8 +245,12 @@ fn split_records<'a>(separator: &str, lines: &[&'a str]) -> Vec<Vec<&'a str>> {
     lines.iter().map(|e| split(e, separator)).collect()
 }
 
+/// A [`Transformation`] that splits each record into its cells, on `separator`, which defaults to `","`.
+/// Cells are trimmed of leading and trailing whitespace.
+///
+/// Each record is split independently, so the number of records that differ is unchanged and the transformation is 1-stable.
 pub fn make_split_records(separator: Option<&str>) -> Fallible<Transformation<VectorDomain<AllDomain<String>>, VectorDomain<VectorDomain<AllDomain<String>>>, SymmetricDistance, SymmetricDistance>> {
-    let separator = separator.unwrap_or(",").to_owned();
+    let separator = get_separator(separator)?;
     Ok(Transformation::new(
         VectorDomain::new_all(),
         VectorDomain::new(VectorDomain::new_all()),
@@ -187,6 +265,73 @@ pub fn make_split_records(separator: Option<&str>) -> Fallible<Transformation<Ve
         StabilityRelation::new_from_constant(1_u32)))
 }
 
+/// split CSV text into records of cells, following RFC 4180
+fn split_csv(s: &str, delimiter: char, quote: char) -> Fallible<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    // whether the current record has consumed any characters
+    let mut started = false;
+    let mut in_quotes = false;
+
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        started = true;
+        if in_quotes {
+            if c != quote {
+                cell.push(c)
+            } else if chars.peek() == Some(&quote) {
+                // an escaped quote
+                cell.push(quote);
+                chars.next();
+            } else {
+                in_quotes = false
+            }
+        } else if c == quote && cell.is_empty() {
+            in_quotes = true
+        } else if c == delimiter {
+            record.push(std::mem::take(&mut cell))
+        } else if c == '\n' || (c == '\r' && chars.peek() == Some(&'\n')) {
+            if c == '\r' { chars.next(); }
+            record.push(std::mem::take(&mut cell));
+            records.push(std::mem::take(&mut record));
+            started = false
+        } else {
+            cell.push(c)
+        }
+    }
+    if in_quotes { return fallible!(FailedFunction, "quoted cell is not terminated") }
+    if started {
+        record.push(cell);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// A [`Transformation`] that splits CSV text into records of cells, following RFC 4180.
+///
+/// Cells may be enclosed in `quote` characters, which defaults to `'"'`. Quoted cells may contain the `delimiter`,
+/// which defaults to `','`, as well as line breaks, and a doubled quote character inside a quoted cell is an escaped quote.
+/// Unlike [`make_split_records`], cells are not trimmed. Records end with `\n` or `\r\n`, and a trailing line break
+/// does not create an empty record. Each record of the CSV is one record of the output, so the transformation is 1-stable.
+pub fn make_split_csv(
+    delimiter: Option<char>, quote: Option<char>
+) -> Fallible<Transformation<AllDomain<String>, VectorDomain<VectorDomain<AllDomain<String>>>, SymmetricDistance, SymmetricDistance>> {
+    let delimiter = delimiter.unwrap_or(',');
+    let quote = quote.unwrap_or('"');
+    if delimiter == quote { return fallible!(MakeTransformation, "delimiter and quote must be different") }
+    if [delimiter, quote].iter().any(|c| *c == '\n' || *c == '\r') {
+        return fallible!(MakeTransformation, "delimiter and quote may not be line breaks")
+    }
+    Ok(Transformation::new(
+        AllDomain::new(),
+        VectorDomain::new(VectorDomain::new_all()),
+        Function::new_fallible(move |arg: &String| split_csv(arg, delimiter, quote)),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -201,6 +346,11 @@ mod tests {
         let arg = "ant\nbat\ncat\n".to_owned();
         let ret = transformation.function.eval(&arg).unwrap_test();
         assert_eq!(ret, vec!["ant".to_owned(), "bat".to_owned(), "cat".to_owned()]);
+
+        let ret = transformation.function.eval(&"ant\r\n\nbat".to_owned()).unwrap_test();
+        assert_eq!(ret, vec!["ant".to_owned(), "".to_owned(), "bat".to_owned()]);
+        assert!(transformation.stability_relation.eval(&1, &1).unwrap_test());
+        assert!(!transformation.stability_relation.eval(&2, &1).unwrap_test());
     }
 
     #[test]
@@ -213,6 +363,34 @@ mod tests {
             vec!["bat".to_owned(), "bar".to_owned()],
             vec!["cat".to_owned(), "baz".to_owned()],
         ]);
+        assert!(transformation.stability_relation.eval(&1, &1).unwrap_test());
+
+        let transformation = make_split_records(Some("\t")).unwrap_test();
+        let ret = transformation.function.eval(&vec!["ant, foo\tbar".to_owned()]).unwrap_test();
+        assert_eq!(ret, vec![vec!["ant, foo".to_owned(), "bar".to_owned()]]);
+        assert!(make_split_records(Some("")).is_err());
+    }
+
+    #[test]
+    fn test_make_split_csv() {
+        let transformation = make_split_csv(None, None).unwrap_test();
+        let arg = "a,\"b, c\",\"say \"\"hi\"\"\"\r\n\"multi\nline\", x \n\n\"\"".to_owned();
+        let ret = transformation.function.eval(&arg).unwrap_test();
+        assert_eq!(ret, vec![
+            vec!["a".to_owned(), "b, c".to_owned(), "say \"hi\"".to_owned()],
+            vec!["multi\nline".to_owned(), " x ".to_owned()],
+            vec!["".to_owned()],
+            vec!["".to_owned()],
+        ]);
+        assert_eq!(transformation.function.eval(&"a,b\n".to_owned()).unwrap_test(), vec![vec!["a".to_owned(), "b".to_owned()]]);
+        assert!(transformation.function.eval(&"\"a,b".to_owned()).is_err());
+        assert!(transformation.stability_relation.eval(&1, &1).unwrap_test());
+
+        let transformation = make_split_csv(Some(';'), Some('\'')).unwrap_test();
+        let ret = transformation.function.eval(&"'a;b';c".to_owned()).unwrap_test();
+        assert_eq!(ret, vec![vec!["a;b".to_owned(), "c".to_owned()]]);
+        assert!(make_split_csv(Some('"'), None).is_err());
+        assert!(make_split_csv(Some('\n'), None).is_err());
     }
 
     #[test]
@@ -229,6 +407,13 @@ mod tests {
             (1, Column::new(vec!["foo".to_owned(), "bar".to_owned(), "baz".to_owned()])),
         ].into_iter().collect();
         assert_eq!(ret, expected);
+
+        // short records are padded, and long records are truncated
+        let arg = vec![vec!["ant".to_owned()], vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]];
+        let ret = transformation.function.eval(&arg).unwrap_test();
+        assert_eq!(ret[&1], Column::new(vec!["".to_owned(), "b".to_owned()]));
+        assert!(transformation.stability_relation.eval(&1, &1).unwrap_test());
+        assert!(make_create_dataframe::<u32>(vec![0, 0]).is_err());
     }
 
     #[test]
@@ -277,6 +462,20 @@ mod tests {
         assert_eq!(ret, expected);
     }
 
+    #[test]
+    fn test_make_parse_column_option() {
+        let parse = make_parse_column_option::<_, i32>(1).unwrap_test();
+        let select = make_select_column::<_, Option<i32>>(1).unwrap_test();
+        let transformation = make_chain_tt(&select, &parse, None).unwrap_test();
+        let arg: DataFrame<usize> = vec![
+            (0, Column::new(vec!["ant".to_owned(), "bat".to_owned(), "cat".to_owned()])),
+            (1, Column::new(vec!["1".to_owned(), "".to_owned(), "a".to_owned()])),
+        ].into_iter().collect();
+        assert_eq!(transformation.function.eval(&arg).unwrap_test(), vec![Some(1), None, None]);
+        assert!(parse.stability_relation.eval(&1, &1).unwrap_test());
+        assert!(make_parse_column_option::<_, i32>(2).unwrap_test().function.eval(&arg).is_err());
+    }
+
     #[test]
     fn test_make_select_column() {
         let transformation = make_select_column::<String, String>("1".to_owned()).unwrap_test();
@@ -287,5 +486,39 @@ mod tests {
         let ret = transformation.function.eval(&arg).unwrap_test();
         let expected = vec!["foo".to_owned(), "bar".to_owned(), "baz".to_owned()];
         assert_eq!(ret, expected);
+        assert!(transformation.stability_relation.eval(&1, &1).unwrap_test());
+
+        assert!(make_select_column::<String, String>("2".to_owned()).unwrap_test().function.eval(&arg).is_err());
+        assert!(make_select_column::<String, i32>("1".to_owned()).unwrap_test().function.eval(&arg).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_make_select_parsed_column() {
+        let parse = make_parse_column::<_, i32>(1, false).unwrap_test();
+        let select = make_select_column::<_, i32>(1).unwrap_test();
+        let transformation = make_chain_tt(&select, &parse, None).unwrap_test();
+        let arg: DataFrame<usize> = vec![
+            (1, Column::new(vec!["1".to_owned(), "2".to_owned()])),
+        ].into_iter().collect();
+        assert_eq!(transformation.function.eval(&arg).unwrap_test(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_make_filter_dataframe() {
+        let parse = make_parse_column::<_, i32>(1, false).unwrap_test();
+        let filter = make_filter_dataframe(1, |v: &i32| *v > 1).unwrap_test();
+        let transformation = make_chain_tt(&filter, &parse, None).unwrap_test();
+        let arg: DataFrame<usize> = vec![
+            (0, Column::new(vec!["ant".to_owned(), "bat".to_owned(), "cat".to_owned()])),
+            (1, Column::new(vec!["1".to_owned(), "2".to_owned(), "3".to_owned()])),
+        ].into_iter().collect();
+        let ret = transformation.function.eval(&arg).unwrap_test();
+        let expected: DataFrame<usize> = vec![
+            (0, Column::new(vec!["bat".to_owned(), "cat".to_owned()])),
+            (1, Column::new(vec![2, 3])),
+        ].into_iter().collect();
+        assert_eq!(ret, expected);
+        assert!(filter.stability_relation.eval(&1, &1).unwrap_test());
+        assert!(make_filter_dataframe(0, |v: &i32| *v > 1).unwrap_test().function.eval(&arg).is_err());
+    }
+}
@@ -1,9 +1,11 @@
 use std::ops::{Add, Mul, Sub};
 
-use num::Float;
+use num::{Float, Zero};
 
-use crate::core::{Domain, Transformation};
-use crate::dom::{AllDomain, InherentNullDomain, VectorDomain, OptionNullDomain};
+use std::collections::Bound;
+
+use crate::core::{Domain, Function, StabilityRelation, Transformation};
+use crate::dom::{AllDomain, InherentNullDomain, IntervalDomain, VectorDomain, OptionNullDomain};
 use crate::error::Fallible;
 use crate::dom::InherentNull;
 use crate::samplers::SampleUniform;
@@ -29,6 +31,27 @@ pub fn make_impute_uniform_float<T>(
         } else { Ok(v.clone()) })
 }
 
+/// A [`Transformation`] that imputes elementwise with a sample from Uniform(lower, upper),
+/// where non-null values are within `[lower, upper]`.
+/// Maps a Vec<T> -> Vec<T>, where the input is a type with built-in nullity.
+///
+/// Imputed values are also within `[lower, upper]`, so the output can be consumed by bounded aggregates without clamping.
+pub fn make_bounded_impute_uniform_float<T>(
+    lower: T, upper: T,
+) -> Fallible<Transformation<VectorDomain<InherentNullDomain<IntervalDomain<T>>>, VectorDomain<IntervalDomain<T>>, SymmetricDistance, SymmetricDistance>>
+    where T: 'static + Float + SampleUniform + InherentNull {
+    if !(lower.is_finite() && upper.is_finite()) { return fallible!(MakeTransformation, "bounds must be finite") }
+    let domain = IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?;
+
+    make_row_by_row_fallible(
+        InherentNullDomain::new(domain.clone()),
+        domain,
+        // rounding may carry the sample past upper
+        move |v: &T| if v.is_null() {
+            T::sample_standard_uniform(false).map(|u| (lower + u * (upper - lower)).min(upper))
+        } else { Ok(*v) })
+}
+
 // utility trait to impute with a constant, regardless of the representation of null
 pub trait ImputableDomain: Domain {
     type NonNull;
@@ -73,11 +96,85 @@ pub fn make_impute_constant<DA>(
         move |v| DA::impute_constant(v, &constant).clone())
 }
 
+/// A [`Transformation`] that imputes elementwise with a constant value, where non-null values are within `[lower, upper]`.
+/// Maps a Vec<Option<T>> -> Vec<T>
+///
+/// The `constant` must also be within `[lower, upper]`, so that the output stays in the interval
+/// and can be consumed by bounded aggregates. Each record is imputed independently, so the stability constant is 1.
+pub fn make_bounded_impute_constant<T>(
+    lower: T, upper: T, constant: T
+) -> Fallible<Transformation<VectorDomain<OptionNullDomain<IntervalDomain<T>>>, VectorDomain<IntervalDomain<T>>, SymmetricDistance, SymmetricDistance>>
+    where T: 'static + Clone + PartialOrd {
+    let domain = IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?;
+    if !domain.member(&constant) { return fallible!(MakeTransformation, "constant must be within [lower, upper]") }
+
+    make_row_by_row(
+        OptionNullDomain::new(domain.clone()),
+        domain,
+        move |v: &Option<T>| v.clone().unwrap_or_else(|| constant.clone()))
+}
+
+// utility trait to inspect nulls, regardless of the representation of null
+pub trait NullableDomain: Domain {
+    type NonNull;
+    fn option(value: &Self::Carrier) -> Option<&Self::NonNull>;
+    fn new() -> Self;
+}
+impl<T> NullableDomain for OptionNullDomain<AllDomain<T>> {
+    type NonNull = T;
+    fn option(value: &Self::Carrier) -> Option<&Self::NonNull> { value.as_ref() }
+    fn new() -> Self { OptionNullDomain::new(AllDomain::new()) }
+}
+impl<T: InherentNull> NullableDomain for InherentNullDomain<AllDomain<T>> {
+    type NonNull = T;
+    fn option(value: &Self::Carrier) -> Option<&Self::NonNull> {
+        if value.is_null() { None } else { Some(value) }
+    }
+    fn new() -> Self { InherentNullDomain::new(AllDomain::new()) }
+}
+
+/// A [`Transformation`] that removes null records.
+/// Maps a Vec<Option<T>> -> Vec<T> if input domain is OptionNullDomain<AllDomain<T>>,
+///     or Vec<T> -> Vec<T> if input domain is InherentNullDomain<AllDomain<T>>
+///
+/// Each record is kept or dropped independently, so the transformation is 1-stable in the symmetric distance.
+/// It is only defined for the symmetric distance: dropping records changes the length of the data,
+/// so a substitution in the input may shift the position of every subsequent record in the output.
+pub fn make_drop_null<DA>(
+) -> Fallible<Transformation<VectorDomain<DA>, VectorDomain<AllDomain<DA::NonNull>>, SymmetricDistance, SymmetricDistance>>
+    where DA: NullableDomain,
+          DA::NonNull: Clone,
+          DA::Carrier: 'static {
+    Ok(Transformation::new(
+        VectorDomain::new(DA::new()),
+        VectorDomain::new_all(),
+        Function::new(|arg: &Vec<DA::Carrier>| arg.iter().filter_map(|v| DA::option(v).cloned()).collect()),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+/// A [`Transformation`] that imputes elementwise with the mean of a public `reference` dataset.
+/// The sensitive data is never used to choose the imputed value, so the stability constant is 1.
+/// Nulls in the reference dataset are not permitted.
+pub fn make_impute_reference_mean<DA>(
+    reference: &[DA::NonNull]
+) -> Fallible<Transformation<VectorDomain<DA>, VectorDomain<AllDomain<DA::NonNull>>, SymmetricDistance, SymmetricDistance>>
+    where DA: ImputableDomain,
+          DA::NonNull: 'static + Float,
+          DA::Carrier: 'static {
+    if reference.is_empty() { return fallible!(MakeTransformation, "reference dataset may not be empty") }
+    let sum = reference.iter().fold(DA::NonNull::zero(), |sum, v| sum + *v);
+    let mean = sum / num_cast!(reference.len(); DA::NonNull)?;
+    if !mean.is_finite() { return fallible!(MakeTransformation, "reference mean must be finite") }
+    make_impute_constant::<DA>(mean)
+}
+
 
 #[cfg(test)]
 mod tests {
     use crate::error::ExplainUnwrap;
-    use crate::trans::{make_impute_constant, make_impute_uniform_float};
+    use crate::trans::{make_bounded_impute_constant, make_bounded_impute_uniform_float, make_drop_null, make_impute_constant, make_impute_reference_mean, make_impute_uniform_float};
     use crate::dom::{OptionNullDomain, InherentNullDomain};
 
     #[test]
@@ -91,6 +188,20 @@ mod tests {
             .eval(&1, &1).unwrap_test());
     }
 
+    #[test]
+    fn test_bounded_impute_uniform() {
+        let imputer = make_bounded_impute_uniform_float(2., 3.).unwrap_test();
+
+        let result = imputer.invoke(&vec![2.5, f64::NAN, f64::NAN]).unwrap_test();
+
+        assert_eq!(result[0], 2.5);
+        assert!(result.iter().all(|v| (2.0..=3.0).contains(v)));
+        assert!(imputer.invoke(&vec![4.]).is_err());
+        assert!(imputer.stability_relation
+            .eval(&1, &1).unwrap_test());
+        assert!(make_bounded_impute_uniform_float(2., f64::INFINITY).is_err());
+    }
+
     #[test]
     fn test_impute_constant_option() {
         let imputer = make_impute_constant::<OptionNullDomain<_>>("IMPUTED".to_string()).unwrap_test();
@@ -102,6 +213,19 @@ mod tests {
             .eval(&1, &1).unwrap_test());
     }
 
+    #[test]
+    fn test_bounded_impute_constant() {
+        let imputer = make_bounded_impute_constant(0, 10, 5).unwrap_test();
+
+        let result = imputer.invoke(&vec![Some(1), None, Some(10)]).unwrap_test();
+
+        assert_eq!(result, vec![1, 5, 10]);
+        assert!(imputer.invoke(&vec![Some(11)]).is_err());
+        assert!(imputer.stability_relation
+            .eval(&1, &1).unwrap_test());
+        assert!(make_bounded_impute_constant(0, 10, 11).is_err());
+    }
+
     #[test]
     fn test_impute_constant_inherent() {
         let imputer = make_impute_constant::<InherentNullDomain<_>>(12.).unwrap_test();
@@ -112,4 +236,27 @@ mod tests {
         assert!(imputer.stability_relation
             .eval(&1, &1).unwrap_test());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_impute_reference_mean() {
+        let imputer = make_impute_reference_mean::<OptionNullDomain<_>>(&[4., 6.]).unwrap_test();
+
+        let result = imputer.function.eval(&vec![Some(1.0), None, Some(3.0)]).unwrap_test();
+
+        assert_eq!(result, vec![1., 5., 3.]);
+        assert!(imputer.stability_relation
+            .eval(&1, &1).unwrap_test());
+        assert!(make_impute_reference_mean::<OptionNullDomain<_>>(&[f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_drop_null() {
+        let option = make_drop_null::<OptionNullDomain<_>>().unwrap_test();
+        assert_eq!(option.function.eval(&vec![Some(1), None, Some(3)]).unwrap_test(), vec![1, 3]);
+        assert!(option.stability_relation
+            .eval(&1, &1).unwrap_test());
+
+        let inherent = make_drop_null::<InherentNullDomain<_>>().unwrap_test();
+        assert_eq!(inherent.function.eval(&vec![f64::NAN, 2.]).unwrap_test(), vec![2.]);
+    }
+}
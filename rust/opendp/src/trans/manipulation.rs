@@ -1,10 +1,14 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
 use num::One;
 
 use crate::core::{Domain, Function, Metric, StabilityRelation, Transformation, DatasetMetric};
 use crate::error::*;
 use crate::traits::{DistanceConstant};
-use crate::dom::{VectorDomain, AllDomain};
-use crate::dist::SymmetricDistance;
+use crate::dom::{VectorDomain, AllDomain, PairDomain, MapDomain, SizedDomain};
+use crate::dist::{HammingDistance, SymmetricDistance};
+use crate::trans::NullableDomain;
 
 
 /// Constructs a [`Transformation`] representing an arbitrary row-by-row transformation.
@@ -70,12 +74,125 @@ pub fn make_is_equal<TI>(
         move |v| v == &value)
 }
 
+/// A [`Transformation`] that evaluates `predicate` elementwise.
+/// Maps a Vec<T> -> Vec<bool>
+pub fn make_indicator<TI, F>(
+    predicate: F
+) -> Fallible<Transformation<VectorDomain<AllDomain<TI>>, VectorDomain<AllDomain<bool>>, SymmetricDistance, SymmetricDistance>>
+    where TI: 'static,
+          F: 'static + Fn(&TI) -> bool {
+    make_row_by_row(
+        AllDomain::new(),
+        AllDomain::new(),
+        predicate)
+}
+
+/// A [`Transformation`] that keeps the records that satisfy `predicate`.
+/// Maps a Vec<T> -> Vec<T>
+///
+/// The predicate must only depend on the record it is given.
+/// Each record is kept or dropped independently, so the transformation is 1-stable in the symmetric distance.
+pub fn make_filter<T, F>(
+    predicate: F
+) -> Fallible<Transformation<VectorDomain<AllDomain<T>>, VectorDomain<AllDomain<T>>, SymmetricDistance, SymmetricDistance>>
+    where T: 'static + Clone,
+          F: 'static + Fn(&T) -> bool {
+    Ok(Transformation::new(
+        VectorDomain::new_all(),
+        VectorDomain::new_all(),
+        Function::new(move |arg: &Vec<T>| arg.iter().filter(|v| predicate(v)).cloned().collect()),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+/// A [`Transformation`] that checks elementwise if values are null.
+/// Maps a Vec<Option<T>> -> Vec<bool> if input domain is OptionNullDomain<AllDomain<T>>,
+///     or Vec<T> -> Vec<bool> if input domain is InherentNullDomain<AllDomain<T>>
+pub fn make_is_null<DA>(
+) -> Fallible<Transformation<VectorDomain<DA>, VectorDomain<AllDomain<bool>>, SymmetricDistance, SymmetricDistance>>
+    where DA: NullableDomain,
+          DA::Carrier: 'static {
+    make_row_by_row(
+        DA::new(),
+        AllDomain::new(),
+        |v| DA::option(v).is_none())
+}
+
+/// A [`Transformation`] that replaces each value not in `kept` with `other`.
+/// Maps a Vec<T> -> Vec<T>
+///
+/// The `kept` categories must be chosen from public information.
+/// Choosing them from the frequencies in the sensitive data would itself leak information.
+pub fn make_collapse_rare<M, T>(
+    kept: Vec<T>, other: T
+) -> Fallible<Transformation<VectorDomain<AllDomain<T>>, VectorDomain<AllDomain<T>>, M, M>>
+    where M: DatasetMetric,
+          T: 'static + Eq + Hash + Clone {
+    let kept = kept.into_iter().collect::<HashSet<T>>();
+    make_row_by_row(
+        AllDomain::new(),
+        AllDomain::new(),
+        move |v| if kept.contains(v) { v.clone() } else { other.clone() })
+}
+
+/// A [`Transformation`] that pairs each record with its index, as a synthetic key.
+/// Maps a Vec<T> -> Vec<(u64, T)>
+///
+/// Keys are positional, so the transformation is only 1-stable when neighboring datasets differ by substitution.
+/// Adding or removing a record would shift the keys of all subsequent records.
+pub fn make_assign_synthetic_key<T>(
+) -> Fallible<Transformation<VectorDomain<AllDomain<T>>, VectorDomain<PairDomain<AllDomain<u64>, AllDomain<T>>>, HammingDistance, HammingDistance>>
+    where T: 'static + Clone {
+    Ok(Transformation::new(
+        VectorDomain::new_all(),
+        VectorDomain::new(PairDomain::new(AllDomain::new(), AllDomain::new())),
+        Function::new(|arg: &Vec<T>| (0..).zip(arg.iter().cloned()).collect()),
+        HammingDistance,
+        HammingDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+/// A [`Transformation`] that converts a vector into a map from index to value.
+/// Maps a Vec<T> -> HashMap<usize, T>
+///
+/// As with [`make_assign_synthetic_key`], indices are positional, so stability is only with respect to substitution.
+pub fn make_vector_to_map<T>(
+) -> Fallible<Transformation<VectorDomain<AllDomain<T>>, MapDomain<AllDomain<usize>, AllDomain<T>>, HammingDistance, HammingDistance>>
+    where T: 'static + Clone {
+    Ok(Transformation::new(
+        VectorDomain::new_all(),
+        MapDomain::new_all(),
+        Function::new(|arg: &Vec<T>| arg.iter().cloned().enumerate().collect()),
+        HammingDistance,
+        HammingDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+/// A [`Transformation`] that converts a map from index to value into a vector of known `length`.
+/// Maps a HashMap<usize, T> -> Vec<T>
+///
+/// Indices missing from the map are filled with `default`, and indices outside of `0..length` are dropped.
+pub fn make_map_to_vector<T>(
+    length: usize, default: T
+) -> Fallible<Transformation<MapDomain<AllDomain<usize>, AllDomain<T>>, SizedDomain<VectorDomain<AllDomain<T>>>, HammingDistance, HammingDistance>>
+    where T: 'static + Clone {
+    Ok(Transformation::new(
+        MapDomain::new_all(),
+        SizedDomain::new(VectorDomain::new_all(), length),
+        Function::new(move |arg: &HashMap<usize, T>| (0..length)
+            .map(|i| arg.get(&i).cloned().unwrap_or_else(|| default.clone()))
+            .collect()),
+        HammingDistance,
+        HammingDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use crate::dist::{HammingDistance};
     use crate::dom::AllDomain;
 
     #[test]
@@ -95,4 +212,67 @@ mod tests {
         assert!(is_equal.stability_relation.eval(&1, &1)?);
         Ok(())
     }
+
+    #[test]
+    fn test_indicator() -> Fallible<()> {
+        let indicator = make_indicator(|v: &i32| *v > 2)?;
+        assert_eq!(indicator.function.eval(&vec![1, 3, 5])?, vec![false, true, true]);
+        assert!(indicator.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter() -> Fallible<()> {
+        let filter = make_filter(|v: &i32| v % 2 == 0)?;
+        assert_eq!(filter.function.eval(&vec![1, 2, 3, 4])?, vec![2, 4]);
+        assert!(filter.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_null() -> Fallible<()> {
+        use crate::dom::{InherentNullDomain, OptionNullDomain};
+        let is_null = make_is_null::<OptionNullDomain<_>>()?;
+        assert_eq!(is_null.function.eval(&vec![Some("a"), None])?, vec![false, true]);
+        assert!(is_null.stability_relation.eval(&1, &1)?);
+
+        let is_nan = make_is_null::<InherentNullDomain<_>>()?;
+        assert_eq!(is_nan.function.eval(&vec![1., f64::NAN])?, vec![false, true]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_assign_synthetic_key() -> Fallible<()> {
+        let assign_key = make_assign_synthetic_key()?;
+        let arg = vec!['a', 'b', 'c'];
+        let ret = assign_key.function.eval(&arg)?;
+        assert_eq!(ret, vec![(0, 'a'), (1, 'b'), (2, 'c')]);
+        assert!(assign_key.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_to_map() -> Fallible<()> {
+        let to_map = make_vector_to_map()?;
+        let map = to_map.function.eval(&vec![10, 20, 30])?;
+        assert_eq!(map, vec![(0, 10), (1, 20), (2, 30)].into_iter().collect());
+
+        let to_vector = make_map_to_vector(3, 0)?;
+        assert_eq!(to_vector.function.eval(&map)?, vec![10, 20, 30]);
+        let sparse = vec![(1, 20), (7, 70)].into_iter().collect();
+        assert_eq!(to_vector.function.eval(&sparse)?, vec![0, 20, 0]);
+
+        assert!(to_map.stability_relation.eval(&1, &1)?);
+        assert!(to_vector.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_collapse_rare() -> Fallible<()> {
+        let collapse = make_collapse_rare::<SymmetricDistance, _>(vec!['a', 'b'], 'z')?;
+        let ret = collapse.function.eval(&vec!['a', 'c', 'b', 'd'])?;
+        assert_eq!(ret, vec!['a', 'z', 'b', 'z']);
+        assert!(collapse.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
 }
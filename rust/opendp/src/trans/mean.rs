@@ -5,9 +5,13 @@ use crate::traits::DistanceConstant;
 use crate::error::Fallible;
 use crate::dom::{VectorDomain, IntervalDomain, AllDomain, SizedDomain};
 use std::collections::Bound;
-use crate::dist::{SymmetricDistance, AbsoluteDistance};
+use crate::dist::{SymmetricDistance, AbsoluteDistance, CoordinateDistance};
 use num::{Float};
 
+/// A [`Transformation`] that computes the mean of a dataset of `n` values in `[lower, upper]`.
+/// The size is public, so changing a record moves the mean by at most `(upper - lower) / n`,
+/// and the mean is `d_in * (upper - lower) / (2 n)`-stable in the symmetric distance.
+/// When the size is not known, see [`make_bounded_sum_count`].
 pub fn make_bounded_mean<T>(
     lower: T, upper: T, n: usize
 ) -> Fallible<Transformation<SizedDomain<VectorDomain<IntervalDomain<T>>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
@@ -26,12 +30,41 @@ pub fn make_bounded_mean<T>(
         StabilityRelation::new_from_constant((upper - lower) / _n / num_cast!(2; T)?)))
 }
 
+/// A [`Transformation`] that computes the sum and the count of a dataset of unknown size with values in `[lower, upper]`,
+/// as a vector `[sum, count]`. The mean is their ratio, which can be computed after adding noise to both.
+/// Adding or removing `d_in` records changes the sum by at most `d_in * max(|lower|, |upper|)` and the count by `d_in`,
+/// so the output distance is the per-coordinate vector `[d_in * max(|lower|, |upper|), d_in]`.
+pub fn make_bounded_sum_count<T>(
+    lower: T, upper: T
+) -> Fallible<Transformation<VectorDomain<IntervalDomain<T>>, SizedDomain<VectorDomain<AllDomain<T>>>, SymmetricDistance, CoordinateDistance<T>>>
+    where T: DistanceConstant + Float,
+          for <'a> T: Sum<&'a T> {
+    let sum_sensitivity = lower.abs().max(upper.abs());
+    let forward_map = move |d_in: &u32| -> Fallible<Box<Vec<T>>> {
+        let d_in = num_cast!(*d_in; T)?;
+        Ok(Box::new(vec![d_in * sum_sensitivity, d_in]))
+    };
+
+    Ok(Transformation::new(
+        VectorDomain::new(IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?),
+        SizedDomain::new(VectorDomain::new_all(), 2),
+        Function::new_fallible(|arg: &Vec<T>| Ok(vec![arg.iter().sum::<T>(), num_cast!(arg.len(); T)?])),
+        SymmetricDistance,
+        CoordinateDistance::default(),
+        StabilityRelation::new_all(
+            move |d_in: &u32, d_out: &Vec<T>| {
+                let min_d_out = forward_map(d_in)?;
+                Ok(d_out.len() == 2 && d_out[0] >= min_d_out[0] && d_out[1] >= min_d_out[1])
+            },
+            Some(forward_map),
+            None::<fn(&_) -> _>)))
+}
 
 
 #[cfg(test)]
 mod tests {
-    use crate::error::ExplainUnwrap;
-    use crate::trans::mean::make_bounded_mean;
+    use crate::error::{ExplainUnwrap, Fallible};
+    use crate::trans::mean::{make_bounded_mean, make_bounded_sum_count};
 
     #[test]
     fn test_make_bounded_mean_hamming() {
@@ -52,4 +85,32 @@ mod tests {
         assert_eq!(ret, expected);
         assert!(transformation.stability_relation.eval(&1, &1.).unwrap_test())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_make_bounded_mean_length() -> Fallible<()> {
+        let transformation = make_bounded_mean(0., 10., 3)?;
+        assert_eq!(transformation.invoke(&vec![1., 2., 3.])?, 2.);
+        // the sensitivity relies on the public length, so other lengths are rejected
+        assert!(transformation.invoke(&vec![1., 2.]).is_err());
+        assert!(transformation.invoke(&vec![1., 2., 30.]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_bounded_sum_count() -> Fallible<()> {
+        use crate::chain::make_chain_mt;
+        use crate::meas::make_base_laplace_heterogeneous;
+
+        let transformation = make_bounded_sum_count(-1., 4.)?;
+        assert_eq!(transformation.function.eval(&vec![1., 2., 3.])?, vec![6., 3.]);
+        assert!(transformation.stability_relation.eval(&2, &vec![8., 2.])?);
+        assert!(!transformation.stability_relation.eval(&2, &vec![8., 1.])?);
+
+        // the sum costs 4 / 8 and the count 1 / 2
+        let measurement = make_chain_mt(&make_base_laplace_heterogeneous(vec![8., 2.])?, &transformation, None)?;
+        assert_eq!(measurement.function.eval(&vec![1., 2., 3.])?.len(), 2);
+        assert!(measurement.privacy_relation.eval(&1, &1.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &0.99)?);
+        Ok(())
+    }
+}
@@ -12,6 +12,19 @@ pub mod variance;
 pub mod impute;
 pub mod clamp;
 pub mod cast;
+pub mod round;
+pub mod pivot;
+pub mod abs;
+pub mod mode;
+pub mod product;
+pub mod bucketize;
+pub mod smooth;
+pub mod sigmoid;
+pub mod sketch;
+pub mod projection;
+pub mod quantile;
+pub mod resize;
+pub mod sample;
 
 pub use crate::trans::dataframe::*;
 pub use crate::trans::manipulation::*;
@@ -22,3 +35,16 @@ pub use crate::trans::variance::*;
 pub use crate::trans::impute::*;
 pub use crate::trans::clamp::*;
 pub use crate::trans::cast::*;
+pub use crate::trans::round::*;
+pub use crate::trans::pivot::*;
+pub use crate::trans::abs::*;
+pub use crate::trans::mode::*;
+pub use crate::trans::product::*;
+pub use crate::trans::bucketize::*;
+pub use crate::trans::smooth::*;
+pub use crate::trans::sigmoid::*;
+pub use crate::trans::sketch::*;
+pub use crate::trans::projection::*;
+pub use crate::trans::quantile::*;
+pub use crate::trans::resize::*;
+pub use crate::trans::sample::*;
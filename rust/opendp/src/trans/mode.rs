@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::core::Function;
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+
+/// A [`Function`] that computes the most frequent value in a dataset. Ties are broken by first appearance.
+///
+/// This is a non-private precursor, and so is deliberately not a [`Transformation`](crate::core::Transformation):
+/// adding or removing a single record may change the mode to any other category, so there is no useful stability bound.
+/// To release a mode privately, score the categories (for instance with [`make_count_by_categories`](crate::trans::make_count_by_categories))
+/// and select among them with a private selection mechanism such as the exponential mechanism.
+pub fn make_mode<T>() -> Fallible<Function<VectorDomain<AllDomain<T>>, AllDomain<T>>>
+    where T: 'static + Eq + Hash + Clone {
+    Ok(Function::new_fallible(|arg: &Vec<T>| {
+        let mut counts = HashMap::new();
+        arg.iter().for_each(|v| *counts.entry(v).or_insert(0_usize) += 1);
+        // iterate over the data, not the map, so that ties are broken deterministically
+        let mut mode: Option<(&T, usize)> = None;
+        for v in arg {
+            let count = counts[v];
+            if mode.map(|(_, best)| count > best).unwrap_or(true) {
+                mode = Some((v, count))
+            }
+        }
+        mode.map(|(v, _)| v.clone())
+            .ok_or_else(|| err!(FailedFunction, "the mode of an empty dataset is undefined"))
+    }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_mode() -> Fallible<()> {
+        let function = make_mode()?;
+        assert_eq!(function.eval(&vec!['a', 'b', 'a', 'c', 'a'])?, 'a');
+        assert_eq!(function.eval(&vec!['b', 'a', 'a', 'b'])?, 'b');
+        assert!(function.eval(&vec![]).is_err());
+        Ok(())
+    }
+}
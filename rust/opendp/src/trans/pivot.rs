@@ -0,0 +1,58 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::core::{Function, StabilityRelation, Transformation};
+use crate::dist::{HammingDistance, SymmetricDistance};
+use crate::dom::{AllDomain, PairDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+
+/// A [`Transformation`] that pivots a vector of (key, value) records into a wide record, with one slot per key.
+/// Slots are ordered as in `keys`, and keys that are missing from the data take the value `default`.
+/// Should a key be repeated, the last record wins. Records with keys not in `keys` are dropped.
+///
+/// Adding or removing a record changes at most one slot of the output, so the stability constant is 1.
+pub fn make_pivot<K, V>(
+    keys: Vec<K>, default: V
+) -> Fallible<Transformation<VectorDomain<PairDomain<AllDomain<K>, AllDomain<V>>>, SizedDomain<VectorDomain<AllDomain<V>>>, SymmetricDistance, HammingDistance>>
+    where K: 'static + Eq + Hash + Clone,
+          V: 'static + Clone {
+    let mut uniques = HashSet::new();
+    if keys.iter().any(move |k| !uniques.insert(k)) {
+        return fallible!(MakeTransformation, "keys must be distinct")
+    }
+    Ok(Transformation::new(
+        VectorDomain::new(PairDomain::new(AllDomain::new(), AllDomain::new())),
+        SizedDomain::new(VectorDomain::new_all(), keys.len()),
+        Function::new(move |data: &Vec<(K, V)>| {
+            let slots = data.iter()
+                .map(|(k, v)| (k, v))
+                .collect::<HashMap<&K, &V>>();
+            keys.iter()
+                .map(|k| slots.get(k).map(|&v| v.clone()).unwrap_or_else(|| default.clone()))
+                .collect()
+        }),
+        SymmetricDistance,
+        HammingDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_pivot() -> Fallible<()> {
+        let transformation = make_pivot(vec!['a', 'b', 'c'], 0)?;
+        let ret = transformation.function.eval(&vec![('a', 1), ('c', 3)])?;
+        assert_eq!(ret, vec![1, 0, 3]);
+        assert!(transformation.stability_relation.eval(&1, &1)?);
+        assert!(!transformation.stability_relation.eval(&2, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_pivot_duplicate_keys() {
+        assert!(make_pivot(vec!['a', 'a'], 0).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::Bound;
+
+use num::Float;
+
+use crate::core::{Function, StabilityRelation, Transformation};
+use crate::dist::{AbsoluteDistance, SymmetricDistance};
+use crate::dom::{AllDomain, IntervalDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::traits::DistanceConstant;
+
+/// A [`Transformation`] that computes the product of a dataset of known size `length`, with elements in `[lower, upper]`.
+///
+/// Bounds must be non-negative. Then any partial product of `length - 1` elements is at most `upper^(length - 1)`,
+/// and swapping a single element scales the product by at most `upper / lower`,
+/// so the product moves by at most `upper^(length - 1) * (upper - lower)`.
+pub fn make_bounded_product<T>(
+    lower: T, upper: T, length: usize
+) -> Fallible<Transformation<SizedDomain<VectorDomain<IntervalDomain<T>>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
+    where T: DistanceConstant + Float {
+    if lower.is_sign_negative() { return fallible!(MakeTransformation, "lower must be non-negative") }
+    if length == 0 { return fallible!(MakeTransformation, "length must be positive") }
+    let _length = num_cast!(length; i32)?;
+    let sensitivity = upper.powi(_length - 1) * (upper - lower);
+    if !sensitivity.is_finite() { return fallible!(MakeTransformation, "sensitivity is not finite") }
+
+    Ok(Transformation::new(
+        SizedDomain::new(VectorDomain::new(IntervalDomain::new(
+            Bound::Included(lower), Bound::Included(upper))?), length),
+        AllDomain::new(),
+        Function::new(|arg: &Vec<T>| arg.iter().fold(T::one(), |acc, v| acc * *v)),
+        SymmetricDistance,
+        AbsoluteDistance::default(),
+        // d_out >= d_in * upper^(n - 1) * (upper - lower) / 2
+        StabilityRelation::new_from_constant(sensitivity / num_cast!(2; T)?)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_bounded_product() -> Fallible<()> {
+        let transformation = make_bounded_product(1., 10., 3)?;
+        let ret = transformation.function.eval(&vec![2., 3., 4.])?;
+        assert_eq!(ret, 24.);
+
+        // replacing one record is a symmetric distance of 2, and moves the product by at most 10^2 * (10 - 1)
+        assert!(transformation.stability_relation.eval(&2, &900.)?);
+        assert!(!transformation.stability_relation.eval(&2, &899.)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_bounded_product_negative_lower() {
+        assert!(make_bounded_product(-1., 10., 3).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::core::{Function, StabilityRelation, Transformation};
+use crate::dist::SymmetricDistance;
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::SampleGaussian;
+
+/// Sample a `output_dim` by `input_dim` Johnson-Lindenstrauss projection, with independent Gaussian(0, 1 / output_dim) entries.
+/// The matrix must be sampled independently of the data, and may then be released.
+pub fn sample_projection_matrix(input_dim: usize, output_dim: usize) -> Fallible<Vec<Vec<f64>>> {
+    if input_dim == 0 || output_dim == 0 { return fallible!(MakeTransformation, "dimensions must be positive") }
+    let scale = (output_dim as f64).sqrt().recip();
+    (0..output_dim)
+        .map(|_| (0..input_dim).map(|_| f64::sample_gaussian(0., scale, false)).collect())
+        .collect()
+}
+
+/// A [`Transformation`] that multiplies each record by a public `projection` matrix, given as a vector of rows.
+/// Records are projected independently, so the transformation is 1-stable in the symmetric distance.
+/// See [`sample_projection_matrix`] to reduce dimensionality while approximately preserving norms.
+pub fn make_random_projection(
+    projection: Vec<Vec<f64>>
+) -> Fallible<Transformation<VectorDomain<VectorDomain<AllDomain<f64>>>, VectorDomain<VectorDomain<AllDomain<f64>>>, SymmetricDistance, SymmetricDistance>> {
+    let input_dim = projection.first().map(|row| row.len()).unwrap_or(0);
+    if input_dim == 0 { return fallible!(MakeTransformation, "the projection must have at least one row and column") }
+    if projection.iter().any(|row| row.len() != input_dim || row.iter().any(|v| !v.is_finite())) {
+        return fallible!(MakeTransformation, "each row of the projection must have {} finite entries", input_dim)
+    }
+    Ok(Transformation::new(
+        VectorDomain::new(VectorDomain::new_all()),
+        VectorDomain::new(VectorDomain::new_all()),
+        Function::new_fallible(move |data: &Vec<Vec<f64>>| data.iter()
+            .map(|record| {
+                if record.len() != input_dim { return fallible!(FailedFunction, "each record must have {} features", input_dim) }
+                Ok(projection.iter()
+                    .map(|row| row.iter().zip(record.iter()).map(|(l, r)| l * r).sum())
+                    .collect())
+            })
+            .collect()),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_random_projection() -> Fallible<()> {
+        let transformation = make_random_projection(vec![vec![1., 0., 1.], vec![0., 2., 0.]])?;
+        assert_eq!(transformation.function.eval(&vec![vec![1., 2., 3.]])?, vec![vec![4., 4.]]);
+        assert!(transformation.function.eval(&vec![vec![1.]]).is_err());
+        assert!(transformation.stability_relation.eval(&1, &1)?);
+        assert!(make_random_projection(vec![vec![1.], vec![1., 2.]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_projection_matrix() -> Fallible<()> {
+        let projection = sample_projection_matrix(100, 200)?;
+        let record = vec![1.; 100];
+        let projected = make_random_projection(projection)?.function.eval(&vec![record])?;
+        // norms are approximately preserved
+        let norm = projected[0].iter().map(|v| v * v).sum::<f64>().sqrt();
+        assert!((norm - 10.).abs() < 3., "norm was {}", norm);
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::cmp::Ordering;
+
+use num::Float;
+
+use crate::core::{Function, StabilityRelation, Transformation};
+use crate::dist::{LInfDistance, SymmetricDistance};
+use crate::dom::{AllDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::traits::DistanceConstant;
+
+/// A [`Transformation`] that scores each of the `candidates` by how close it is to the `alpha`-quantile of a dataset.
+///
+/// The score of candidate `c` is `-|#{x < c} - alpha * n|`, so the best candidates have a score of zero.
+/// `candidates` must be strictly increasing. Records that are not comparable to the candidates, like nan, are never below a candidate.
+/// Adding or removing a record changes `#{x < c}` by at most 1 and `alpha * n` by `alpha`, so every score changes
+/// by at most `max(alpha, 1 - alpha)`. The scores are intended to be released with the exponential mechanism or report noisy max.
+pub fn make_quantile_score_candidates<TI, Q>(
+    candidates: Vec<TI>, alpha: Q
+) -> Fallible<Transformation<VectorDomain<AllDomain<TI>>, SizedDomain<VectorDomain<AllDomain<Q>>>, SymmetricDistance, LInfDistance<Q>>>
+    where TI: 'static + Clone + PartialOrd,
+          Q: DistanceConstant + Float {
+    if candidates.is_empty() { return fallible!(MakeTransformation, "there must be at least one candidate") }
+    if candidates.windows(2).any(|w| w[0].partial_cmp(&w[1]) != Some(Ordering::Less)) {
+        return fallible!(MakeTransformation, "candidates must be strictly increasing")
+    }
+    if !(alpha >= Q::zero() && alpha <= Q::one()) { return fallible!(MakeTransformation, "alpha must be within [0, 1]") }
+    let num_candidates = candidates.len();
+
+    Ok(Transformation::new(
+        VectorDomain::new_all(),
+        SizedDomain::new(VectorDomain::new_all(), num_candidates),
+        Function::new_fallible(move |arg: &Vec<TI>| {
+            // counts[i] is the number of records below candidate i, but not below candidate i - 1
+            let mut counts = vec![0usize; num_candidates + 1];
+            arg.iter().for_each(|x|
+                counts[candidates.partition_point(|c| x.partial_cmp(c) != Some(Ordering::Less))] += 1);
+            let target = alpha * num_cast!(arg.len(); Q)?;
+            let mut num_below = 0;
+            counts.iter().take(num_candidates)
+                .map(|count| {
+                    num_below += count;
+                    Ok(-(num_cast!(num_below; Q)? - target).abs())
+                })
+                .collect()
+        }),
+        SymmetricDistance,
+        LInfDistance::default(),
+        StabilityRelation::new_from_constant(alpha.max(Q::one() - alpha))))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::make_chain_mt;
+    use crate::meas::make_base_exponential_candidates;
+
+    #[test]
+    fn test_make_quantile_score_candidates() -> Fallible<()> {
+        let transformation = make_quantile_score_candidates(vec![0, 25, 50, 75, 100], 0.5)?;
+        let data = (0..100).collect::<Vec<i32>>();
+        assert_eq!(transformation.function.eval(&data)?, vec![-50., -25., 0., -25., -50.]);
+        assert!(transformation.stability_relation.eval(&1, &0.5)?);
+        assert!(!transformation.stability_relation.eval(&1, &0.49)?);
+
+        let quartile = make_quantile_score_candidates(vec![0., 25., 50.], 0.25)?;
+        assert!(quartile.stability_relation.eval(&2, &1.5)?);
+        assert!(!quartile.stability_relation.eval(&2, &1.4)?);
+        // nan is never below a candidate
+        assert_eq!(quartile.function.eval(&vec![f64::NAN, 10., 30.])?, vec![-0.75, -0.25, -1.25]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_quantile_score_candidates_invalid() {
+        assert!(make_quantile_score_candidates::<i32, f64>(vec![], 0.5).is_err());
+        assert!(make_quantile_score_candidates(vec![1, 1], 0.5).is_err());
+        assert!(make_quantile_score_candidates(vec![2, 1], 0.5).is_err());
+        assert!(make_quantile_score_candidates(vec![1, 2], 1.5).is_err());
+    }
+
+    #[test]
+    fn test_chain_quantile_exponential() -> Fallible<()> {
+        let candidates = vec![0, 25, 50, 75, 100];
+        let scores = make_quantile_score_candidates(candidates.clone(), 0.5)?;
+        let measurement = make_chain_mt(&make_base_exponential_candidates(candidates, 0.01)?, &scores, None)?;
+        assert_eq!(measurement.function.eval(&(0..100).collect())?, 50);
+        assert!(measurement.privacy_relation.eval(&1, &100.)?);
+        assert!(!measurement.privacy_relation.eval(&1, &99.)?);
+        Ok(())
+    }
+}
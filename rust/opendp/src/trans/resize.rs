@@ -0,0 +1,79 @@
+use crate::core::{Domain, Function, StabilityRelation, Transformation};
+use crate::dist::SymmetricDistance;
+use crate::dom::{SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::shuffle;
+
+/// A [`Transformation`] that resizes a dataset of unknown size to exactly `length` records.
+/// Maps a Vec<T> -> Vec<T>, where the output has a public length.
+///
+/// Records are shuffled, and then the dataset is truncated to `length` records or padded with copies of `constant`.
+/// Adding or removing a record either replaces one record of the output, or replaces a padding constant with a record,
+/// so the output changes by at most 2 in the symmetric distance. For example, a replacement on sized data
+/// is a symmetric distance of 2, which is the unit that sized aggregates like [`super::make_bounded_mean`] are calibrated to.
+pub fn make_resize_constant<DA>(
+    atom_domain: DA, constant: DA::Carrier, length: usize
+) -> Fallible<Transformation<VectorDomain<DA>, SizedDomain<VectorDomain<DA>>, SymmetricDistance, SymmetricDistance>>
+    where DA: 'static + Domain,
+          DA::Carrier: 'static + Clone {
+    if length == 0 { return fallible!(MakeTransformation, "length must be positive") }
+    if !atom_domain.member(&constant) { return fallible!(MakeTransformation, "constant must be a member of the atom domain") }
+
+    Ok(Transformation::new(
+        VectorDomain::new(atom_domain.clone()),
+        SizedDomain::new(VectorDomain::new(atom_domain), length),
+        Function::new_fallible(move |arg: &Vec<DA::Carrier>| {
+            let mut data = arg.clone();
+            shuffle(&mut data)?;
+            data.resize(length, constant.clone());
+            Ok(data)
+        }),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(2)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::Bound;
+
+    use super::*;
+    use crate::chain::make_chain_tt;
+    use crate::dom::{AllDomain, IntervalDomain};
+    use crate::trans::make_bounded_mean;
+
+    #[test]
+    fn test_make_resize_constant() -> Fallible<()> {
+        let transformation = make_resize_constant(AllDomain::new(), -1, 4)?;
+        assert_eq!(transformation.function.eval(&vec![1, 1])?, vec![1, 1, -1, -1]);
+        let truncated = transformation.invoke(&vec![1, 2, 3, 4, 5, 6])?;
+        assert_eq!(truncated.len(), 4);
+        assert!(truncated.iter().all(|v| (1..=6).contains(v)));
+
+        assert!(transformation.stability_relation.eval(&1, &2)?);
+        assert!(!transformation.stability_relation.eval(&1, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_resize_constant_invalid() -> Fallible<()> {
+        let domain = IntervalDomain::new(Bound::Included(0.), Bound::Included(10.))?;
+        assert!(make_resize_constant(domain.clone(), 11., 4).is_err());
+        assert!(make_resize_constant(domain, 5., 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain_resize_mean() -> Fallible<()> {
+        let domain = IntervalDomain::new(Bound::Included(0.), Bound::Included(10.))?;
+        let resize = make_resize_constant(domain, 5., 4)?;
+        let mean = make_bounded_mean(0., 10., 4)?;
+        let chain = make_chain_tt(&mean, &resize, None)?;
+        assert_eq!(chain.function.eval(&vec![1., 3.])?, 3.5);
+        // one added record is a replacement in the resized data, which moves the mean by 10 / 4
+        assert!(chain.stability_relation.eval(&1, &2.5)?);
+        assert!(!chain.stability_relation.eval(&1, &2.4)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::Bound;
+
+use num::Float;
+
+use crate::core::{DatasetMetric, Transformation};
+use crate::dom::{IntervalDomain, VectorDomain};
+use crate::error::*;
+use crate::trans::make_row_by_row;
+
+fn round_to_grid<T: Float>(step: T, v: T) -> T {
+    (v / step).round() * step
+}
+
+/// A [`Transformation`] that rounds each element to the nearest multiple of a public `step`.
+/// Maps a Vec<T> -> Vec<T>, where both input and output elements are bounded.
+/// Rounding is deterministic (ties round away from zero), so neighboring datasets remain neighbors.
+pub fn make_round_to_grid<M, T>(
+    lower: T, upper: T, step: T
+) -> Fallible<Transformation<VectorDomain<IntervalDomain<T>>, VectorDomain<IntervalDomain<T>>, M, M>>
+    where M: DatasetMetric,
+          T: 'static + Float {
+    if lower.is_nan() { return fallible!(MakeTransformation, "lower may not be nan") }
+    if upper.is_nan() { return fallible!(MakeTransformation, "upper may not be nan") }
+    if !step.is_finite() || step <= T::zero() {
+        return fallible!(MakeTransformation, "step must be positive and finite")
+    }
+
+    make_row_by_row(
+        IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?,
+        // rounding is monotonic, so the rounded bounds bound the rounded data
+        IntervalDomain::new(
+            Bound::Included(round_to_grid(step, lower)),
+            Bound::Included(round_to_grid(step, upper)))?,
+        move |v| round_to_grid(step, *v))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dist::SymmetricDistance;
+
+    #[test]
+    fn test_make_round_to_grid() -> Fallible<()> {
+        let transformation = make_round_to_grid::<SymmetricDistance, f64>(0., 2., 0.5)?;
+        let ret = transformation.function.eval(&vec![1.2, 1.7])?;
+        assert_eq!(ret, vec![1.0, 1.5]);
+        assert!(transformation.stability_relation.eval(&1, &1)?);
+        assert!(!transformation.stability_relation.eval(&2, &1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_round_to_grid_invalid_step() {
+        assert!(make_round_to_grid::<SymmetricDistance, f64>(0., 2., 0.).is_err());
+        assert!(make_round_to_grid::<SymmetricDistance, f64>(0., 2., -1.).is_err());
+        assert!(make_round_to_grid::<SymmetricDistance, f64>(0., 2., f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::core::{Domain, Function, StabilityRelation, Transformation};
+use crate::dist::SymmetricDistance;
+use crate::dom::{SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::samplers::{SampleBernoulli, shuffle};
+
+/// A [`Transformation`] that keeps each record independently with probability `prob`.
+/// Maps a Vec<T> -> Vec<T>
+///
+/// In the worst case the differing records are all kept, so the transformation is 1-stable.
+/// Privacy amplification by subsampling is accounted for by [`crate::chain::make_bernoulli_amplification`].
+pub fn make_sample_bernoulli<DA>(
+    atom_domain: DA, prob: f64
+) -> Fallible<Transformation<VectorDomain<DA>, VectorDomain<DA>, SymmetricDistance, SymmetricDistance>>
+    where DA: 'static + Domain,
+          DA::Carrier: 'static + Clone {
+    if !(prob > 0. && prob <= 1.) { return fallible!(MakeTransformation, "prob must be within (0, 1]") }
+
+    Ok(Transformation::new(
+        VectorDomain::new(atom_domain.clone()),
+        VectorDomain::new(atom_domain),
+        Function::new_fallible(move |arg: &Vec<DA::Carrier>| {
+            let mut sample = Vec::new();
+            for v in arg {
+                if bool::sample_bernoulli(prob, false)? { sample.push(v.clone()) }
+            }
+            Ok(sample)
+        }),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+/// A [`Transformation`] that draws a uniformly random subset of `m` records from a dataset of `n` records.
+/// Maps a Vec<T> -> Vec<T>, where both the input and output have a public length.
+///
+/// Neighboring sized datasets differ by replacing records, and a replaced record either appears in the sample or not,
+/// so the transformation is 1-stable. Privacy amplification by sampling without replacement is accounted for
+/// by [`crate::chain::make_without_replacement_amplification`].
+pub fn make_sample_without_replacement<DA>(
+    atom_domain: DA, n: usize, m: usize
+) -> Fallible<Transformation<SizedDomain<VectorDomain<DA>>, SizedDomain<VectorDomain<DA>>, SymmetricDistance, SymmetricDistance>>
+    where DA: 'static + Domain,
+          DA::Carrier: 'static + Clone {
+    if m == 0 || m > n { return fallible!(MakeTransformation, "m must be within [1, n]") }
+
+    Ok(Transformation::new(
+        SizedDomain::new(VectorDomain::new(atom_domain.clone()), n),
+        SizedDomain::new(VectorDomain::new(atom_domain), m),
+        Function::new_fallible(move |arg: &Vec<DA::Carrier>| {
+            if arg.len() != n { return fallible!(FailedFunction, "expected {} records", n) }
+            let mut sample = arg.clone();
+            shuffle(&mut sample)?;
+            sample.truncate(m);
+            Ok(sample)
+        }),
+        SymmetricDistance,
+        SymmetricDistance,
+        StabilityRelation::new_from_constant(1_u32)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::AllDomain;
+
+    #[test]
+    fn test_make_sample_bernoulli() -> Fallible<()> {
+        let data = (0..1000).collect::<Vec<i32>>();
+        let transformation = make_sample_bernoulli(AllDomain::new(), 0.5)?;
+        let sample = transformation.function.eval(&data)?;
+        assert!(sample.len() > 300 && sample.len() < 700, "sampled {} records", sample.len());
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(make_sample_bernoulli(AllDomain::new(), 1.)?.function.eval(&data)?, data);
+
+        assert!(transformation.stability_relation.eval(&1, &1)?);
+        assert!(make_sample_bernoulli::<AllDomain<i32>>(AllDomain::new(), 0.).is_err());
+        assert!(make_sample_bernoulli::<AllDomain<i32>>(AllDomain::new(), f64::NAN).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_sample_without_replacement() -> Fallible<()> {
+        let data = (0..10).collect::<Vec<i32>>();
+        let transformation = make_sample_without_replacement(AllDomain::new(), 10, 4)?;
+        let mut sample = transformation.invoke(&data)?;
+        sample.sort_unstable();
+        sample.dedup();
+        assert_eq!(sample.len(), 4);
+        assert!(transformation.invoke(&data[..9].to_vec()).is_err());
+
+        assert!(transformation.stability_relation.eval(&2, &2)?);
+        assert!(make_sample_without_replacement::<AllDomain<i32>>(AllDomain::new(), 10, 11).is_err());
+        assert!(make_sample_without_replacement::<AllDomain<i32>>(AllDomain::new(), 10, 0).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::Bound;
+
+use num::Float;
+
+use crate::core::{Function, StabilityRelation, Transformation};
+use crate::dist::LpDistance;
+use crate::dom::{AllDomain, IntervalDomain, VectorDomain};
+use crate::error::*;
+use crate::traits::DistanceConstant;
+
+/// A [`Transformation`] that applies the logistic sigmoid `1 / (1 + exp(-x))` to each element.
+/// Maps a Vec<T> -> Vec<T>, where the output is bounded within `[0, 1]`.
+///
+/// The derivative of the sigmoid is at most 1/4, so each element moves at most a quarter as far as its input,
+/// and the Lp distance between outputs is at most 1/4 of the Lp distance between inputs.
+pub fn make_sigmoid<T, const P: usize>(
+) -> Fallible<Transformation<VectorDomain<AllDomain<T>>, VectorDomain<IntervalDomain<T>>, LpDistance<T, P>, LpDistance<T, P>>>
+    where T: 'static + DistanceConstant + Float {
+    Ok(Transformation::new(
+        VectorDomain::new_all(),
+        VectorDomain::new(IntervalDomain::new(Bound::Included(T::zero()), Bound::Included(T::one()))?),
+        Function::new(|arg: &Vec<T>| arg.iter()
+            .map(|v| (T::one() + (-*v).exp()).recip())
+            .collect()),
+        LpDistance::default(),
+        LpDistance::default(),
+        StabilityRelation::new_from_constant(num_cast!(0.25; T)?)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_sigmoid() -> Fallible<()> {
+        let transformation = make_sigmoid::<f64, 1>()?;
+        let ret = transformation.function.eval(&vec![0., 50., -50.])?;
+        assert_eq!(ret[0], 0.5);
+        assert!((ret[1] - 1.).abs() < 1e-12);
+        assert!(ret[2].abs() < 1e-12);
+
+        assert!(transformation.stability_relation.eval(&1., &0.25)?);
+        assert!(!transformation.stability_relation.eval(&1., &0.24)?);
+        Ok(())
+    }
+}
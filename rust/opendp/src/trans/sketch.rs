@@ -0,0 +1,96 @@
+use std::hash::Hash;
+use std::ops::AddAssign;
+
+use num::{One, Zero};
+
+use crate::core::{Function, SensitivityMetric, StabilityRelation, Transformation};
+use crate::dist::SymmetricDistance;
+use crate::dom::{AllDomain, VectorDomain};
+use crate::error::*;
+use crate::traits::{stable_hash, DistanceConstant};
+
+// the column of `key` in row `row` of a sketch with `width` columns
+fn count_min_column<K: Hash>(key: &K, row: usize, width: usize) -> usize {
+    (stable_hash(row as u64, key) % width as u64) as usize
+}
+
+/// A [`Transformation`] that builds a Count-Min sketch with `depth` rows of `width` counters.
+///
+/// Each record increments one counter in each row, chosen by a hash function per row.
+/// The sketch is released in row-major order, so that vector noise mechanisms can be chained after it,
+/// and can be queried with [`count_min_query`].
+/// Each record changes one counter per row, so the L1 stability constant is `depth`.
+pub fn make_count_min_sketch<MO, TI, TO>(
+    width: usize, depth: usize
+) -> Fallible<Transformation<VectorDomain<AllDomain<TI>>, VectorDomain<AllDomain<TO>>, SymmetricDistance, MO>>
+    where MO: SensitivityMetric,
+          MO::Distance: DistanceConstant,
+          TI: 'static + Hash,
+          TO: Zero + One + AddAssign + Clone {
+    if width == 0 || depth == 0 { return fallible!(MakeTransformation, "width and depth must be positive") }
+    Ok(Transformation::new(
+        VectorDomain::new_all(),
+        VectorDomain::new_all(),
+        Function::new(move |data: &Vec<TI>| {
+            let mut sketch = vec![TO::zero(); width * depth];
+            data.iter().for_each(|key| (0..depth).for_each(|row|
+                sketch[row * width + count_min_column(key, row, width)] += TO::one()));
+            sketch
+        }),
+        SymmetricDistance,
+        MO::default(),
+        StabilityRelation::new_from_constant(num_cast!(depth; MO::Distance)?)))
+}
+
+/// Estimate the count of `key` from a (possibly noisy) Count-Min sketch with `width` columns,
+/// as the smallest of its counters.
+pub fn count_min_query<K: Hash, T: PartialOrd + Clone>(sketch: &[T], width: usize, key: &K) -> Fallible<T> {
+    let depth = sketch.len().checked_div(width).unwrap_or(0);
+    if depth == 0 || depth * width != sketch.len() {
+        return fallible!(FailedFunction, "the sketch must have a positive number of rows of {} counters", width)
+    }
+    (0..depth)
+        .map(|row| sketch[row * width + count_min_column(key, row, width)].clone())
+        .fold(None, |min: Option<T>, v| match min {
+            Some(m) if m <= v => Some(m),
+            _ => Some(v)
+        })
+        .ok_or_else(|| err!(FailedFunction, "the sketch is empty"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dist::L1Distance;
+    use crate::meas::make_base_laplace;
+
+    #[test]
+    fn test_make_count_min_sketch() -> Fallible<()> {
+        let transformation = make_count_min_sketch::<L1Distance<i32>, _, i32>(16, 3)?;
+        let data = vec!["a", "a", "b", "c", "a"];
+        let sketch = transformation.function.eval(&data)?;
+        assert_eq!(sketch.len(), 48);
+        assert_eq!(sketch.iter().sum::<i32>(), 15);
+        // collisions may only inflate counts
+        assert!(count_min_query(&sketch, 16, &"a")? >= 3);
+        assert!(count_min_query(&sketch, 16, &"b")? >= 1);
+
+        assert!(transformation.stability_relation.eval(&1, &3)?);
+        assert!(!transformation.stability_relation.eval(&1, &2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain_count_min_sketch_laplace() -> Fallible<()> {
+        let sketch = make_count_min_sketch::<L1Distance<f64>, _, f64>(64, 4)?;
+        let laplace = make_base_laplace::<VectorDomain<_>>(1e-6)?;
+        let measurement = (sketch >> laplace)?;
+        let noisy = measurement.function.eval(&vec![1, 2, 2, 3, 3, 3])?;
+        assert!((count_min_query(&noisy, 64, &3)? - 3.).abs() < 1e-3);
+        // the depth multiplies the privacy loss
+        assert!(measurement.privacy_relation.eval(&1, &4e6)?);
+        assert!(!measurement.privacy_relation.eval(&1, &3.9e6)?);
+        Ok(())
+    }
+}
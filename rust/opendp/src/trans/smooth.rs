@@ -0,0 +1,59 @@
+use std::collections::Bound;
+
+use num::Float;
+
+use crate::core::{Function, StabilityRelation, Transformation};
+use crate::dist::{HammingDistance, L1Distance};
+use crate::dom::{AllDomain, IntervalDomain, SizedDomain, VectorDomain};
+use crate::error::*;
+use crate::traits::DistanceConstant;
+
+/// A [`Transformation`] that computes a centered moving average of odd `width` over a time series of known `length`.
+/// The series is padded with zeros, and every window is divided by `width`, so the output has the same length as the input.
+///
+/// Changing a single element influences at most `width` outputs, each by at most `(upper - lower) / width`,
+/// so the L1 sensitivity is `upper - lower`.
+pub fn make_moving_average<T>(
+    lower: T, upper: T, length: usize, width: usize
+) -> Fallible<Transformation<SizedDomain<VectorDomain<IntervalDomain<T>>>, VectorDomain<AllDomain<T>>, HammingDistance, L1Distance<T>>>
+    where T: DistanceConstant + Float {
+    if width % 2 != 1 { return fallible!(MakeTransformation, "width must be odd") }
+    let _width = num_cast!(width; T)?;
+    let half = width / 2;
+
+    Ok(Transformation::new(
+        SizedDomain::new(VectorDomain::new(IntervalDomain::new(
+            Bound::Included(lower), Bound::Included(upper))?), length),
+        VectorDomain::new_all(),
+        Function::new(move |arg: &Vec<T>| (0..arg.len())
+            .map(|i| arg[i.saturating_sub(half)..(i + half + 1).min(arg.len())].iter()
+                .fold(T::zero(), |sum, v| sum + *v) / _width)
+            .collect()),
+        HammingDistance,
+        L1Distance::default(),
+        StabilityRelation::new_from_constant(upper - lower)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_moving_average() -> Fallible<()> {
+        let transformation = make_moving_average(0., 10., 4, 3)?;
+        let ret = transformation.function.eval(&vec![3., 3., 3., 3.])?;
+        assert_eq!(ret.len(), 4);
+        assert!(ret[1..3].iter().all(|v| (v - 3.).abs() < 1e-12));
+        assert!((ret[0] - 2.).abs() < 1e-12);
+
+        assert!(transformation.stability_relation.eval(&1, &10.)?);
+        assert!(!transformation.stability_relation.eval(&1, &9.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_moving_average_even_width() {
+        assert!(make_moving_average(0., 10., 4, 2).is_err());
+    }
+}
@@ -1,11 +1,15 @@
 use std::cmp::Ordering;
-use std::collections::Bound;
+use std::collections::{Bound, HashMap};
+use std::convert::TryFrom;
+use std::hash::Hash;
 use std::iter::Sum;
 use std::ops::Sub;
 
+use num::Bounded;
+
 use crate::core::{Function, StabilityRelation, Transformation};
 use crate::dist::{SymmetricDistance, AbsoluteDistance};
-use crate::dom::{AllDomain, IntervalDomain, SizedDomain, VectorDomain};
+use crate::dom::{AllDomain, IntervalDomain, MapDomain, SizedDomain, VectorDomain};
 use crate::error::*;
 use crate::traits::{Abs, DistanceConstant};
 
@@ -13,6 +17,9 @@ fn max<T: PartialOrd>(a: T, b: T) -> Option<T> {
     a.partial_cmp(&b).map(|o| if let Ordering::Less = o {b} else {a})
 }
 
+/// A [`Transformation`] that sums a dataset of values in `[lower, upper]`.
+/// Adding or removing a record changes the sum by at most `max(|lower|, |upper|)`,
+/// so the sum is `d_in * max(|lower|, |upper|)`-stable in the symmetric distance.
 pub fn make_bounded_sum<T>(
     lower: T, upper: T
 ) -> Fallible<Transformation<VectorDomain<IntervalDomain<T>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
@@ -30,7 +37,41 @@ pub fn make_bounded_sum<T>(
             .ok_or_else(|| err!(InvalidDistance, "lower and upper must be comparable"))?)))
 }
 
+/// A [`Transformation`] that sums a dataset of integers in `[lower, upper]`, without overflow.
+/// Values are accumulated in an `i128`, and the total is saturated at the bounds of `T`.
+/// Saturation moves neighboring sums no further apart, so the sum is `d_in * max(|lower|, |upper|)`-stable,
+/// no matter how many records there are.
+/// `T` must be narrower than `i128`, so that the accumulator itself never saturates,
+/// and `max(|lower|, |upper|)` must be representable in `T`.
+pub fn make_bounded_sum_saturating<T>(
+    lower: T, upper: T
+) -> Fallible<Transformation<VectorDomain<IntervalDomain<T>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
+    where T: 'static + DistanceConstant + Sub<Output=T> + Bounded + Into<i128> + TryFrom<i128> {
+    let (min, max_value): (i128, i128) = (T::min_value().into(), T::max_value().into());
+    if max_value == i128::MAX { return fallible!(MakeTransformation, "T must be narrower than i128") }
+    // computed in i128, as the absolute value of T::MIN is not representable in T
+    let sensitivity = lower.clone().into().abs().max(upper.clone().into().abs());
+    let sensitivity = T::try_from(sensitivity)
+        .map_err(|_| err!(MakeTransformation, "max(|lower|, |upper|) must be representable"))?;
+
+    Ok(Transformation::new(
+        VectorDomain::new(IntervalDomain::new(
+            Bound::Included(lower), Bound::Included(upper))?),
+        AllDomain::new(),
+        Function::new_fallible(move |arg: &Vec<T>| {
+            let sum = arg.iter().fold(0i128, |sum, v| sum + v.clone().into());
+            T::try_from(sum.max(min).min(max_value))
+                .map_err(|_| err!(FailedFunction, "the saturated sum is not representable"))
+        }),
+        SymmetricDistance,
+        AbsoluteDistance::default(),
+        StabilityRelation::new_from_constant(sensitivity)))
+}
 
+/// A [`Transformation`] that sums a dataset of `length` values in `[lower, upper]`.
+/// Since the size is known, neighboring datasets differ by changed records, and each change is a symmetric distance of 2.
+/// Changing a record changes the sum by at most `upper - lower`, so the sum is `d_in * (upper - lower) / 2`-stable.
+/// A Hamming distance of `k` corresponds to a symmetric distance of `2 k`.
 pub fn make_bounded_sum_n<T>(
     lower: T, upper: T, length: usize
 ) -> Fallible<Transformation<SizedDomain<VectorDomain<IntervalDomain<T>>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
@@ -48,9 +89,32 @@ pub fn make_bounded_sum_n<T>(
         StabilityRelation::new_from_constant((upper - lower) / T::distance_cast(2)?)))
 }
 
+/// A [`Transformation`] that sums the values of a sparse vector, represented as a map from index to value.
+/// Each entry is a record, so adding or removing an entry changes the sum by at most `max(|lower|, |upper|)`.
+pub fn make_sparse_bounded_sum<K, T>(
+    lower: T, upper: T
+) -> Fallible<Transformation<MapDomain<AllDomain<K>, IntervalDomain<T>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
+    where K: Eq + Hash,
+          T: DistanceConstant + Sub<Output=T> + Abs,
+          for <'a> T: Sum<&'a T> {
+
+    Ok(Transformation::new(
+        MapDomain::new(AllDomain::new(), IntervalDomain::new(
+            Bound::Included(lower.clone()), Bound::Included(upper.clone()))?),
+        AllDomain::new(),
+        Function::new(|arg: &HashMap<K, T>| arg.values().sum()),
+        SymmetricDistance,
+        AbsoluteDistance::default(),
+        StabilityRelation::new_from_constant(max(lower.abs(), upper.abs())
+            .ok_or_else(|| err!(InvalidDistance, "lower and upper must be comparable"))?)))
+}
+
 
 #[cfg(test)]
 mod tests {
+    use crate::chain::make_chain_tt;
+    use crate::trans::make_clamp;
+
     use super::*;
 
     #[test]
@@ -71,6 +135,35 @@ mod tests {
         assert_eq!(ret, expected);
     }
 
+    #[test]
+    fn test_make_clamp_bounded_sum() -> Fallible<()> {
+        let clamp = make_clamp::<VectorDomain<AllDomain<i32>>, SymmetricDistance>(-2, 5)?;
+        let sum = make_bounded_sum::<i32>(-2, 5)?;
+        let chain = make_chain_tt(&sum, &clamp, None)?;
+        let ret = chain.function.eval(&vec![-10, 1, 3, 20])?;
+        assert_eq!(ret, 7);
+        // the stability constant is max(|lower|, |upper|) = 5
+        assert!(chain.stability_relation.eval(&2, &10)?);
+        assert!(!chain.stability_relation.eval(&2, &9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_bounded_sum_saturating() -> Fallible<()> {
+        let transformation = make_bounded_sum_saturating::<i8>(-100, 100)?;
+        assert_eq!(transformation.function.eval(&vec![100, 100, -50])?, 127);
+        assert_eq!(transformation.function.eval(&vec![-100, -100])?, -128);
+        // intermediate values may leave the range of the type
+        assert_eq!(transformation.function.eval(&vec![100, 100, -100, -100, 5])?, 5);
+        assert!(transformation.stability_relation.eval(&1, &100)?);
+        assert!(!transformation.stability_relation.eval(&1, &99)?);
+
+        // removing a record of -128 moves the sum by 128, which is not representable in an i8
+        assert!(make_bounded_sum_saturating::<i8>(-128, 100).is_err());
+        assert!(make_bounded_sum_saturating::<i128>(-100, 100).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_make_bounded_sum_n() {
         let transformation = make_bounded_sum_n::<i32>(0, 10, 5).unwrap_test();
@@ -79,4 +172,24 @@ mod tests {
         let expected = 15;
         assert_eq!(ret, expected);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_make_bounded_sum_n_relation() -> Fallible<()> {
+        let transformation = make_bounded_sum_n::<f64>(-1., 3., 5)?;
+        // one changed record is a symmetric distance of 2, and moves the sum by at most 4
+        assert!(transformation.stability_relation.eval(&2, &4.)?);
+        assert!(!transformation.stability_relation.eval(&2, &3.9)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_sparse_bounded_sum() -> Fallible<()> {
+        let transformation = make_sparse_bounded_sum::<usize, f64>(0., 10.)?;
+        let arg = vec![(0, 2.), (5, 3.)].into_iter().collect();
+        let ret = transformation.function.eval(&arg)?;
+        assert_eq!(ret, 5.);
+        assert!(transformation.stability_relation.eval(&1, &10.)?);
+        assert!(!transformation.stability_relation.eval(&1, &9.9)?);
+        Ok(())
+    }
+}
@@ -6,11 +6,14 @@ use num::{Float, One, Zero};
 
 use crate::core::{Function, StabilityRelation, Transformation};
 use crate::dist::{SymmetricDistance, AbsoluteDistance};
-use crate::dom::{AllDomain, IntervalDomain, SizedDomain, VectorDomain};
-use crate::error::Fallible;
+use crate::dom::{AllDomain, IntervalDomain, PairDomain, SizedDomain, VectorDomain};
+use crate::error::*;
 use crate::traits::DistanceConstant;
 
 
+/// A [`Transformation`] that computes the variance of a dataset of `length` values in `[lower, upper]`,
+/// with `ddof` delta degrees of freedom (0 for the population variance, 1 for the sample variance).
+/// Replacing one record changes the variance by at most `(upper - lower)^2 * length / (length + 1) / (length - ddof)`.
 pub fn make_bounded_variance<T>(
     lower: T, upper: T, length: usize, ddof: usize
 ) -> Fallible<Transformation<SizedDomain<VectorDomain<IntervalDomain<T>>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
@@ -39,8 +42,39 @@ pub fn make_bounded_variance<T>(
                 / _2)))
 }
 
-type CovarianceDomain<T> = SizedDomain<VectorDomain<IntervalDomain<(T, T)>>>;
+/// A [`Transformation`] that computes the standard deviation of a dataset of `length` values in `[lower, upper]`,
+/// with `ddof` delta degrees of freedom.
+/// The standard deviation is the norm of the centered data, scaled by `1 / sqrt(length - ddof)`, and centering
+/// is a projection, so replacing one record changes the standard deviation by at most `(upper - lower) / sqrt(length - ddof)`.
+pub fn make_bounded_standard_deviation<T>(
+    lower: T, upper: T, length: usize, ddof: usize
+) -> Fallible<Transformation<SizedDomain<VectorDomain<IntervalDomain<T>>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
+    where T: DistanceConstant + Float {
+    if ddof >= length { return fallible!(MakeTransformation, "ddof must be less than length") }
+    let _length = num_cast!(length; T)?;
+    let _ddof = num_cast!(ddof; T)?;
+    let _2 = num_cast!(2; T)?;
 
+    Ok(Transformation::new(
+        SizedDomain::new(VectorDomain::new(
+            IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?), length),
+        AllDomain::new(),
+        Function::new(move |arg: &Vec<T>| {
+            let mean = arg.iter().fold(T::zero(), |sum, v| sum + *v) / _length;
+            (arg.iter().fold(T::zero(), |sum, v| sum + (*v - mean).powi(2)) / (_length - _ddof)).sqrt()
+        }),
+        SymmetricDistance,
+        AbsoluteDistance::default(),
+        // a replacement is a symmetric distance of 2
+        StabilityRelation::new_from_constant((upper - lower) / (_length - _ddof).sqrt() / _2)))
+}
+
+type CovarianceDomain<T> = SizedDomain<VectorDomain<PairDomain<IntervalDomain<T>, IntervalDomain<T>>>>;
+
+/// A [`Transformation`] that computes the covariance of a dataset of `length` pairs,
+/// where the first coordinate is in `[lower.0, upper.0]` and the second in `[lower.1, upper.1]`.
+/// Each coordinate is bounded separately, so replacing one pair changes the covariance by at most
+/// `(upper.0 - lower.0) * (upper.1 - lower.1) * length / (length + 1) / (length - ddof)`.
 pub fn make_bounded_covariance<T>(
     lower: (T, T),
     upper: (T, T),
@@ -56,8 +90,9 @@ pub fn make_bounded_covariance<T>(
     let _2 = _1.clone() + &_1;
 
     Ok(Transformation::new(
-        SizedDomain::new(VectorDomain::new(
-            IntervalDomain::new(Bound::Included(lower.clone()), Bound::Included(upper.clone()))?), length),
+        SizedDomain::new(VectorDomain::new(PairDomain::new(
+            IntervalDomain::new(Bound::Included(lower.0.clone()), Bound::Included(upper.0.clone()))?,
+            IntervalDomain::new(Bound::Included(lower.1.clone()), Bound::Included(upper.1.clone()))?)), length),
         AllDomain::new(),
         Function::new(enclose!((_length, _ddof), move |arg: &Vec<(T, T)>| {
             let (sum_l, sum_r) = arg.iter().fold(
@@ -79,6 +114,25 @@ pub fn make_bounded_covariance<T>(
                 / _2)))
 }
 
+/// A [`Transformation`] that computes the sum of squared deviations from a public `mean`.
+/// Adding or removing a record changes the sum by at most `max((lower - mean)^2, (upper - mean)^2)`.
+pub fn make_sum_squared_deviations<T>(
+    lower: T, upper: T, mean: T
+) -> Fallible<Transformation<VectorDomain<IntervalDomain<T>>, AllDomain<T>, SymmetricDistance, AbsoluteDistance<T>>>
+    where T: DistanceConstant + Float {
+    if mean.is_nan() { return fallible!(MakeTransformation, "mean may not be nan") }
+    let sensitivity = (lower - mean).powi(2).max((upper - mean).powi(2));
+
+    Ok(Transformation::new(
+        VectorDomain::new(IntervalDomain::new(Bound::Included(lower), Bound::Included(upper))?),
+        AllDomain::new(),
+        Function::new(move |arg: &Vec<T>| arg.iter()
+            .fold(T::zero(), |sum, v| sum + (*v - mean).powi(2))),
+        SymmetricDistance,
+        AbsoluteDistance::default(),
+        StabilityRelation::new_from_constant(sensitivity)))
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -102,6 +156,26 @@ mod tests {
         assert!(transformation_pop.stability_relation.eval(&1, &(100. * 4. / 25.)).unwrap_test());
     }
 
+    #[test]
+    fn test_make_bounded_variance_length() -> Fallible<()> {
+        let transformation = make_bounded_variance(0., 10., 3, 0)?;
+        assert!(transformation.invoke(&vec![1., 2., 3.]).is_ok());
+        assert!(transformation.invoke(&vec![1., 2., 3., 4.]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_bounded_standard_deviation() -> Fallible<()> {
+        let transformation = make_bounded_standard_deviation(0., 10., 5, 1)?;
+        let ret = transformation.invoke(&vec![1., 2., 3., 4., 5.])?;
+        assert!((ret - 2.5f64.sqrt()).abs() < 1e-12);
+        // one replacement moves the standard deviation by at most 10 / sqrt(4)
+        assert!(transformation.stability_relation.eval(&2, &5.)?);
+        assert!(!transformation.stability_relation.eval(&2, &4.9)?);
+        assert!(make_bounded_standard_deviation(0., 10., 1, 1).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_make_bounded_covariance_hamming() {
         let arg = vec![(1., 3.), (2., 4.), (3., 5.), (4., 6.), (5., 7.)];
@@ -118,4 +192,24 @@ mod tests {
         assert_eq!(ret, expected);
         assert!(transformation_pop.stability_relation.eval(&1, &(100. * 4. / 25.)).unwrap_test());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_make_bounded_covariance_bounds() -> Fallible<()> {
+        let transformation = make_bounded_covariance((0., 2.), (10., 12.), 2, 0)?;
+        assert_eq!(transformation.invoke(&vec![(0., 2.), (10., 12.)])?, 25.);
+        // both coordinates are bounded, not just the pair in lexicographic order
+        assert!(transformation.invoke(&vec![(0., 100.), (10., 12.)]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_sum_squared_deviations() -> Fallible<()> {
+        let transformation = make_sum_squared_deviations(0., 10., 3.)?;
+        let ret = transformation.function.eval(&vec![2., 4.])?;
+        assert_eq!(ret, 2.);
+        // max((0 - 3)^2, (10 - 3)^2) = 49
+        assert!(transformation.stability_relation.eval(&1, &49.)?);
+        assert!(!transformation.stability_relation.eval(&1, &48.9)?);
+        Ok(())
+    }
+}